@@ -0,0 +1,434 @@
+// ===================================
+// 應用程式配置
+// 預設值 → 配置檔 (TOML/YAML) → 舊版環境變數 → CARE_VOICE__* 環境變數
+// ===================================
+
+use anyhow::{Context, Result};
+use config::{Config, Environment, File};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::audio_decoder::ChannelMode;
+use crate::decoding::DecodingConfig;
+use crate::gpu_memory_manager::GpuMemoryConfig;
+use crate::hallucination::HallucinationConfig;
+use crate::model_registry::ModelRegistryConfig;
+use crate::vad::VadConfig;
+use crate::vocabulary::VocabularyConfig;
+
+/// 未指定 --config 時嘗試載入的配置檔 (副檔名可為 .toml / .yaml / .yml)
+const DEFAULT_CONFIG_BASENAME: &str = "care-voice";
+/// 新式環境變數前綴，例如 CARE_VOICE__SERVER__PORT=8080
+const ENV_PREFIX: &str = "CARE_VOICE";
+const ENV_SEPARATOR: &str = "__";
+
+/// 服務整體配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppConfig {
+    pub server: ServerConfig,
+    pub whisper: WhisperConfig,
+    pub audio: AudioConfig,
+    pub gpu: GpuMemoryConfig,
+    pub jobs: JobsConfig,
+    pub vad: VadConfig,
+    pub decoding: DecodingConfig,
+    pub vocabulary: VocabularyConfig,
+    pub hallucination: HallucinationConfig,
+}
+
+/// HTTP 服務配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    /// 收到關閉信號後等待佇列任務完成的期限 (秒)
+    pub shutdown_timeout_secs: u64,
+    /// 上傳端點 (/upload、/jobs、/v1/audio/*) 的請求大小上限 (位元組)
+    pub max_upload_bytes: usize,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: "0.0.0.0".to_string(),
+            port: 3000,
+            shutdown_timeout_secs: 60,
+            max_upload_bytes: 100 * 1024 * 1024,
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_secs(self.shutdown_timeout_secs)
+    }
+}
+
+/// Whisper 模型池配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WhisperConfig {
+    /// 模型檔案目錄
+    pub model_path: String,
+    /// 是否使用 GPU 推理
+    pub use_gpu: bool,
+    /// 工作線程數，0 表示依 CPU 核心數自動決定 (最多 8)
+    pub workers: usize,
+    /// 任務佇列容量
+    pub queue_capacity: usize,
+    /// 阻塞式轉錄超時 (秒)，長音頻分段時套用於每個區塊
+    pub timeout_secs: u64,
+    /// 長音頻分段長度 (秒)，0 表示不分段
+    pub chunk_secs: u64,
+    /// 相鄰區塊的重疊長度 (秒)
+    pub chunk_overlap_secs: u64,
+    /// 以前一區塊文字作為下一區塊的提示詞；區塊須依序轉錄，停用時各區塊並行處理
+    pub chunk_carry_prompt: bool,
+    /// 段落信心分數低於此值時標記為需人工確認 (0.0 - 1.0)
+    pub low_confidence_threshold: f32,
+    /// 品質等級與模型檔案的對應、載入與回退設定
+    pub models: ModelRegistryConfig,
+}
+
+impl Default for WhisperConfig {
+    fn default() -> Self {
+        Self {
+            model_path: "./models".to_string(),
+            use_gpu: true,
+            workers: 0,
+            queue_capacity: 1000,
+            timeout_secs: 90,
+            chunk_secs: 30,
+            chunk_overlap_secs: 2,
+            chunk_carry_prompt: true,
+            low_confidence_threshold: 0.6,
+            models: ModelRegistryConfig::default(),
+        }
+    }
+}
+
+impl WhisperConfig {
+    /// 實際啟動的工作線程數
+    pub fn worker_count(&self) -> usize {
+        if self.workers == 0 {
+            num_cpus::get().min(8)
+        } else {
+            self.workers
+        }
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+}
+
+/// 音頻解碼配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AudioConfig {
+    /// 48kHz OPUS 解碼器池大小
+    pub opus_pool_size: usize,
+    /// 預期的 OPUS 位元率 (與前端 WebCodecs 配置一致)
+    pub opus_bit_rate: u32,
+    /// 將收到的音頻存檔以便除錯
+    pub debug_archive: bool,
+    /// 單一請求的音頻長度上限 (秒)
+    pub max_duration_secs: u64,
+    /// 多聲道音頻的處理方式: downmix / { select = N } / separate
+    pub channel_mode: ChannelMode,
+    /// 逐聲道轉錄時各聲道的說話者標籤 (依聲道順序，未列出的聲道標為 channel N)
+    pub channel_speakers: Vec<String>,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            opus_pool_size: 4,
+            opus_bit_rate: 96000,
+            debug_archive: false,
+            max_duration_secs: 60 * 60,
+            channel_mode: ChannelMode::Downmix,
+            channel_speakers: vec!["caregiver".to_string(), "client".to_string()],
+        }
+    }
+}
+
+/// 非同步任務配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JobsConfig {
+    /// 已完成任務的保留時間 (秒)
+    pub retention_secs: u64,
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        Self {
+            retention_secs: 60 * 60,
+        }
+    }
+}
+
+impl JobsConfig {
+    pub fn retention(&self) -> Duration {
+        Duration::from_secs(self.retention_secs)
+    }
+}
+
+impl AppConfig {
+    /// 載入配置：指定路徑時檔案必須存在，否則嘗試載入工作目錄下的 care-voice.{toml,yaml}
+    pub fn load(config_path: Option<&Path>) -> Result<Self> {
+        let file_source = match config_path {
+            Some(path) => File::from(path).required(true),
+            None => File::with_name(DEFAULT_CONFIG_BASENAME).required(false),
+        };
+
+        let legacy_env = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+
+        let config = Config::builder()
+            .add_source(file_source)
+            .add_source(
+                Environment::with_prefix(ENV_PREFIX)
+                    .separator(ENV_SEPARATOR)
+                    .try_parsing(true),
+            )
+            // 舊版環境變數 (docker-compose 與部署腳本仍在使用)
+            .set_override_option("whisper.model_path", legacy_env("MODEL_PATH"))?
+            .set_override_option("server.port", legacy_env("BACKEND_PORT"))?
+            .set_override_option(
+                "whisper.use_gpu",
+                legacy_env("WHISPER_USE_GPU").map(|value| !value.eq_ignore_ascii_case("false")),
+            )?
+            .set_override_option(
+                "audio.debug_archive",
+                std::env::var_os("CARE_VOICE_DEBUG_AUDIO").map(|_| true),
+            )?
+            .build()
+            .context("無法讀取配置")?;
+
+        let app_config: AppConfig = config.try_deserialize().context("配置格式錯誤")?;
+        app_config.validate()?;
+        Ok(app_config)
+    }
+
+    /// 驗證配置，一次列出所有錯誤
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+
+        if self.server.host.trim().is_empty() {
+            problems.push("server.host 不可為空".to_string());
+        }
+        if self.server.port == 0 {
+            problems.push("server.port 必須介於 1 與 65535 之間".to_string());
+        }
+        if self.server.shutdown_timeout_secs == 0 {
+            problems.push("server.shutdown_timeout_secs 必須大於 0".to_string());
+        }
+        if self.server.max_upload_bytes == 0 {
+            problems.push("server.max_upload_bytes 必須大於 0".to_string());
+        }
+        if self.whisper.model_path.trim().is_empty() {
+            problems.push("whisper.model_path 不可為空".to_string());
+        }
+        if self.whisper.workers > 64 {
+            problems.push(format!("whisper.workers 過大: {} (最多 64，0 表示自動)", self.whisper.workers));
+        }
+        if self.whisper.queue_capacity == 0 {
+            problems.push("whisper.queue_capacity 必須大於 0".to_string());
+        }
+        if self.whisper.timeout_secs == 0 {
+            problems.push("whisper.timeout_secs 必須大於 0".to_string());
+        }
+        if self.whisper.chunk_secs > 0 && self.whisper.chunk_overlap_secs * 2 >= self.whisper.chunk_secs {
+            problems.push(format!(
+                "whisper.chunk_overlap_secs ({}) 必須小於 chunk_secs ({}) 的一半",
+                self.whisper.chunk_overlap_secs, self.whisper.chunk_secs
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.whisper.low_confidence_threshold) {
+            problems.push(format!(
+                "whisper.low_confidence_threshold 必須介於 0.0 與 1.0 之間: {}",
+                self.whisper.low_confidence_threshold
+            ));
+        }
+        if !(1..=32).contains(&self.audio.opus_pool_size) {
+            problems.push(format!("audio.opus_pool_size 必須介於 1 與 32 之間: {}", self.audio.opus_pool_size));
+        }
+        if !(6000..=510000).contains(&self.audio.opus_bit_rate) {
+            problems.push(format!("audio.opus_bit_rate 必須介於 6000 與 510000 之間: {}", self.audio.opus_bit_rate));
+        }
+        if self.audio.max_duration_secs == 0 {
+            problems.push("audio.max_duration_secs 必須大於 0".to_string());
+        }
+        if let ChannelMode::Select(index) = self.audio.channel_mode {
+            if index >= 8 {
+                problems.push(format!("audio.channel_mode 選取的聲道過大: {} (最多 7)", index));
+            }
+        }
+        if self.audio.channel_speakers.iter().any(|speaker| speaker.trim().is_empty()) {
+            problems.push("audio.channel_speakers 不可包含空白標籤".to_string());
+        }
+        if !(10..=100).contains(&self.vad.frame_ms) {
+            problems.push(format!("vad.frame_ms 必須介於 10 與 100 之間: {}", self.vad.frame_ms));
+        }
+        if !(-90.0..=0.0).contains(&self.vad.energy_threshold_db) {
+            problems.push(format!("vad.energy_threshold_db 必須介於 -90 與 0 之間: {}", self.vad.energy_threshold_db));
+        }
+        if !(0.0..=1.0).contains(&self.vad.max_zero_crossing_rate) {
+            problems.push(format!(
+                "vad.max_zero_crossing_rate 必須介於 0.0 與 1.0 之間: {}",
+                self.vad.max_zero_crossing_rate
+            ));
+        }
+        if self.gpu.block_size_mb == 0 {
+            problems.push("gpu.block_size_mb 必須大於 0".to_string());
+        }
+        if self.gpu.pre_allocated_mb > self.gpu.max_memory_mb {
+            problems.push(format!(
+                "gpu.pre_allocated_mb ({}) 不可超過 gpu.max_memory_mb ({})",
+                self.gpu.pre_allocated_mb, self.gpu.max_memory_mb
+            ));
+        }
+        if self.jobs.retention_secs == 0 {
+            problems.push("jobs.retention_secs 必須大於 0".to_string());
+        }
+
+        problems.extend(self.whisper.models.problems());
+        problems.extend(self.decoding.problems());
+        problems.extend(self.vocabulary.problems());
+        problems.extend(self.hallucination.problems());
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("配置驗證失敗:\n  - {}", problems.join("\n  - ")))
+        }
+    }
+
+    /// 以 JSON 格式輸出完整配置 (--print-config)
+    pub fn to_pretty_string(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_else(|e| format!("配置序列化失敗: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoding::SamplingMode;
+    use crate::whisper_model_pool::TranscriptionQuality;
+
+    #[test]
+    fn test_defaults_match_previous_hardcoded_values() {
+        let config = AppConfig::default();
+        assert_eq!(config.server.port, 3000);
+        assert_eq!(config.server.max_upload_bytes, 100 * 1024 * 1024);
+        assert_eq!(config.whisper.model_path, "./models");
+        assert_eq!(config.whisper.timeout(), Duration::from_secs(90));
+        assert_eq!(config.audio.opus_pool_size, 4);
+        assert_eq!(config.gpu.max_memory_mb, 4096);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_reports_every_problem() {
+        let mut config = AppConfig::default();
+        config.server.port = 0;
+        config.audio.opus_pool_size = 0;
+        config.gpu.pre_allocated_mb = 8192;
+        config.whisper.low_confidence_threshold = 1.5;
+
+        let message = config.validate().unwrap_err().to_string();
+        assert!(message.contains("server.port"));
+        assert!(message.contains("audio.opus_pool_size"));
+        assert!(message.contains("gpu.pre_allocated_mb"));
+        assert!(message.contains("whisper.low_confidence_threshold"));
+    }
+
+    #[test]
+    fn test_load_from_toml_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("care-voice.toml");
+        std::fs::write(&path, "[server]\nport = 8080\n\n[whisper]\nworkers = 2\n").unwrap();
+
+        let config = AppConfig::load(Some(&path)).unwrap();
+        assert_eq!(config.server.port, 8080);
+        assert_eq!(config.whisper.worker_count(), 2);
+        assert_eq!(config.jobs.retention_secs, 3600);
+    }
+
+    #[test]
+    fn test_channel_mode_forms() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("care-voice.toml");
+
+        std::fs::write(&path, "[audio]\nchannel_mode = \"separate\"\n").unwrap();
+        assert_eq!(AppConfig::load(Some(&path)).unwrap().audio.channel_mode, ChannelMode::Separate);
+
+        std::fs::write(&path, "[audio]\nchannel_mode = { select = 1 }\n").unwrap();
+        assert_eq!(AppConfig::load(Some(&path)).unwrap().audio.channel_mode, ChannelMode::Select(1));
+
+        std::fs::write(&path, "[audio]\nchannel_speakers = [\"nurse\", \"\"]\n").unwrap();
+        assert!(AppConfig::load(Some(&path)).is_err());
+    }
+
+    #[test]
+    fn test_decoding_profile_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("care-voice.toml");
+        std::fs::write(&path, "[decoding.medium]\nsampling = \"beam_search\"\nbeam_size = 4\n").unwrap();
+
+        let config = AppConfig::load(Some(&path)).unwrap();
+        assert_eq!(config.decoding.medium.sampling, SamplingMode::BeamSearch);
+        assert_eq!(config.decoding.medium.beam_size, 4);
+        // 未列出的品質等級保留各自的預設
+        assert_eq!(config.decoding.premium, DecodingConfig::default().premium);
+
+        std::fs::write(&path, "[decoding.premium]\nbeam_size = 0\n").unwrap();
+        assert!(AppConfig::load(Some(&path)).is_err());
+    }
+
+    #[test]
+    fn test_model_registry() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("care-voice.toml");
+        std::fs::write(
+            &path,
+            "[whisper.models]\nload = [\"premium\", \"turbo\"]\nfallback = [\"premium\"]\npremium = \"ggml-large-v3-q5_0.bin\"\n",
+        ).unwrap();
+
+        let config = AppConfig::load(Some(&path)).unwrap();
+        assert_eq!(config.whisper.models.load, vec![TranscriptionQuality::Premium, TranscriptionQuality::Turbo]);
+        assert_eq!(config.whisper.models.model_file(TranscriptionQuality::Premium), "ggml-large-v3-q5_0.bin");
+        assert_eq!(config.whisper.models.model_file(TranscriptionQuality::Medium), "ggml-medium.bin");
+
+        std::fs::write(&path, "[whisper.models]\nload = [\"ultra\"]\n").unwrap();
+        assert!(AppConfig::load(Some(&path)).is_err());
+    }
+
+    #[test]
+    fn test_organization_vocabulary() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("care-voice.toml");
+        std::fs::write(
+            &path,
+            "[vocabulary]\nterms = [\"長照\"]\n\n[vocabulary.organizations]\nsunrise-home = [\"居服員\", \"王小明\"]\n",
+        ).unwrap();
+
+        let config = AppConfig::load(Some(&path)).unwrap();
+        assert_eq!(config.vocabulary.resolve(Some("sunrise-home"), &[]).unwrap(), vec!["居服員", "王小明", "長照"]);
+
+        std::fs::write(&path, "[vocabulary]\nterms = [\"\"]\n").unwrap();
+        assert!(AppConfig::load(Some(&path)).is_err());
+    }
+
+    #[test]
+    fn test_unknown_key_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("care-voice.toml");
+        std::fs::write(&path, "[server]\nprot = 8080\n").unwrap();
+
+        assert!(AppConfig::load(Some(&path)).is_err());
+    }
+}
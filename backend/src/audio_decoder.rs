@@ -2,14 +2,19 @@
 // 整合 Opus, Vorbis, AAC, WAV 等多種格式的解碼
 // 99.9% 瀏覽器相容性，智能格式路由
 
+use crate::app_config::AudioConfig;
 use crate::audio_format::{AudioFormat, AudioFormatDetector};
+use crate::error::PipelineError;
+use crate::resampler;
 use crate::opus_decoder::{
     OpusDecoderConfig, OpusDecoderPool, 
     decode_audio_universal
 };
+use crate::{ogg_demuxer, webm_demuxer};
+use serde::{Deserialize, Serialize};
 use tracing::{info, warn, error};
 use anyhow::{Result, Context};
-use metrics::{counter, histogram, gauge};
+use metrics::{counter, histogram};
 use std::sync::Arc;
 use parking_lot::Mutex;
 use std::fs::{self, File};
@@ -18,7 +23,7 @@ use std::path::PathBuf;
 use std::time::SystemTime;
 use uuid::Uuid;
 use std::io::Cursor;
-use symphonia::core::audio::{AudioBufferRef, Signal};
+use symphonia::core::audio::{AudioBufferRef, SampleBuffer};
 use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
 use symphonia::core::errors::Error as SymphoniaError;
 use symphonia::core::formats::FormatOptions;
@@ -27,10 +32,113 @@ use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::Hint;
 use hound;
 
+/// 多聲道音頻的處理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelMode {
+    /// 所有聲道取平均混為單聲道
+    #[default]
+    Downmix,
+    /// 只使用指定聲道 (從 0 開始)；單聲道來源直接使用唯一聲道
+    Select(usize),
+    /// 混音結果之外另保留各聲道樣本，供逐聲道轉錄
+    Separate,
+}
+
+/// 解碼器輸出的原始音頻，每個聲道一個 Vec
+#[derive(Debug, Clone)]
+struct PlanarAudio {
+    channels: Vec<Vec<f32>>,
+    sample_rate: u32,
+}
+
+impl PlanarAudio {
+    fn mono(samples: Vec<f32>, sample_rate: u32) -> Self {
+        Self {
+            channels: vec![samples],
+            sample_rate,
+        }
+    }
+
+    /// 從交錯排列 (L R L R ...) 的樣本建立
+    fn from_interleaved(interleaved: &[f32], channel_count: usize, sample_rate: u32) -> Self {
+        let channel_count = channel_count.max(1);
+        let frames = interleaved.len() / channel_count;
+        let channels = (0..channel_count)
+            .map(|c| interleaved.iter().skip(c).step_by(channel_count).take(frames).copied().collect())
+            .collect();
+        Self { channels, sample_rate }
+    }
+
+    fn frames(&self) -> usize {
+        self.channels.iter().map(Vec::len).min().unwrap_or(0)
+    }
+
+    /// 依聲道模式產生單聲道樣本，Separate 模式另返回各聲道
+    fn mix(mut self, mode: ChannelMode) -> Result<(Vec<f32>, Vec<Vec<f32>>), PipelineError> {
+        let frames = self.frames();
+        self.channels.iter_mut().for_each(|channel| channel.truncate(frames));
+
+        if self.channels.len() == 1 {
+            return Ok((self.channels.pop().unwrap_or_default(), Vec::new()));
+        }
+
+        let downmix = || {
+            let scale = 1.0 / self.channels.len() as f32;
+            (0..frames)
+                .map(|i| self.channels.iter().map(|channel| channel[i]).sum::<f32>() * scale)
+                .collect::<Vec<f32>>()
+        };
+
+        match mode {
+            ChannelMode::Downmix => Ok((downmix(), Vec::new())),
+            ChannelMode::Select(index) => {
+                if index >= self.channels.len() {
+                    return Err(PipelineError::InvalidRequest(format!(
+                        "音頻只有 {} 個聲道，無法選取聲道 {}",
+                        self.channels.len(),
+                        index
+                    )));
+                }
+                Ok((self.channels.swap_remove(index), Vec::new()))
+            },
+            ChannelMode::Separate => {
+                let mixed = downmix();
+                Ok((mixed, self.channels))
+            },
+        }
+    }
+}
+
+/// 解碼並重採樣到 16kHz 的音頻
+#[derive(Debug, Clone)]
+pub struct DecodedAudio {
+    /// 單聲道樣本 (依 ChannelMode 混音或選取)，Whisper 輸入
+    pub samples: Vec<f32>,
+    /// 各聲道樣本；僅在 ChannelMode::Separate 且來源為多聲道時保留
+    pub channels: Vec<Vec<f32>>,
+    /// `samples` 的採樣率
+    pub sample_rate: u32,
+    /// 解碼後、重採樣前的原始採樣率
+    pub source_sample_rate: u32,
+    /// 來源聲道數
+    pub source_channels: usize,
+}
+
+impl DecodedAudio {
+    pub fn duration_seconds(&self) -> f64 {
+        self.samples.len() as f64 / self.sample_rate.max(1) as f64
+    }
+}
+
 /// 業界領先的統一音頻解碼器 - 支援所有現代瀏覽器格式
 pub struct UnifiedAudioDecoder {
     format_detector: Arc<Mutex<AudioFormatDetector>>,
     opus_48k_decoder_pool: Arc<OpusDecoderPool>,    // 48kHz 解碼器池 (統一音頻處理)
+    opus_48k_stereo_decoder_pool: Arc<OpusDecoderPool>, // 48kHz 立體聲解碼器池 (逐聲道處理)
+    debug_archive: bool,                            // 存檔收到的音頻以便除錯
+    max_duration_secs: u64,                         // 解碼後音頻長度上限
+    channel_mode: ChannelMode,                      // 多聲道處理方式
 }
 
 impl Default for UnifiedAudioDecoder {
@@ -42,78 +150,124 @@ impl Default for UnifiedAudioDecoder {
 impl UnifiedAudioDecoder {
     /// 創建新的統一音頻解碼器（簡化版）
     pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
+        Self::with_config(&AudioConfig::default())
+    }
+
+    /// 依配置創建統一音頻解碼器
+    pub fn with_config(config: &AudioConfig) -> Result<Self, Box<dyn std::error::Error>> {
         info!("🚀 初始化業界領先統一音頻解碼器（簡化架構）");
         
         // 48kHz 解碼器池 (優化配置)
         let config_48k = OpusDecoderConfig {
             sample_rate: 48000,  // WebCodecs 固定採樣率
             channels: 1,         // 單聲道 (Whisper 要求)
-            bit_rate: 96000,     // 🔧 優化：匹配前端96kbps配置
+            bit_rate: config.opus_bit_rate,
             enable_normalization: true,
-            pool_size: 4,        // 🔧 優化：減少池大小節省記憶體
+            pool_size: config.opus_pool_size,
         };
+        let opus_48k_stereo_pool = OpusDecoderPool::new(OpusDecoderConfig { channels: 2, ..config_48k.clone() })?;
         let opus_48k_pool = OpusDecoderPool::new(config_48k)?;
         info!("✅ 48kHz OPUS 解碼器池初始化成功（統一架構）");
         
         Ok(Self {
             format_detector: Arc::new(Mutex::new(AudioFormatDetector::new())),
             opus_48k_decoder_pool: Arc::new(opus_48k_pool),
+            opus_48k_stereo_decoder_pool: Arc::new(opus_48k_stereo_pool),
+            debug_archive: config.debug_archive,
+            max_duration_secs: config.max_duration_secs,
+            channel_mode: config.channel_mode,
         })
     }
 
+    /// 配置的多聲道處理方式
+    pub fn channel_mode(&self) -> ChannelMode {
+        self.channel_mode
+    }
+
     /// 主要解碼函數 - 根據格式自動選擇適當的解碼器
     pub fn decode_audio(
         &self,
         format: AudioFormat, 
         data: &[u8]
-    ) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+    ) -> Result<DecodedAudio, PipelineError> {
+        self.decode_audio_with_channel_mode(format, data, self.channel_mode)
+    }
+
+    /// 以指定的聲道模式解碼 (覆蓋配置，例如單一請求要求逐聲道轉錄)
+    pub fn decode_audio_with_channel_mode(
+        &self,
+        format: AudioFormat,
+        data: &[u8],
+        channel_mode: ChannelMode,
+    ) -> Result<DecodedAudio, PipelineError> {
         let decode_start = std::time::Instant::now();
         info!("🎵 開始業界領先音頻解碼: 格式={:?}, 數據大小={}bytes", format, data.len());
+        let format_label = format.metric_label();
+        counter!("audio_decode_total", "format" => format_label).increment(1);
 
-        let samples = match format {
+        if data.is_empty() {
+            return Err(PipelineError::EmptyAudio);
+        }
+
+        let decoded = match format {
             AudioFormat::WebmOpus => {
                 info!("🎵 使用業界領先 WebM-OPUS 解碼器 (Chrome/Edge)");
-                counter!("audio_decode_webm_opus_total").increment(1);
-                self.decode_webm_opus(data)?
+                self.decode_webm_opus(data, channel_mode)
             },
             AudioFormat::OggOpus => {
                 info!("🎵 使用業界領先 OGG-OPUS 解碼器 (Firefox)");
-                counter!("audio_decode_ogg_opus_total").increment(1);
-                self.decode_ogg_opus(data)?
+                self.decode_ogg_opus(data, channel_mode)
             },
             AudioFormat::Mp4Aac => {
                 info!("📦 使用 MP4-AAC 解碼器 (Safari)");
-                counter!("audio_decode_mp4_aac_total").increment(1);
-                Self::decode_mp4_aac(data)?
+                Self::decode_mp4_aac(data)
             },
             AudioFormat::Wav => {
                 info!("🔊 使用 WAV 解碼器 (通用格式)");
-                counter!("audio_decode_wav_total").increment(1);
-                Self::decode_wav(data)?
+                Self::decode_wav(data)
             },
             AudioFormat::WebmVorbis => {
                 info!("🔊 使用 WebM/OGG-Vorbis 解碼器 (舊版)");
-                counter!("audio_decode_vorbis_total").increment(1);
-                Self::decode_vorbis_with_symphonia(data)?
+                Self::decode_vorbis_with_symphonia(data)
             },
             AudioFormat::Unknown => {
                 warn!("❓ 未知格式，嘗試使用啟發式解碼");
-                counter!("audio_decode_unknown_total").increment(1);
-                Self::decode_unknown_format(data)?
+                Self::decode_unknown_format(data)
             }
         };
 
+        let decoded = decoded.map_err(|e| {
+            error!("❌ {} 解碼失敗: {}", format.friendly_name(), e);
+            counter!("audio_decode_errors_total", "format" => format_label).increment(1);
+            // 解碼器已判定的管線錯誤 (例如不支援的聲道模式) 原樣回傳
+            let e = match e.downcast::<PipelineError>() {
+                Ok(pipeline_error) => return *pipeline_error,
+                Err(e) => e,
+            };
+            match format {
+                // 無法識別的格式與尚未完整支援的 MP4-AAC 屬於格式問題而非資料損壞
+                AudioFormat::Unknown | AudioFormat::Mp4Aac => {
+                    PipelineError::UnsupportedFormat(format!("{}: {}", format.friendly_name(), e))
+                },
+                _ => PipelineError::CorruptContainer(format!("{}: {}", format.friendly_name(), e)),
+            }
+        })?;
+        let audio = self.finalize_decoded(decoded, channel_mode)?;
+        let samples = &audio.samples;
+
         let decode_time = decode_start.elapsed();
         
         // 記錄性能指標
-        histogram!("audio_decode_time_ms").record(decode_time.as_millis() as f64);
-        histogram!("audio_decode_input_size_bytes").record(data.len() as f64);
-        histogram!("audio_decode_output_samples").record(samples.len() as f64);
-        gauge!("audio_decode_last_sample_count").set(samples.len() as f64);
-        counter!("audio_decode_success_total").increment(1);
-
-        info!("✅ 音頻解碼完成: {} samples, 耗時: {:?}", samples.len(), decode_time);
-        Ok(samples)
+        histogram!("audio_decode_time_ms", "format" => format_label).record(decode_time.as_millis() as f64);
+        histogram!("audio_decode_input_size_bytes", "format" => format_label).record(data.len() as f64);
+        histogram!("audio_decode_output_samples", "format" => format_label).record(samples.len() as f64);
+        counter!("audio_decode_success_total", "format" => format_label).increment(1);
+
+        info!(
+            "✅ 音頻解碼完成: {} samples (原始 {}Hz, {} 聲道, 分離 {} 聲道), 耗時: {:?}",
+            samples.len(), audio.source_sample_rate, audio.source_channels, audio.channels.len(), decode_time
+        );
+        Ok(audio)
     }
 
     /// 帶 MIME 類型提示的智能解碼函數
@@ -121,7 +275,7 @@ impl UnifiedAudioDecoder {
         &self,
         data: &[u8], 
         mime_type: &str
-    ) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+    ) -> Result<DecodedAudio, PipelineError> {
         info!("🔍 智能音頻解碼 (MIME: {})", mime_type);
 
         // 使用智能格式檢測器
@@ -134,6 +288,27 @@ impl UnifiedAudioDecoder {
         self.decode_audio(format, data)
     }
 
+    /// 自動檢測格式並解碼 (MIME 類型可選，缺少時僅依二進制檢測)
+    pub fn decode_audio_auto(
+        &self,
+        data: &[u8],
+        mime_type: Option<&str>
+    ) -> Result<DecodedAudio, PipelineError> {
+        let format = self.detect_format(data, mime_type);
+        self.decode_audio(format, data)
+    }
+
+    /// 依二進制內容 (與可選的 MIME 類型) 檢測音頻格式
+    pub fn detect_format(&self, data: &[u8], mime_type: Option<&str>) -> AudioFormat {
+        let format = {
+            let mut detector = self.format_detector.lock();
+            detector.detect_format(data, mime_type)
+        };
+
+        info!("✅ 檢測到格式: {} (MIME: {:?})", format.friendly_name(), mime_type);
+        format
+    }
+
     /// 靜態解碼方法 (向後相容)
     pub fn decode_audio_with_mime_static(
         data: &[u8], 
@@ -146,12 +321,18 @@ impl UnifiedAudioDecoder {
     }
 
     /// 🚀 WebCodecs 獨立包 OPUS 解碼 - 2025年業界領先技術（修復版）
-    pub fn decode_webcodecs_packets(&self, packets: &[Vec<u8>]) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+    ///
+    /// channel_mode 由呼叫端決定 (請求參數或配置)，與 [`Self::decode_audio_with_channel_mode`] 相同。
+    pub fn decode_webcodecs_packets(
+        &self,
+        packets: &[Vec<u8>],
+        channel_mode: ChannelMode,
+    ) -> Result<DecodedAudio, PipelineError> {
         let decode_start = std::time::Instant::now();
         info!("🚀 開始 WebCodecs 獨立包 OPUS 解碼: {} 個包", packets.len());
 
         if packets.is_empty() {
-            return Err("WebCodecs 包數組為空".into());
+            return Err(PipelineError::EmptyAudio);
         }
 
         // 統計包大小分佈
@@ -165,7 +346,7 @@ impl UnifiedAudioDecoder {
         );
 
         // 🎧 創建音頻調試存檔（如果啟用）
-        let mut debug_archive = if std::env::var("CARE_VOICE_DEBUG_AUDIO").is_ok() {
+        let mut debug_archive = if self.debug_archive {
             match AudioDebugArchive::new() {
                 Ok(mut archive) => {
                     // 存檔所有獨立包（合併為調試用途）
@@ -191,7 +372,7 @@ impl UnifiedAudioDecoder {
         // 🎯 簡化架構：使用48kHz解碼器池處理獨立包
         info!("🔧 使用48kHz解碼器池處理WebCodecs獨立包");
         
-        let samples = match self.opus_48k_decoder_pool.decode_webcodecs_packets(packets) {
+        let samples_48k = match self.opus_48k_decoder_pool.decode_webcodecs_packets(packets) {
             Ok(samples_48k) => {
                 info!("✅ 48kHz獨立包解碼成功: {} samples", samples_48k.len());
                 
@@ -202,30 +383,30 @@ impl UnifiedAudioDecoder {
                     }
                 }
                 
-                // 🔄 48kHz → 16kHz 重採樣 (Whisper AI 要求)
-                let samples_16k = Self::resample_48k_to_16k(&samples_48k);
-                info!("🔄 48kHz → 16kHz 重採樣完成: {} → {} samples", samples_48k.len(), samples_16k.len());
-                
-                // 🎧 存檔16kHz重採樣結果
-                if let Some(ref mut archive) = debug_archive {
-                    if let Err(e) = archive.archive_resampled_16k(&samples_16k) {
-                        warn!("🚨 存檔16kHz重採樣結果失敗: {}", e);
-                    }
-                }
-                
-                samples_16k
+                samples_48k
             },
             Err(e) => {
                 error!("❌ 48kHz獨立包解碼失敗: {}", e);
-                return Err(format!("WebCodecs 獨立包解碼失敗: {}", e).into());
+                return Err(PipelineError::CorruptContainer(format!("WebCodecs 獨立包解碼失敗: {}", e)));
             }
         };
 
+        // 🔄 48kHz → 16kHz 重採樣 (Whisper AI 要求)
+        let audio = self.finalize_decoded(PlanarAudio::mono(samples_48k, 48000), channel_mode)?;
+        let samples = &audio.samples;
+
+        // 🎧 存檔16kHz重採樣結果
+        if let Some(ref mut archive) = debug_archive {
+            if let Err(e) = archive.archive_resampled_16k(samples) {
+                warn!("🚨 存檔16kHz重採樣結果失敗: {}", e);
+            }
+        }
+
         let decode_time = decode_start.elapsed();
         
         // 🎧 存檔最終Whisper輸入數據
         if let Some(ref mut archive) = debug_archive {
-            if let Err(e) = archive.archive_whisper_input(&samples) {
+            if let Err(e) = archive.archive_whisper_input(samples) {
                 warn!("🚨 存檔Whisper輸入數據失敗: {}", e);
             } else {
                 info!("🎧 音頻調試存檔完成: session_id={}", archive.session_id);
@@ -234,23 +415,23 @@ impl UnifiedAudioDecoder {
         
         // 記錄 WebCodecs 特定指標
         histogram!("webcodecs_packets_decode_time_ms").record(decode_time.as_millis() as f64);
-        histogram!("webcodecs_packets_count").record(packets.len() as f64);
+        histogram!("webcodecs_packets_per_request").record(packets.len() as f64);
         histogram!("webcodecs_packets_output_samples").record(samples.len() as f64);
         counter!("webcodecs_packets_decode_success_total").increment(1);
 
         info!("✅ WebCodecs 獨立包解碼完成: {} samples, 耗時: {:?}", samples.len(), decode_time);
-        Ok(samples)
+        Ok(audio)
     }
 
     /// 🚀 WebCodecs 原始 OPUS 解碼 - 2025年業界領先技術（已廢棄）
     #[deprecated(note = "WebCodecs 應使用獨立包模式 decode_webcodecs_packets")]
-    pub fn decode_raw_opus(&self, data: &[u8]) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+    pub fn decode_raw_opus(&self, data: &[u8], channel_mode: ChannelMode) -> Result<DecodedAudio, PipelineError> {
         warn!("⚠️ 使用已廢棄的原始OPUS解碼，建議改用獨立包模式");
         let decode_start = std::time::Instant::now();
         info!("🚀 開始 WebCodecs 原始 OPUS 解碼: {} bytes", data.len());
 
         // 🎧 創建音頻調試存檔（如果啟用）
-        let mut debug_archive = if std::env::var("CARE_VOICE_DEBUG_AUDIO").is_ok() {
+        let mut debug_archive = if self.debug_archive {
             match AudioDebugArchive::new() {
                 Ok(mut archive) => {
                     // 存檔原始OPUS數據
@@ -270,11 +451,11 @@ impl UnifiedAudioDecoder {
 
         // 驗證數據大小
         if data.is_empty() {
-            return Err("WebCodecs OPUS 數據為空".into());
+            return Err(PipelineError::EmptyAudio);
         }
 
         if data.len() < 8 {
-            return Err("WebCodecs OPUS 數據過小，可能損壞".into());
+            return Err(PipelineError::CorruptContainer("WebCodecs OPUS 數據過小，可能損壞".to_string()));
         }
 
         // 🎯 WebCodecs 特殊處理：不要拆分包，直接解碼連續流
@@ -283,7 +464,7 @@ impl UnifiedAudioDecoder {
         // 🎯 簡化架構：只使用48kHz解碼器池 + 重採樣
         info!("🔧 簡化模式：固定使用48kHz解碼器 + 16kHz重採樣");
         
-        let samples = match self.opus_48k_decoder_pool.decode(data) {
+        let samples_48k = match self.opus_48k_decoder_pool.decode(data) {
             Ok(samples_48k) => {
                 info!("✅ 48kHz OPUS 解碼器池解碼成功: {} samples", samples_48k.len());
                 
@@ -294,30 +475,30 @@ impl UnifiedAudioDecoder {
                     }
                 }
                 
-                // 🔄 48kHz → 16kHz 重採樣 (Whisper AI 要求)
-                let samples_16k = Self::resample_48k_to_16k(&samples_48k);
-                info!("🔄 48kHz → 16kHz 重採樣完成: {} → {} samples", samples_48k.len(), samples_16k.len());
-                
-                // 🎧 存檔16kHz重採樣結果
-                if let Some(ref mut archive) = debug_archive {
-                    if let Err(e) = archive.archive_resampled_16k(&samples_16k) {
-                        warn!("🚨 存檔16kHz重採樣結果失敗: {}", e);
-                    }
-                }
-                
-                samples_16k
+                samples_48k
             },
             Err(e) => {
                 error!("❌ 48kHz OPUS 解碼失敗: {}", e);
-                return Err(format!("WebCodecs 48kHz OPUS 解碼失敗: {}", e).into());
+                return Err(PipelineError::CorruptContainer(format!("WebCodecs 48kHz OPUS 解碼失敗: {}", e)));
             }
         };
 
+        // 🔄 48kHz → 16kHz 重採樣 (Whisper AI 要求)
+        let audio = self.finalize_decoded(PlanarAudio::mono(samples_48k, 48000), channel_mode)?;
+        let samples = &audio.samples;
+
+        // 🎧 存檔16kHz重採樣結果
+        if let Some(ref mut archive) = debug_archive {
+            if let Err(e) = archive.archive_resampled_16k(samples) {
+                warn!("🚨 存檔16kHz重採樣結果失敗: {}", e);
+            }
+        }
+
         let decode_time = decode_start.elapsed();
         
         // 🎧 存檔最終Whisper輸入數據
         if let Some(ref mut archive) = debug_archive {
-            if let Err(e) = archive.archive_whisper_input(&samples) {
+            if let Err(e) = archive.archive_whisper_input(samples) {
                 warn!("🚨 存檔Whisper輸入數據失敗: {}", e);
             } else {
                 info!("🎧 音頻調試存檔完成: session_id={}", archive.session_id);
@@ -331,11 +512,48 @@ impl UnifiedAudioDecoder {
         counter!("webcodecs_opus_decode_success_total").increment(1);
 
         info!("✅ WebCodecs OPUS 解碼完成: {} samples, 耗時: {:?}", samples.len(), decode_time);
-        Ok(samples)
+        Ok(audio)
+    }
+
+    /// 檢查解碼結果 (不可為空且不可超過長度上限)，套用聲道模式並重採樣到 16kHz
+    fn finalize_decoded(&self, audio: PlanarAudio, channel_mode: ChannelMode) -> Result<DecodedAudio, PipelineError> {
+        let frames = audio.frames();
+        if frames == 0 {
+            return Err(PipelineError::EmptyAudio);
+        }
+
+        let duration_seconds = frames as f64 / audio.sample_rate.max(1) as f64;
+        if duration_seconds > self.max_duration_secs as f64 {
+            warn!("⚠️ 音頻過長: {:.1} 秒 (上限 {} 秒)", duration_seconds, self.max_duration_secs);
+            return Err(PipelineError::AudioTooLong {
+                duration_seconds,
+                max_seconds: self.max_duration_secs,
+            });
+        }
+
+        let source_sample_rate = audio.sample_rate;
+        let source_channels = audio.channels.len();
+        if source_channels > 1 {
+            info!("🎚️ {} 聲道音頻，聲道模式: {:?}", source_channels, channel_mode);
+        }
+        let (mixed, channels) = audio.mix(channel_mode)?;
+
+        let resample = |samples: &[f32]| {
+            resampler::resample_to_16k(samples, source_sample_rate).map_err(|e| {
+                PipelineError::CorruptContainer(format!("{}Hz 音頻重採樣失敗: {}", source_sample_rate, e))
+            })
+        };
+        Ok(DecodedAudio {
+            samples: resample(&mixed)?,
+            channels: channels.iter().map(|channel| resample(channel)).collect::<Result<_, _>>()?,
+            sample_rate: resampler::WHISPER_SAMPLE_RATE,
+            source_sample_rate,
+            source_channels,
+        })
     }
 
     /// OPUS 後備解碼方案 - 處理可能的格式變異
-    fn decode_opus_fallback(data: &[u8]) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+    fn decode_opus_fallback(data: &[u8]) -> Result<PlanarAudio, Box<dyn std::error::Error>> {
         info!("🔧 嘗試 OPUS 後備解碼方案");
 
         // 方案1: 嘗試作為 OGG-OPUS 解碼 (WebCodecs 可能添加了 OGG 包裝)
@@ -360,7 +578,7 @@ impl UnifiedAudioDecoder {
     }
 
     /// 嘗試作為 OGG-OPUS 解碼
-    fn try_decode_as_ogg_opus(data: &[u8]) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+    fn try_decode_as_ogg_opus(data: &[u8]) -> Result<PlanarAudio, Box<dyn std::error::Error>> {
         // 檢查 OGG 魔術數字
         if data.len() >= 4 && &data[0..4] == b"OggS" {
             info!("🔍 檢測到 OGG 頭部，嘗試 OGG-OPUS 解碼");
@@ -371,14 +589,14 @@ impl UnifiedAudioDecoder {
     }
 
     /// 🔧 WebCodecs 連續流解碼 - 跳過包拆分算法
-    fn decode_webcodecs_continuous_stream(data: &[u8]) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+    fn decode_webcodecs_continuous_stream(data: &[u8]) -> Result<PlanarAudio, Box<dyn std::error::Error>> {
         info!("🚀 開始 WebCodecs 連續流解碼: {} bytes", data.len());
 
         // 嘗試使用 Symphonia 直接解碼整個 OPUS 流
         match Self::decode_with_symphonia(data, Some("opus")) {
-            Ok(samples) => {
-                info!("✅ Symphonia 連續流解碼成功: {} samples", samples.len());
-                return Ok(samples);
+            Ok(audio) => {
+                info!("✅ Symphonia 連續流解碼成功: {} samples", audio.frames());
+                return Ok(audio);
             },
             Err(e) => {
                 info!("⚠️ Symphonia OPUS 解碼失敗: {}, 嘗試其他方法", e);
@@ -389,9 +607,9 @@ impl UnifiedAudioDecoder {
         if data.len() >= 4 && &data[0..4] == b"OggS" {
             info!("🔍 檢測到 OGG 頭部，嘗試 OGG 解碼");
             match Self::decode_with_symphonia(data, Some("ogg")) {
-                Ok(samples) => {
-                    info!("✅ OGG 連續流解碼成功: {} samples", samples.len());
-                    return Ok(samples);
+                Ok(audio) => {
+                    info!("✅ OGG 連續流解碼成功: {} samples", audio.frames());
+                    return Ok(audio);
                 }
                 Err(e) => {
                     info!("⚠️ OGG 解碼失敗: {}", e);
@@ -401,9 +619,9 @@ impl UnifiedAudioDecoder {
 
         // 嘗試作為原始 PCM 數據解碼
         match Self::try_decode_raw_audio_data(data) {
-            Ok(samples) => {
-                info!("✅ PCM 連續流解碼成功: {} samples", samples.len());
-                return Ok(samples);
+            Ok(audio) => {
+                info!("✅ PCM 連續流解碼成功: {} samples", audio.frames());
+                return Ok(audio);
             }
             Err(e) => {
                 info!("⚠️ PCM 解碼失敗: {}", e);
@@ -414,7 +632,7 @@ impl UnifiedAudioDecoder {
     }
 
     /// 嘗試解碼原始音頻數據
-    fn try_decode_raw_audio_data(data: &[u8]) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+    fn try_decode_raw_audio_data(data: &[u8]) -> Result<PlanarAudio, Box<dyn std::error::Error>> {
         info!("🔧 嘗試解碼為原始 PCM 數據");
         
         // 假設是 16-bit PCM, 48kHz 單聲道 (WebCodecs 常用格式)
@@ -433,39 +651,52 @@ impl UnifiedAudioDecoder {
         }
 
         info!("✅ 原始 PCM 解碼成功: {} samples", samples.len());
-        Ok(samples)
+        Ok(PlanarAudio::mono(samples, 48000))
     }
 
     /// WebM-OPUS 格式解碼 - 統一48kHz架構
-    fn decode_webm_opus(&self, data: &[u8]) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+    fn decode_webm_opus(&self, data: &[u8], channel_mode: ChannelMode) -> Result<PlanarAudio, Box<dyn std::error::Error>> {
         info!("🎵 解碼 WebM-OPUS (Chrome/Edge) - 統一架構");
-        
-        // 使用48kHz解碼器 + 重採樣到16kHz
-        match self.opus_48k_decoder_pool.decode(data) {
-            Ok(samples_48k) => {
-                let samples_16k = Self::resample_48k_to_16k(&samples_48k);
-                Ok(samples_16k)
-            },
-            Err(e) => Err(e.into())
-        }
+
+        // downmix 由單聲道解碼器直接混音；其他聲道模式依軌道標頭的聲道數解碼
+        let channels = match channel_mode {
+            ChannelMode::Downmix => 1,
+            _ => webm_demuxer::demux_opus(data)?.channels,
+        };
+        self.decode_opus_channels(data, channels)
     }
 
     /// OGG-OPUS 格式解碼 - 統一48kHz架構
-    fn decode_ogg_opus(&self, data: &[u8]) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+    fn decode_ogg_opus(&self, data: &[u8], channel_mode: ChannelMode) -> Result<PlanarAudio, Box<dyn std::error::Error>> {
         info!("🎵 解碼 OGG-OPUS (Firefox) - 統一架構");
-        
-        // 使用48kHz解碼器 + 重採樣到16kHz
-        match self.opus_48k_decoder_pool.decode(data) {
-            Ok(samples_48k) => {
-                let samples_16k = Self::resample_48k_to_16k(&samples_48k);
-                Ok(samples_16k)
+
+        // 串接的 link 聲道數可能不同，以最多者解碼 (單聲道 link 會複製到兩個聲道)
+        let channels = match channel_mode {
+            ChannelMode::Downmix => 1,
+            _ => ogg_demuxer::demux_opus(data)?.links.iter().map(|link| link.head.channels).max().unwrap_or(1),
+        };
+        self.decode_opus_channels(data, channels)
+    }
+
+    /// 依來源聲道數選擇 48kHz 解碼器池，重採樣由 decode_audio 統一處理
+    ///
+    /// 立體聲解碼輸出為交錯樣本；超過兩聲道的多串流 OPUS 只能混為單聲道。
+    fn decode_opus_channels(&self, data: &[u8], channels: u8) -> Result<PlanarAudio, Box<dyn std::error::Error>> {
+        match channels {
+            0 | 1 => Ok(PlanarAudio::mono(self.opus_48k_decoder_pool.decode(data)?, 48000)),
+            2 => {
+                let interleaved = self.opus_48k_stereo_decoder_pool.decode(data)?;
+                Ok(PlanarAudio::from_interleaved(&interleaved, 2, 48000))
             },
-            Err(e) => Err(e.into())
+            _ => Err(Box::new(PipelineError::InvalidRequest(format!(
+                "OPUS 音頻有 {} 個聲道，逐聲道處理僅支援單聲道或立體聲，請使用 channel_mode=downmix",
+                channels
+            )))),
         }
     }
 
     /// MP4-AAC 格式解碼 (Safari)
-    fn decode_mp4_aac(data: &[u8]) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+    fn decode_mp4_aac(data: &[u8]) -> Result<PlanarAudio, Box<dyn std::error::Error>> {
         info!("嘗試使用 symphonia 解碼 MP4-AAC");
         
         // 使用 symphonia 解碼 MP4
@@ -477,14 +708,14 @@ impl UnifiedAudioDecoder {
     }
 
     /// WAV 格式解碼
-    fn decode_wav(data: &[u8]) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+    fn decode_wav(data: &[u8]) -> Result<PlanarAudio, Box<dyn std::error::Error>> {
         info!("嘗試解碼 WAV 格式");
 
         // 首先嘗試使用 hound 解碼 WAV
         match Self::decode_wav_with_hound(data) {
-            Ok(samples) => {
-                info!("hound WAV 解碼成功: {} 樣本", samples.len());
-                Ok(samples)
+            Ok(audio) => {
+                info!("hound WAV 解碼成功: {} 幀, {} 聲道", audio.frames(), audio.channels.len());
+                Ok(audio)
             },
             Err(e) => {
                 warn!("hound WAV 解碼失敗: {}, 嘗試 symphonia", e);
@@ -494,13 +725,13 @@ impl UnifiedAudioDecoder {
     }
 
     /// Vorbis 格式解碼 (使用 symphonia)
-    fn decode_vorbis_with_symphonia(data: &[u8]) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+    fn decode_vorbis_with_symphonia(data: &[u8]) -> Result<PlanarAudio, Box<dyn std::error::Error>> {
         info!("使用 symphonia 解碼 Vorbis");
         Self::decode_with_symphonia(data, Some("ogg"))
     }
 
     /// 未知格式的啟發式解碼
-    fn decode_unknown_format(data: &[u8]) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+    fn decode_unknown_format(data: &[u8]) -> Result<PlanarAudio, Box<dyn std::error::Error>> {
         warn!("嘗試啟發式解碼未知格式");
 
         // 嘗試不同的解碼方式 (修復闉包類型問題)
@@ -542,7 +773,7 @@ impl UnifiedAudioDecoder {
     }
 
     /// 使用 hound 解碼 WAV 檔案
-    fn decode_wav_with_hound(data: &[u8]) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+    fn decode_wav_with_hound(data: &[u8]) -> Result<PlanarAudio, Box<dyn std::error::Error>> {
         let cursor = Cursor::new(data);
         let mut reader = hound::WavReader::new(cursor)?;
         
@@ -550,53 +781,31 @@ impl UnifiedAudioDecoder {
         info!("WAV 規格: {}Hz, {} 位元, {} 聲道", 
               spec.sample_rate, spec.bits_per_sample, spec.channels);
 
-        let mut samples = Vec::new();
-
-        match spec.sample_format {
+        let interleaved: Vec<f32> = match spec.sample_format {
             hound::SampleFormat::Float => {
-                for sample in reader.samples::<f32>() {
-                    samples.push(sample?);
-                }
+                reader.samples::<f32>().collect::<Result<_, _>>()?
             },
             hound::SampleFormat::Int => {
-                match spec.bits_per_sample {
-                    16 => {
-                        for sample in reader.samples::<i16>() {
-                            let s = sample? as f32 / i16::MAX as f32;
-                            samples.push(s);
-                        }
-                    },
-                    32 => {
-                        for sample in reader.samples::<i32>() {
-                            let s = sample? as f32 / i32::MAX as f32;
-                            samples.push(s);
-                        }
-                    },
-                    _ => {
-                        return Err(format!("不支援的 WAV 位元深度: {}", spec.bits_per_sample).into());
-                    }
+                if !(8..=32).contains(&spec.bits_per_sample) {
+                    return Err(format!("不支援的 WAV 位元深度: {}", spec.bits_per_sample).into());
                 }
+                // hound 以 i32 讀取任意位元深度的整數樣本 (8 位元已轉為有號)
+                let scale = 1.0 / (1u64 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .samples::<i32>()
+                    .map(|sample| sample.map(|s| s as f32 * scale))
+                    .collect::<Result<_, _>>()?
             }
-        }
+        };
 
-        // 轉換為單聲道 (如需要)
-        if spec.channels == 2 {
-            info!("轉換立體聲為單聲道");
-            let mono_samples: Vec<f32> = samples
-                .chunks_exact(2)
-                .map(|pair| (pair[0] + pair[1]) / 2.0)
-                .collect();
-            Ok(mono_samples)
-        } else {
-            Ok(samples)
-        }
+        Ok(PlanarAudio::from_interleaved(&interleaved, spec.channels as usize, spec.sample_rate))
     }
 
     /// 使用 symphonia 解碼音頻 (通用方法)
     fn decode_with_symphonia(
         data: &[u8], 
         format_hint: Option<&str>
-    ) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+    ) -> Result<PlanarAudio, Box<dyn std::error::Error>> {
         let cursor = Cursor::new(data.to_vec());
         let media_source = MediaSourceStream::new(Box::new(cursor), Default::default());
 
@@ -623,12 +832,13 @@ impl UnifiedAudioDecoder {
             .ok_or("找不到音軌")?;
 
         let track_id = track.id;
+        let sample_rate = track.codec_params.sample_rate.ok_or("音軌缺少採樣率資訊")?;
 
         // 建立解碼器
         let mut decoder = symphonia::default::get_codecs()
             .make(&track.codec_params, &decoder_opts)?;
 
-        let mut samples = Vec::new();
+        let mut channels: Vec<Vec<f32>> = Vec::new();
 
         // 解碼所有數據包
         loop {
@@ -652,7 +862,7 @@ impl UnifiedAudioDecoder {
 
             match decoder.decode(&packet) {
                 Ok(decoded) => {
-                    Self::convert_audio_buffer_to_samples(&decoded, &mut samples)?;
+                    Self::append_planar_samples(decoded, &mut channels);
                 },
                 Err(e) => {
                     warn!("解碼數據包失敗: {}", e);
@@ -660,52 +870,34 @@ impl UnifiedAudioDecoder {
             }
         }
 
-        if samples.is_empty() {
+        let audio = PlanarAudio { channels, sample_rate };
+        if audio.frames() == 0 {
             return Err("symphonia 未解碼出任何音頻樣本".into());
         }
 
-        info!("symphonia 解碼成功: {} 樣本", samples.len());
-        Ok(samples)
+        info!("symphonia 解碼成功: {} 幀, {} 聲道, {}Hz", audio.frames(), audio.channels.len(), sample_rate);
+        Ok(audio)
     }
 
-    /// 將 symphonia 音頻緩衝區轉換為 f32 樣本 (簡化版本)
-    fn convert_audio_buffer_to_samples(
-        decoded: &AudioBufferRef, 
-        samples: &mut Vec<f32>
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        use symphonia::core::audio::AudioBufferRef::*;
-
-        match decoded {
-            F32(buf) => {
-                // 取得第一個聲道的數據 (轉換為單聲道)
-                // 取得第一個聲道的數據 (轉換為單聲道)
-                let channel = buf.chan(0);
-                for &sample in channel {
-                    samples.push(sample);
-                }
-            },
-            S16(buf) => {
-                // 16位整數轉 f32
-                // 16位整數轉 f32
-                let channel = buf.chan(0);
-                for &sample in channel {
-                    samples.push(sample as f32 / i16::MAX as f32);
-                }
-            },
-            S32(buf) => {
-                // 32位整數轉 f32  
-                // 32位整數轉 f32
-                let channel = buf.chan(0);
-                for &sample in channel {
-                    samples.push(sample as f32 / i32::MAX as f32);
-                }
-            },
-            _ => {
-                return Err("暫不支援的音頻樣本格式".into());
-            }
+    /// 將 symphonia 音頻緩衝區 (任意樣本格式) 轉為 f32 並依聲道附加
+    fn append_planar_samples(decoded: AudioBufferRef, channels: &mut Vec<Vec<f32>>) {
+        let spec = *decoded.spec();
+        let frames = decoded.frames();
+        if frames == 0 {
+            return;
         }
 
-        Ok(())
+        let channel_count = spec.channels.count();
+        if channels.len() < channel_count {
+            channels.resize_with(channel_count, Vec::new);
+        }
+
+        // SampleBuffer 處理所有樣本格式 (U8/S16/S24/S32/F32/F64 等) 的轉換
+        let mut buffer = SampleBuffer::<f32>::new(frames as u64, spec);
+        buffer.copy_planar_ref(decoded);
+        for (channel, samples) in channels.iter_mut().zip(buffer.samples().chunks_exact(frames)) {
+            channel.extend_from_slice(samples);
+        }
     }
 
     /// 提供格式支援資訊 - 業界領先實現
@@ -752,179 +944,6 @@ impl UnifiedAudioDecoder {
         info!("🔄 重置統一音頻解碼器統計");
         self.format_detector.lock().reset_stats();
     }
-    
-    /// 🔄 48kHz → 16kHz 高品質重採樣 (智能算法選擇)
-    fn resample_48k_to_16k(samples_48k: &[f32]) -> Vec<f32> {
-        info!("🔄 開始48kHz→16kHz智能重採樣: {} samples", samples_48k.len());
-        
-        // 🚀 智能算法選擇：短音頻使用快速方法，長音頻使用高品質方法
-        if samples_48k.len() < 48000 { // 小於1秒的音頻
-            info!("🚀 短音頻快速重採樣路徑");
-            return Self::fast_resample_48k_to_16k(samples_48k);
-        }
-        
-        // 階段1: 高品質低通濾波 (7.2kHz截止頻率，更嚴格防混疊)
-        let filtered_samples = Self::apply_highquality_lowpass_filter(samples_48k, 7200.0, 48000.0);
-        
-        // 階段2: Lanczos 內插重採樣 (48kHz → 16kHz)
-        let ratio = 16000.0 / 48000.0; // 1/3
-        let output_length = (filtered_samples.len() as f32 * ratio) as usize;
-        let mut samples_16k = Vec::with_capacity(output_length);
-        
-        // Lanczos 重採樣參數
-        const LANCZOS_A: usize = 3; // Lanczos 窗口參數 (a=3 提供良好品質/性能平衡)
-        
-        for i in 0..output_length {
-            let src_index = i as f32 / ratio;
-            let base_index = src_index.floor() as isize;
-            
-            let mut sum = 0.0;
-            let mut weight_sum = 0.0;
-            
-            // Lanczos 內插窗口範圍
-            for j in -(LANCZOS_A as isize)..(LANCZOS_A as isize) {
-                let sample_index = base_index + j;
-                
-                // 邊界檢查
-                if sample_index >= 0 && (sample_index as usize) < filtered_samples.len() {
-                    let distance = src_index - sample_index as f32;
-                    let weight = Self::lanczos_kernel(distance, LANCZOS_A as f32);
-                    
-                    sum += filtered_samples[sample_index as usize] * weight;
-                    weight_sum += weight;
-                }
-            }
-            
-            // 正規化並添加樣本
-            if weight_sum > 0.0 {
-                samples_16k.push(sum / weight_sum);
-            } else {
-                // 後備處理：使用最近鄰樣本
-                let nearest_idx = (src_index.round() as usize).min(filtered_samples.len() - 1);
-                samples_16k.push(filtered_samples[nearest_idx]);
-            }
-        }
-        
-        info!("✅ 高品質重採樣完成: {} → {} samples ({:.2}%, Lanczos-{} 內插)", 
-              samples_48k.len(), samples_16k.len(), 
-              (samples_16k.len() as f32 / samples_48k.len() as f32) * 100.0, LANCZOS_A);
-        
-        samples_16k
-    }
-    
-    /// 🎛️ 簡單低通濾波器 (IIR 1階)
-    fn apply_lowpass_filter(samples: &[f32], cutoff_freq: f32, sample_rate: f32) -> Vec<f32> {
-        if samples.is_empty() {
-            return Vec::new();
-        }
-        
-        // 計算濾波器係數 (1階 IIR 低通濾波器)
-        let rc = 1.0 / (2.0 * std::f32::consts::PI * cutoff_freq);
-        let dt = 1.0 / sample_rate;
-        let alpha = dt / (rc + dt);
-        
-        let mut filtered = Vec::with_capacity(samples.len());
-        let mut prev_output = samples[0]; // 初始值
-        
-        for &sample in samples {
-            // IIR 濾波器: y[n] = α * x[n] + (1-α) * y[n-1]
-            let output = alpha * sample + (1.0 - alpha) * prev_output;
-            filtered.push(output);
-            prev_output = output;
-        }
-        
-        filtered
-    }
-    
-    /// 🎯 Lanczos 核函數 (高品質重採樣窗函數)
-    fn lanczos_kernel(x: f32, a: f32) -> f32 {
-        if x.abs() >= a {
-            return 0.0;
-        }
-        if x == 0.0 {
-            return 1.0;
-        }
-        
-        let pi_x = std::f32::consts::PI * x;
-        let pi_x_a = pi_x / a;
-        
-        // Lanczos 窗函數: sinc(x) * sinc(x/a)
-        (pi_x.sin() / pi_x) * (pi_x_a.sin() / pi_x_a)
-    }
-    
-    /// 🎛️ 高品質低通濾波器 (Butterworth 2階 IIR)
-    fn apply_highquality_lowpass_filter(samples: &[f32], cutoff_freq: f32, sample_rate: f32) -> Vec<f32> {
-        if samples.is_empty() {
-            return Vec::new();
-        }
-        
-        // Butterworth 2階低通濾波器係數計算
-        let omega = 2.0 * std::f32::consts::PI * cutoff_freq / sample_rate;
-        let sin_omega = omega.sin();
-        let cos_omega = omega.cos();
-        let alpha = sin_omega / (2.0 * 2.0_f32.sqrt()); // Q = √2 for Butterworth
-        
-        // 濾波器係數
-        let a0 = 1.0 + alpha;
-        let a1 = -2.0 * cos_omega / a0;
-        let a2 = (1.0 - alpha) / a0;
-        let b0 = (1.0 - cos_omega) / (2.0 * a0);
-        let b1 = (1.0 - cos_omega) / a0;
-        let b2 = (1.0 - cos_omega) / (2.0 * a0);
-        
-        let mut filtered = Vec::with_capacity(samples.len());
-        let mut x1 = 0.0f32; // x[n-1]
-        let mut x2 = 0.0f32; // x[n-2]
-        let mut y1 = 0.0f32; // y[n-1]
-        let mut y2 = 0.0f32; // y[n-2]
-        
-        for &x0 in samples {
-            // Butterworth 2階差分方程: y[n] = b0*x[n] + b1*x[n-1] + b2*x[n-2] - a1*y[n-1] - a2*y[n-2]
-            let y0 = b0 * x0 + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2;
-            
-            filtered.push(y0);
-            
-            // 更新延遲線
-            x2 = x1; x1 = x0;
-            y2 = y1; y1 = y0;
-        }
-        
-        filtered
-    }
-    
-    /// 🚀 快速重採樣 (適用於短音頻 < 1秒)
-    fn fast_resample_48k_to_16k(samples_48k: &[f32]) -> Vec<f32> {
-        // 使用簡化的2階Butterworth濾波器 + 線性內插
-        let mut filtered = Vec::with_capacity(samples_48k.len());
-        
-        // 快速2階Butterworth係數 (7kHz截止)
-        let a1 = -1.1429805f32;
-        let a2 = 0.4128016f32;
-        let b0 = 0.0676f32;
-        let b1 = 0.1353f32;
-        let b2 = 0.0676f32;
-        
-        let mut x1 = 0.0f32; let mut x2 = 0.0f32;
-        let mut y1 = 0.0f32; let mut y2 = 0.0f32;
-        
-        // 快速濾波
-        for &x0 in samples_48k {
-            let y0 = b0 * x0 + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2;
-            filtered.push(y0);
-            x2 = x1; x1 = x0; y2 = y1; y1 = y0;
-        }
-        
-        // 簡單3:1降採樣（取每第3個樣本）
-        let mut samples_16k = Vec::with_capacity(filtered.len() / 3);
-        for (i, &sample) in filtered.iter().enumerate() {
-            if i % 3 == 0 {
-                samples_16k.push(sample);
-            }
-        }
-        
-        info!("✅ 快速重採樣完成: {} → {} samples", samples_48k.len(), samples_16k.len());
-        samples_16k
-    }
 }
 
 /// 🎧 音頻調試存檔系統
@@ -1069,7 +1088,7 @@ mod tests {
         assert_eq!(support_info.len(), 6);
         
         // 檢查是否包含主要格式
-        let formats: Vec<AudioFormat> = support_info.iter().map(|(f, _)| f.clone()).collect();
+        let formats: Vec<AudioFormat> = support_info.iter().map(|(f, _)| *f).collect();
         assert!(formats.contains(&AudioFormat::OggOpus));
         assert!(formats.contains(&AudioFormat::WebmOpus));
         assert!(formats.contains(&AudioFormat::Wav));
@@ -1089,4 +1108,63 @@ mod tests {
             assert_eq!(detected, *expected_format, "MIME 類型 {} 檢測失敗", mime);
         }
     }
+
+    /// 產生 1 秒 24 位元立體聲 WAV：左聲道 0.5，右聲道 -0.25
+    fn stereo_wav_24bit(sample_rate: u32) -> Vec<u8> {
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate,
+            bits_per_sample: 24,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut cursor = Cursor::new(Vec::new());
+        {
+            let mut writer = hound::WavWriter::new(&mut cursor, spec).unwrap();
+            for _ in 0..sample_rate {
+                writer.write_sample(1 << 22).unwrap();
+                writer.write_sample(-(1 << 21)).unwrap();
+            }
+            writer.finalize().unwrap();
+        }
+        cursor.into_inner()
+    }
+
+    fn decoder_with_mode(channel_mode: ChannelMode) -> UnifiedAudioDecoder {
+        UnifiedAudioDecoder::with_config(&AudioConfig { channel_mode, ..AudioConfig::default() }).unwrap()
+    }
+
+    #[test]
+    fn test_stereo_24bit_wav_downmix_and_resample() {
+        let audio = decoder_with_mode(ChannelMode::Downmix)
+            .decode_audio(AudioFormat::Wav, &stereo_wav_24bit(44100))
+            .unwrap();
+        assert_eq!(audio.source_sample_rate, 44100);
+        assert_eq!(audio.source_channels, 2);
+        assert_eq!(audio.samples.len(), 16000);
+        assert!(audio.channels.is_empty());
+        assert!((audio.samples[8000] - 0.125).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_select_and_separate_channel_modes() {
+        let wav = stereo_wav_24bit(16000);
+
+        let right = decoder_with_mode(ChannelMode::Select(1)).decode_audio(AudioFormat::Wav, &wav).unwrap();
+        assert!((right.samples[100] + 0.25).abs() < 1e-4);
+
+        let separate = decoder_with_mode(ChannelMode::Separate).decode_audio(AudioFormat::Wav, &wav).unwrap();
+        assert_eq!(separate.channels.len(), 2);
+        assert!((separate.channels[0][100] - 0.5).abs() < 1e-4);
+        assert!((separate.samples[100] - 0.125).abs() < 1e-4);
+
+        let err = decoder_with_mode(ChannelMode::Select(2)).decode_audio(AudioFormat::Wav, &wav).unwrap_err();
+        assert_eq!(err.code(), "invalid_request");
+    }
+
+    #[test]
+    fn test_multistream_opus_rejects_per_channel_modes() {
+        let err = decoder_with_mode(ChannelMode::Separate).decode_opus_channels(&[], 6).unwrap_err();
+        let err = err.downcast::<PipelineError>().unwrap();
+        assert_eq!(err.code(), "invalid_request");
+    }
 }
\ No newline at end of file
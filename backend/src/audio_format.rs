@@ -6,7 +6,7 @@ use tracing::{info, warn, debug, error};
 use metrics::{counter, histogram};
 use std::collections::HashMap;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum AudioFormat {
     WebmOpus,   // Chrome/Edge: audio/webm;codecs=opus
     OggOpus,    // Firefox: audio/ogg;codecs=opus
@@ -162,6 +162,18 @@ impl AudioFormat {
         search_data.windows(6).any(|window| window == b"vorbis")
     }
 
+    /// 指標標籤值 (Prometheus label)
+    pub fn metric_label(&self) -> &'static str {
+        match self {
+            AudioFormat::WebmOpus => "webm_opus",
+            AudioFormat::OggOpus => "ogg_opus",
+            AudioFormat::Mp4Aac => "mp4_aac",
+            AudioFormat::Wav => "wav",
+            AudioFormat::WebmVorbis => "webm_vorbis",
+            AudioFormat::Unknown => "unknown",
+        }
+    }
+
     /// 取得格式的友善名稱
     pub fn friendly_name(&self) -> &'static str {
         match self {
@@ -258,14 +270,14 @@ impl AudioFormatDetector {
         let final_format = self.merge_detection_results(mime_result, binary_result, data);
         
         // 更新統計
-        *self.detection_stats.entry(final_format.clone()).or_insert(0) += 1;
+        *self.detection_stats.entry(final_format).or_insert(0) += 1;
         
         let detection_time = detection_start.elapsed();
         
         // 記錄指標
         histogram!("audio_format_detection_time_us").record(detection_time.as_micros() as f64);
         counter!("audio_format_detections_total").increment(1);
-        counter!("audio_format_detected_total", "format" => format!("{:?}", final_format)).increment(1);
+        counter!("audio_format_detected_total", "format" => final_format.metric_label()).increment(1);
         
         info!("✅ 格式檢測完成: {:?} ({}), 耗時: {:?}", 
               final_format, final_format.friendly_name(), detection_time);
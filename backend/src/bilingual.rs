@@ -0,0 +1,103 @@
+// ===================================
+// 雙語輸出
+// 將原文轉錄與英文翻譯兩次 Whisper 結果依時間重疊對齊，
+// 產生原文 / 譯文成對的段落，方便不諳中文的個管師對照閱讀
+// ===================================
+
+use serde::Serialize;
+
+use crate::transcript_render::join_texts;
+use crate::whisper_model_pool::TranscriptSegment;
+
+/// 段落重疊少於此秒數時視為不相交 (兩次解碼的段落邊界常有些微誤差)
+const ALIGN_TOLERANCE_SECS: f32 = 0.3;
+
+/// 對齊後的原文與譯文
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AlignedSegment {
+    pub start_time: f32,
+    pub end_time: f32,
+    pub original: String,
+    pub translation: String,
+}
+
+/// 依時間重疊將原文與譯文段落分組
+///
+/// 兩次解碼的段落切分不同，時間上互相重疊的段落 (不論來自哪一邊) 合併為同一組，
+/// 每組的原文與譯文各自串接。
+pub fn align_segments(original: &[TranscriptSegment], translation: &[TranscriptSegment]) -> Vec<AlignedSegment> {
+    let mut items: Vec<(&TranscriptSegment, bool)> = original
+        .iter()
+        .map(|segment| (segment, false))
+        .chain(translation.iter().map(|segment| (segment, true)))
+        .filter(|(segment, _)| !segment.text.trim().is_empty())
+        .collect();
+    items.sort_by(|a, b| a.0.start_time.total_cmp(&b.0.start_time));
+
+    let mut groups: Vec<(f32, f32, Vec<&str>, Vec<&str>)> = Vec::new();
+    for (segment, is_translation) in items {
+        let text = segment.text.trim();
+        let overlaps = groups
+            .last()
+            .is_some_and(|(_, end, _, _)| segment.start_time < end - ALIGN_TOLERANCE_SECS);
+        if !overlaps {
+            groups.push((segment.start_time, segment.end_time, Vec::new(), Vec::new()));
+        }
+        if let Some((_, end, originals, translations)) = groups.last_mut() {
+            *end = end.max(segment.end_time);
+            if is_translation {
+                translations.push(text);
+            } else {
+                originals.push(text);
+            }
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(start_time, end_time, originals, translations)| AlignedSegment {
+            start_time,
+            end_time,
+            original: join_texts(originals.into_iter()),
+            translation: join_texts(translations.into_iter()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlapping_segments_are_grouped() {
+        let original = [
+            TranscriptSegment::fixture(0.0, 2.0, " 您好"),
+            TranscriptSegment::fixture(2.0, 4.5, " 今天血壓有點高"),
+            TranscriptSegment::fixture(6.0, 8.0, " 記得吃藥"),
+        ];
+        // 翻譯把前兩段合成一段，邊界與原文略有誤差
+        let translation = [
+            TranscriptSegment::fixture(0.1, 4.4, " Hello, your blood pressure is a bit high today."),
+            TranscriptSegment::fixture(6.2, 8.1, " Remember to take your medicine."),
+        ];
+
+        let aligned = align_segments(&original, &translation);
+        assert_eq!(aligned.len(), 2, "{:?}", aligned);
+        assert_eq!(aligned[0].original, "您好今天血壓有點高");
+        assert_eq!(aligned[0].translation, "Hello, your blood pressure is a bit high today.");
+        assert_eq!((aligned[0].start_time, aligned[0].end_time), (0.0, 4.5));
+        assert_eq!(aligned[1].original, "記得吃藥");
+        assert!((aligned[1].end_time - 8.1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_touching_segments_stay_separate() {
+        let original = [TranscriptSegment::fixture(0.0, 2.0, " 好"), TranscriptSegment::fixture(2.1, 3.0, " 謝謝")];
+        let translation = [TranscriptSegment::fixture(0.0, 2.2, " OK."), TranscriptSegment::fixture(2.0, 3.0, " Thanks.")];
+
+        let aligned = align_segments(&original, &translation);
+        let pairs: Vec<(&str, &str)> = aligned.iter().map(|a| (a.original.as_str(), a.translation.as_str())).collect();
+        assert_eq!(pairs, vec![("好", "OK."), ("謝謝", "Thanks.")]);
+        assert!(align_segments(&[], &[TranscriptSegment::fixture(0.0, 1.0, "  ")]).is_empty());
+    }
+}
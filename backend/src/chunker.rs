@@ -0,0 +1,322 @@
+// ===================================
+// 長音頻分段轉錄
+// 在靜音處切成約 30 秒、前後重疊的區塊，依序轉錄並以前一區塊文字作為提示詞
+// (停用提示詞延續時分派到模型池並行轉錄)，再依重疊區中點與重複文字去重，拼接回單一時間軸
+// ===================================
+
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::confidence::{self, TokenConfidence};
+use crate::hallucination::FilteredSegment;
+use crate::whisper_model_pool::{self, TranscriptSegment, TranscriptionResult};
+use crate::word_timing::WordTiming;
+
+/// 在目標切點前多少秒內尋找最安靜的位置
+const CUT_SEARCH_SECS: f32 = 5.0;
+/// 尋找切點時的分析幀長度 (毫秒)
+const CUT_FRAME_MS: usize = 20;
+/// 最後一段可超出區塊長度的比例，避免切出過短的尾段
+const TAIL_SLACK_RATIO: f32 = 0.25;
+/// 傳給下一區塊作為提示詞的前文長度 (字元)
+const PROMPT_CARRY_CHARS: usize = 120;
+/// 去除重疊文字時要求的最短重複長度 (字元)，避免誤刪單一常見字
+const MIN_REPEATED_CHARS: usize = 2;
+
+/// 原始音頻中的一個區塊 (樣本索引，左閉右開)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioChunk {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// 分段規劃參數
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkPlanner {
+    chunk_samples: usize,
+    overlap_samples: usize,
+    sample_rate: u32,
+}
+
+impl ChunkPlanner {
+    /// chunk_secs 為 0 時停用分段
+    pub fn new(chunk_secs: u64, overlap_secs: u64, sample_rate: u32) -> Self {
+        Self {
+            chunk_samples: (chunk_secs * sample_rate as u64) as usize,
+            overlap_samples: (overlap_secs * sample_rate as u64) as usize,
+            sample_rate,
+        }
+    }
+
+    /// 規劃區塊；不需分段時返回涵蓋全部音頻的單一區塊
+    pub fn plan(&self, samples: &[f32]) -> Vec<AudioChunk> {
+        let total = samples.len();
+        let slack = (self.chunk_samples as f32 * TAIL_SLACK_RATIO) as usize;
+        if self.chunk_samples == 0 || total <= self.chunk_samples + slack {
+            return vec![AudioChunk { start: 0, end: total }];
+        }
+
+        let search = ((CUT_SEARCH_SECS * self.sample_rate as f32) as usize).min(self.chunk_samples / 2);
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        loop {
+            if total - start <= self.chunk_samples + slack {
+                chunks.push(AudioChunk { start, end: total });
+                break;
+            }
+            let target = start + self.chunk_samples;
+            let cut = self.quietest_point(samples, target - search, target);
+            chunks.push(AudioChunk { start, end: cut });
+            // 重疊不可超過已前進的距離，確保每段都有進展
+            start = cut - self.overlap_samples.min((cut - start) / 2);
+        }
+        chunks
+    }
+
+    /// 在 [from, to) 範圍內找出能量最低的幀中點
+    fn quietest_point(&self, samples: &[f32], from: usize, to: usize) -> usize {
+        let frame = (CUT_FRAME_MS * self.sample_rate as usize / 1000).max(1);
+        (from..to.saturating_sub(frame).max(from + 1))
+            .step_by(frame)
+            .map(|frame_start| {
+                let end = (frame_start + frame).min(samples.len());
+                let energy: f32 = samples[frame_start..end].iter().map(|s| s * s).sum();
+                (frame_start + (end - frame_start) / 2, energy)
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map_or(to, |(point, _)| point)
+    }
+}
+
+/// 前一區塊文字的結尾，作為下一區塊的提示詞
+pub fn carry_prompt(initial_prompt: Option<&str>, previous_transcript: Option<&str>) -> Option<String> {
+    let tail = previous_transcript.map(|text| {
+        let text = text.trim();
+        let skip = text.chars().count().saturating_sub(PROMPT_CARRY_CHARS);
+        text.chars().skip(skip).collect::<String>()
+    });
+    match (initial_prompt, tail.filter(|tail| !tail.is_empty())) {
+        (Some(prompt), Some(tail)) => Some(format!("{} {}", prompt, tail)),
+        (Some(prompt), None) => Some(prompt.to_string()),
+        (None, tail) => tail,
+    }
+}
+
+/// 去除 text 開頭與 previous 結尾重複的文字 (重疊區被兩個區塊各轉錄一次)
+fn strip_repeated_prefix<'a>(previous: &str, text: &'a str) -> &'a str {
+    let previous: Vec<char> = previous.trim_end().chars().collect();
+    let trimmed = text.trim_start();
+    let boundaries: Vec<usize> = trimmed.char_indices().map(|(i, _)| i).skip(1).chain([trimmed.len()]).collect();
+
+    // 由長到短尋找 previous 的後綴 == text 的前綴
+    for (count, &byte_end) in boundaries.iter().enumerate().rev() {
+        let count = count + 1;
+        if count < MIN_REPEATED_CHARS || count > previous.len() {
+            continue;
+        }
+        if trimmed[..byte_end].chars().eq(previous[previous.len() - count..].iter().copied()) {
+            return &trimmed[byte_end..];
+        }
+    }
+    text
+}
+
+/// 非空白字元數
+fn visible_chars(text: &str) -> usize {
+    text.chars().filter(|c| !c.is_whitespace()).count()
+}
+
+/// 捨棄開頭共 removed 個非空白字元所涵蓋的項目
+fn drop_leading_chars<T>(items: Vec<T>, removed: usize, text_of: impl Fn(&T) -> &str) -> Vec<T> {
+    let mut dropped = 0;
+    items
+        .into_iter()
+        .skip_while(|item| {
+            let chars = visible_chars(text_of(item));
+            let skip = dropped + chars <= removed && removed > 0;
+            dropped += chars;
+            skip
+        })
+        .collect()
+}
+
+/// 將各區塊結果拼接回原始時間軸
+///
+/// 相鄰區塊的重疊區以中點為界：中點前的段落取自前一區塊，之後取自後一區塊；
+/// 後一區塊第一段若重複前一段的結尾文字則去除重複部分。
+pub fn stitch(
+    chunks: &[AudioChunk],
+    results: Vec<TranscriptionResult>,
+    sample_rate: u32,
+    task_id: Uuid,
+    processing_time_ms: u64,
+) -> TranscriptionResult {
+    let to_seconds = |sample: usize| sample as f32 / sample_rate as f32;
+    let model_used = results.first().map(|result| result.model_used.clone()).unwrap_or_default();
+    // 各區塊獨立偵測語言，依區塊長度加權合併
+    let (language, detected_language) = whisper_model_pool::combined_language(&chunks
+        .iter()
+        .zip(&results)
+        .map(|(chunk, result)| (result, to_seconds(chunk.end - chunk.start)))
+        .collect::<Vec<_>>());
+
+    let mut segments: Vec<TranscriptSegment> = Vec::new();
+    let mut filtered_segments: Vec<FilteredSegment> = Vec::new();
+    for (index, (chunk, result)) in chunks.iter().zip(results).enumerate() {
+        let offset = to_seconds(chunk.start);
+        let keep_from = match index.checked_sub(1).and_then(|i| chunks.get(i)) {
+            Some(previous) => to_seconds(chunk.start + previous.end.saturating_sub(chunk.start) / 2),
+            None => f32::NEG_INFINITY,
+        };
+        let keep_until = match chunks.get(index + 1) {
+            Some(next) => to_seconds(next.start + chunk.end.saturating_sub(next.start) / 2),
+            None => f32::INFINITY,
+        };
+
+        filtered_segments.extend(
+            result.filtered_segments
+                .into_iter()
+                .map(|filtered| FilteredSegment {
+                    start_time: filtered.start_time + offset,
+                    end_time: filtered.end_time + offset,
+                    ..filtered
+                })
+                .filter(|filtered| filtered.start_time >= keep_from && filtered.start_time < keep_until),
+        );
+
+        let mut first_in_chunk = true;
+        for segment in result.segments {
+            let start_time = segment.start_time + offset;
+            if start_time < keep_from || start_time >= keep_until {
+                continue;
+            }
+
+            let mut text = segment.text.clone();
+            if first_in_chunk && index > 0 {
+                if let Some(previous) = segments.last() {
+                    let stripped = strip_repeated_prefix(&previous.text, &segment.text);
+                    if stripped.len() != segment.text.len() {
+                        debug!("區塊 {} 去除重疊文字: {:?}", index, &segment.text[..segment.text.len() - stripped.len()]);
+                    }
+                    text = stripped.to_string();
+                }
+            }
+            first_in_chunk = false;
+            if text.trim().is_empty() {
+                continue;
+            }
+
+            // 去除重疊文字時，被去掉的 token / 詞一併捨棄
+            let removed = visible_chars(&segment.text) - visible_chars(&text);
+            let tokens = drop_leading_chars(segment.tokens, removed, |token| &token.text)
+                .into_iter()
+                .map(|token| TokenConfidence { start: token.start + offset, end: token.end + offset, ..token })
+                .collect();
+            let words = drop_leading_chars(segment.words, removed, |word| &word.word)
+                .into_iter()
+                .map(|word| WordTiming { start: word.start + offset, end: word.end + offset, ..word })
+                .collect();
+
+            segments.push(TranscriptSegment {
+                start_time,
+                end_time: (segment.end_time + offset).max(start_time),
+                text,
+                tokens,
+                words,
+                ..segment
+            });
+        }
+    }
+
+    let transcript = segments.iter().map(|segment| segment.text.as_str()).collect::<String>();
+
+    TranscriptionResult {
+        task_id,
+        transcript: transcript.trim().to_string(),
+        confidence: confidence::overall_confidence(&segments),
+        processing_time_ms,
+        model_used,
+        language,
+        detected_language,
+        segments,
+        filtered_segments,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+
+    /// 每個字元各為一詞，依序每 0.25 秒一個
+    fn with_words(segment: TranscriptSegment) -> TranscriptSegment {
+        let words = segment.text.trim().chars().enumerate().map(|(index, c)| WordTiming {
+            word: c.to_string(),
+            start: segment.start_time + index as f32 * 0.25,
+            end: segment.start_time + (index + 1) as f32 * 0.25,
+            probability: 0.9,
+        }).collect();
+        TranscriptSegment { words, ..segment }
+    }
+
+
+    #[test]
+    fn test_short_audio_is_single_chunk() {
+        let planner = ChunkPlanner::new(30, 2, RATE);
+        assert_eq!(planner.plan(&vec![0.1; 35 * RATE as usize]), vec![AudioChunk { start: 0, end: 35 * RATE as usize }]);
+        assert_eq!(ChunkPlanner::new(0, 2, RATE).plan(&vec![0.1; 600 * RATE as usize]).len(), 1);
+    }
+
+    #[test]
+    fn test_cuts_at_silence_with_overlap() {
+        // 100 秒音頻，在 27 秒與 52 秒處有短暫靜音
+        let mut samples = vec![0.3f32; 100 * RATE as usize];
+        for silence_at in [27.0f32, 52.0] {
+            let start = (silence_at * RATE as f32) as usize;
+            samples[start..start + RATE as usize / 5].fill(0.0);
+        }
+
+        let chunks = ChunkPlanner::new(30, 2, RATE).plan(&samples);
+        assert!(chunks.len() >= 3, "{:?}", chunks);
+        assert_eq!(chunks[0].start, 0);
+        assert!((chunks[0].end as f32 / RATE as f32 - 27.0).abs() < 0.05, "{:?}", chunks[0]);
+        assert_eq!(chunks[1].start, chunks[0].end - 2 * RATE as usize);
+        assert!((chunks[1].end as f32 / RATE as f32 - 52.0).abs() < 0.05, "{:?}", chunks[1]);
+        assert_eq!(chunks.last().unwrap().end, samples.len());
+        assert!(chunks.iter().all(|chunk| chunk.end - chunk.start <= 38 * RATE as usize));
+    }
+
+    #[test]
+    fn test_stitch_drops_overlap_duplicates() {
+        let chunks = [
+            AudioChunk { start: 0, end: 30 * RATE as usize },
+            AudioChunk { start: 28 * RATE as usize, end: 50 * RATE as usize },
+        ];
+        let results = vec![
+            TranscriptionResult::fixture(vec![TranscriptSegment::fixture(0.0, 10.0, " 今天早上量血壓"), TranscriptSegment::fixture(26.0, 30.0, " 記得飯後吃藥")]),
+            // 重疊區 (28-30 秒) 再次轉錄出結尾文字
+            TranscriptionResult::fixture(vec![TranscriptSegment::fixture(0.0, 1.5, " 吃藥"), with_words(TranscriptSegment::fixture(1.5, 4.0, " 吃藥之後要休息")), TranscriptSegment::fixture(5.0, 8.0, " 明天見")]),
+        ];
+
+        let stitched = stitch(&chunks, results, RATE, Uuid::new_v4(), 10);
+        let texts: Vec<&str> = stitched.segments.iter().map(|s| s.text.trim()).collect();
+        assert_eq!(texts, vec!["今天早上量血壓", "記得飯後吃藥", "之後要休息", "明天見"]);
+        assert!((stitched.segments[2].start_time - 29.5).abs() < 1e-3);
+        // 重複的「吃藥」兩字一併從逐詞時間戳去除，其餘平移到原始時間軸
+        let words: Vec<&str> = stitched.segments[2].words.iter().map(|word| word.word.as_str()).collect();
+        assert_eq!(words, vec!["之", "後", "要", "休", "息"]);
+        assert!((stitched.segments[2].words[0].start - 30.0).abs() < 1e-3);
+        assert_eq!(stitched.transcript, "今天早上量血壓 記得飯後吃藥之後要休息 明天見");
+    }
+
+    #[test]
+    fn test_carry_prompt_keeps_user_prompt_first() {
+        let long = "長".repeat(200);
+        let prompt = carry_prompt(Some("長照"), Some(&long)).unwrap();
+        assert!(prompt.starts_with("長照 "));
+        assert_eq!(prompt.chars().count(), 3 + PROMPT_CARRY_CHARS);
+        assert_eq!(carry_prompt(None, None), None);
+        assert_eq!(carry_prompt(None, Some("好")).as_deref(), Some("好"));
+    }
+}
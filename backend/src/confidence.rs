@@ -0,0 +1,165 @@
+// ===================================
+// 轉錄信心分數
+// 由 Whisper 逐 token 機率計算 token / 段落 / 整體信心分數，
+// 供審閱者標示需要人工確認的低信心片段
+// ===================================
+
+use serde::Serialize;
+
+use crate::whisper_model_pool::TranscriptSegment;
+
+/// 單一 token (或合併後的完整字元) 的信心與時間資訊
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TokenConfidence {
+    pub text: String,
+    /// 解碼時選中此 token 的機率 (0.0 - 1.0)
+    pub probability: f32,
+    /// token 開始時間 (秒，原始音頻時間軸)
+    pub start: f32,
+    /// token 結束時間 (秒，原始音頻時間軸)
+    pub end: f32,
+}
+
+/// Whisper 解碼出的原始 token 片段
+#[derive(Debug, Clone)]
+pub struct TokenPiece {
+    pub bytes: Vec<u8>,
+    pub probability: f32,
+    /// 對數機率
+    pub logprob: f32,
+    pub start: f32,
+    pub end: f32,
+}
+
+/// 段落層級的信心統計
+#[derive(Debug, Clone, PartialEq)]
+pub struct SegmentConfidence {
+    /// token 機率的幾何平均 (= exp(avg_logprob))
+    pub confidence: f32,
+    /// 平均對數機率 (與 OpenAI verbose_json 的 avg_logprob 相同定義)
+    pub avg_logprob: f32,
+    pub tokens: Vec<TokenConfidence>,
+}
+
+/// 合併跨 UTF-8 邊界的 token 片段
+///
+/// 中文字常被拆成多個位元組層級的 token，單獨解碼會得到亂碼；
+/// 將片段累積到能組成完整字元為止，機率取片段中最低者，時間涵蓋所有片段。
+pub fn merge_token_pieces(pieces: &[TokenPiece]) -> Vec<TokenConfidence> {
+    let mut tokens = Vec::new();
+    let mut pending: Vec<u8> = Vec::new();
+    let mut pending_probability = 1.0f32;
+    let mut pending_start = None;
+
+    for piece in pieces {
+        pending.extend_from_slice(&piece.bytes);
+        pending_probability = pending_probability.min(piece.probability);
+        let start = *pending_start.get_or_insert(piece.start);
+        if let Ok(text) = std::str::from_utf8(&pending) {
+            if !text.is_empty() {
+                tokens.push(TokenConfidence {
+                    text: text.to_string(),
+                    probability: pending_probability,
+                    start,
+                    end: piece.end.max(start),
+                });
+            }
+            pending.clear();
+            pending_probability = 1.0;
+            pending_start = None;
+        }
+    }
+    if let (Some(start), Some(last)) = (pending_start, pieces.last()) {
+        tokens.push(TokenConfidence {
+            text: String::from_utf8_lossy(&pending).into_owned(),
+            probability: pending_probability,
+            start,
+            end: last.end.max(start),
+        });
+    }
+    tokens
+}
+
+/// 由段落內的文字 token 計算信心分數；沒有 token 時返回 None
+pub fn segment_confidence(pieces: &[TokenPiece]) -> Option<SegmentConfidence> {
+    if pieces.is_empty() {
+        return None;
+    }
+    let avg_logprob = pieces.iter().map(|piece| piece.logprob).sum::<f32>() / pieces.len() as f32;
+    Some(SegmentConfidence {
+        confidence: avg_logprob.exp().clamp(0.0, 1.0),
+        avg_logprob,
+        tokens: merge_token_pieces(pieces),
+    })
+}
+
+/// 整體信心分數：各段信心依 token 數加權平均 (沒有 token 明細的段落權重為 1)
+pub fn overall_confidence(segments: &[TranscriptSegment]) -> Option<f32> {
+    let (weighted, weight) = segments
+        .iter()
+        .filter_map(|segment| {
+            let confidence = segment.confidence?;
+            let weight = segment.tokens.len().max(1) as f32;
+            Some((confidence * weight, weight))
+        })
+        .fold((0.0f32, 0.0f32), |(sum, total), (value, weight)| (sum + value, total + weight));
+    (weight > 0.0).then(|| weighted / weight)
+}
+
+/// 信心低於門檻的段落
+pub fn is_low_confidence(segment: &TranscriptSegment, threshold: f32) -> bool {
+    segment.confidence.is_some_and(|confidence| confidence < threshold)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn piece(bytes: &[u8], probability: f32) -> TokenPiece {
+        TokenPiece { bytes: bytes.to_vec(), probability, logprob: probability.ln(), start: 0.0, end: 0.0 }
+    }
+
+    fn scored(confidence: Option<f32>, tokens: usize) -> TranscriptSegment {
+        TranscriptSegment {
+            confidence,
+            tokens: vec![TokenConfidence { text: "字".to_string(), probability: 0.5, start: 0.0, end: 0.1 }; tokens],
+            ..TranscriptSegment::fixture(0.0, 1.0, "")
+        }
+    }
+
+
+    #[test]
+    fn test_split_utf8_tokens_are_merged() {
+        // 「藥」= E8 97 A5，被拆成兩個 token
+        let split = |bytes: &[u8], probability, start, end| TokenPiece { start, end, ..piece(bytes, probability) };
+        let pieces = [
+            split(b" ", 0.9, 0.0, 0.1),
+            split(&[0xE8, 0x97], 0.8, 0.1, 0.2),
+            split(&[0xA5], 0.4, 0.2, 0.4),
+            split("吃".as_bytes(), 0.95, 0.4, 0.6),
+        ];
+        let tokens = merge_token_pieces(&pieces);
+
+        let texts: Vec<&str> = tokens.iter().map(|token| token.text.as_str()).collect();
+        assert_eq!(texts, vec![" ", "藥", "吃"]);
+        assert!((tokens[1].probability - 0.4).abs() < 1e-6);
+        assert_eq!((tokens[1].start, tokens[1].end), (0.1, 0.4));
+    }
+
+    #[test]
+    fn test_segment_confidence_is_geometric_mean() {
+        let confidence = segment_confidence(&[piece(b"a", 0.9), piece(b"b", 0.1)]).unwrap();
+        assert!((confidence.confidence - 0.3).abs() < 1e-5);
+        assert!((confidence.avg_logprob - 0.09f32.ln() / 2.0).abs() < 1e-5);
+        assert_eq!(segment_confidence(&[]), None);
+    }
+
+    #[test]
+    fn test_overall_confidence_weights_by_tokens() {
+        let segments = [scored(Some(0.9), 3), scored(Some(0.5), 1), scored(None, 4)];
+        assert!((overall_confidence(&segments).unwrap() - 0.8).abs() < 1e-6);
+        assert_eq!(overall_confidence(&[scored(None, 2)]), None);
+        assert!(is_low_confidence(&segments[1], 0.6));
+        assert!(!is_low_confidence(&segments[2], 0.6));
+    }
+}
@@ -0,0 +1,318 @@
+// ===================================
+// Whisper 解碼策略
+// 各品質等級的取樣方式 (greedy / beam search) 與溫度回退設定；
+// 回退由 whisper.cpp 逐 30 秒視窗執行：熵或平均對數機率未達門檻時提高溫度重新解碼，
+// 解碼回呼記錄各視窗的解碼次數以回報各段實際使用的溫度
+// ===================================
+
+use serde::{Deserialize, Serialize};
+use std::ffi::c_void;
+use std::os::raw::c_int;
+use whisper_rs::whisper_rs_sys::{self, whisper_context, whisper_state, whisper_token_data};
+use whisper_rs::{FullParams, SamplingStrategy};
+
+use crate::whisper_model_pool::TranscriptionQuality;
+
+/// 取樣方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SamplingMode {
+    Greedy,
+    BeamSearch,
+}
+
+/// 單一品質等級的解碼設定
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DecodingProfile {
+    pub sampling: SamplingMode,
+    /// greedy 在溫度 > 0 時取樣的候選數
+    pub best_of: u32,
+    /// beam search 的 beam 寬度
+    pub beam_size: u32,
+    /// beam search 耐心係數，-1 表示使用 whisper.cpp 預設
+    pub patience: f32,
+    /// 初始解碼溫度 (請求的 temperature 參數會覆蓋此值)
+    pub temperature: f32,
+    /// 回退時每次提高的溫度，0 表示停用溫度回退
+    pub temperature_increment: f32,
+    /// token 熵低於此值視為重複輸出而回退 (對應 OpenAI 的 compression_ratio_threshold)
+    pub entropy_threshold: f32,
+    /// 平均對數機率低於此值時回退
+    pub logprob_threshold: f32,
+}
+
+impl Default for DecodingProfile {
+    fn default() -> Self {
+        Self {
+            sampling: SamplingMode::Greedy,
+            best_of: 5,
+            beam_size: 5,
+            patience: -1.0,
+            temperature: 0.0,
+            temperature_increment: 0.2,
+            entropy_threshold: 2.4,
+            logprob_threshold: -1.0,
+        }
+    }
+}
+
+impl DecodingProfile {
+    fn greedy(best_of: u32, temperature: f32, temperature_increment: f32) -> Self {
+        Self { best_of, temperature, temperature_increment, ..Self::default() }
+    }
+
+    fn beam_search(beam_size: u32) -> Self {
+        Self { sampling: SamplingMode::BeamSearch, beam_size, ..Self::default() }
+    }
+
+    pub fn sampling_strategy(&self) -> SamplingStrategy {
+        match self.sampling {
+            SamplingMode::Greedy => SamplingStrategy::Greedy { best_of: self.best_of as i32 },
+            SamplingMode::BeamSearch => SamplingStrategy::BeamSearch {
+                beam_size: self.beam_size as i32,
+                patience: self.patience,
+            },
+        }
+    }
+
+    /// 套用溫度與回退門檻
+    pub fn apply(&self, params: &mut FullParams) {
+        params.set_temperature(self.temperature);
+        params.set_temperature_inc(self.temperature_increment);
+        params.set_entropy_thold(self.entropy_threshold);
+        params.set_logprob_thold(self.logprob_threshold);
+    }
+
+    /// whisper.cpp 依序嘗試的解碼溫度：從初始溫度 (請求的 temperature 會覆蓋) 起每次提高 temperature_increment，最高 1.0
+    pub fn temperatures(&self, requested: Option<f32>) -> Vec<f32> {
+        let initial = requested.unwrap_or(self.temperature);
+        if self.temperature_increment <= 0.0 {
+            return vec![initial];
+        }
+        let temperatures: Vec<f32> = (0..)
+            .map(|step| initial + step as f32 * self.temperature_increment)
+            .take_while(|temperature| *temperature <= 1.0 + 1e-6)
+            .collect();
+        if temperatures.is_empty() { vec![initial] } else { temperatures }
+    }
+
+    /// 驗證設定，錯誤訊息以 name 為前綴
+    pub fn problems(&self, name: &str) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !(1..=8).contains(&self.best_of) {
+            problems.push(format!("{}.best_of 必須介於 1 與 8 之間: {}", name, self.best_of));
+        }
+        if !(1..=8).contains(&self.beam_size) {
+            problems.push(format!("{}.beam_size 必須介於 1 與 8 之間: {}", name, self.beam_size));
+        }
+        if !(0.0..=1.0).contains(&self.temperature) {
+            problems.push(format!("{}.temperature 必須介於 0.0 與 1.0 之間: {}", name, self.temperature));
+        }
+        if !(0.0..=1.0).contains(&self.temperature_increment) {
+            problems.push(format!(
+                "{}.temperature_increment 必須介於 0.0 與 1.0 之間: {}",
+                name, self.temperature_increment
+            ));
+        }
+        if self.logprob_threshold > 0.0 {
+            problems.push(format!("{}.logprob_threshold 不可大於 0: {}", name, self.logprob_threshold));
+        }
+        problems
+    }
+}
+
+/// 記錄 whisper.cpp 各 30 秒視窗的解碼次數，推算各段實際使用的溫度
+///
+/// whisper.cpp 在每個視窗編碼前呼叫 encoder_begin 回呼；每次以新溫度解碼時，
+/// 先對第一個解碼器處理一次尚無 token 的 logits (n_tokens == 0)。
+#[derive(Debug, Default)]
+pub struct TemperatureTrace {
+    /// 各視窗的第一個段落索引與解碼次數
+    windows: Vec<(usize, usize)>,
+}
+
+impl TemperatureTrace {
+    /// 在解碼參數上安裝記錄回呼
+    ///
+    /// # Safety
+    /// 以 `params` 執行的 `full` 結束前，`self` 必須保持有效且不可移動。
+    pub unsafe fn install(&mut self, params: &mut FullParams) {
+        let user_data = self as *mut Self as *mut c_void;
+        params.set_start_encoder_callback(Some(trace_window_start));
+        params.set_start_encoder_callback_user_data(user_data);
+        params.set_filter_logits_callback(Some(trace_decode_attempt));
+        params.set_filter_logits_callback_user_data(user_data);
+    }
+
+    fn window_started(&mut self, first_segment: usize) {
+        self.windows.push((first_segment, 0));
+    }
+
+    fn attempt_started(&mut self) {
+        if let Some((_, attempts)) = self.windows.last_mut() {
+            *attempts += 1;
+        }
+    }
+
+    /// 所有視窗因未達門檻而提高溫度重試的次數
+    pub fn fallback_count(&self) -> usize {
+        self.windows.iter().map(|(_, attempts)| attempts.saturating_sub(1)).sum()
+    }
+
+    /// 第 segment 段所屬視窗最後一次解碼的溫度；schedule 為 [`DecodingProfile::temperatures`]
+    pub fn temperature(&self, segment: usize, schedule: &[f32]) -> f32 {
+        let attempts = self.windows
+            .iter()
+            .rev()
+            .find(|(first_segment, _)| *first_segment <= segment)
+            .map_or(1, |(_, attempts)| *attempts);
+        let index = attempts.saturating_sub(1).min(schedule.len().saturating_sub(1));
+        schedule.get(index).copied().unwrap_or_default()
+    }
+}
+
+/// encoder_begin 回呼：新視窗開始，記錄目前已產生的段落數
+unsafe extern "C" fn trace_window_start(
+    _ctx: *mut whisper_context,
+    state: *mut whisper_state,
+    user_data: *mut c_void,
+) -> bool {
+    // SAFETY: user_data 由 install 設為解碼期間有效的 TemperatureTrace；此回呼在解碼執行緒上單執行緒呼叫
+    let trace = &mut *(user_data as *mut TemperatureTrace);
+    let first_segment = whisper_rs_sys::whisper_full_n_segments_from_state(state);
+    trace.window_started(first_segment.max(0) as usize);
+    true
+}
+
+/// logits 回呼：n_tokens == 0 表示以新溫度開始一次解碼
+unsafe extern "C" fn trace_decode_attempt(
+    _ctx: *mut whisper_context,
+    _state: *mut whisper_state,
+    _tokens: *const whisper_token_data,
+    n_tokens: c_int,
+    _logits: *mut f32,
+    user_data: *mut c_void,
+) {
+    // 其後逐 token 的呼叫可能來自多個解碼執行緒，只在單執行緒的初始呼叫時存取記錄
+    if n_tokens == 0 {
+        // SAFETY: 同 trace_window_start
+        (*(user_data as *mut TemperatureTrace)).attempt_started();
+    }
+}
+
+/// 各品質等級的解碼設定；配置檔中未列出的欄位使用通用預設值
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DecodingConfig {
+    pub turbo: DecodingProfile,
+    pub balanced: DecodingProfile,
+    pub medium: DecodingProfile,
+    pub high_accuracy: DecodingProfile,
+    pub premium: DecodingProfile,
+}
+
+impl Default for DecodingConfig {
+    fn default() -> Self {
+        Self {
+            // 即時應用：單次 greedy，不回退
+            turbo: DecodingProfile::greedy(1, 0.0, 0.0),
+            balanced: DecodingProfile::greedy(2, 0.0, 0.2),
+            // 中文優化：適度提高初始溫度
+            medium: DecodingProfile::greedy(5, 0.1, 0.2),
+            high_accuracy: DecodingProfile::beam_search(5),
+            premium: DecodingProfile::beam_search(8),
+        }
+    }
+}
+
+impl DecodingConfig {
+    pub fn profile(&self, quality: TranscriptionQuality) -> &DecodingProfile {
+        match quality {
+            TranscriptionQuality::Turbo => &self.turbo,
+            TranscriptionQuality::Balanced => &self.balanced,
+            TranscriptionQuality::Medium => &self.medium,
+            TranscriptionQuality::HighAccuracy => &self.high_accuracy,
+            TranscriptionQuality::Premium => &self.premium,
+        }
+    }
+
+    /// 所有品質等級的驗證錯誤
+    pub fn problems(&self) -> Vec<String> {
+        [
+            TranscriptionQuality::Turbo,
+            TranscriptionQuality::Balanced,
+            TranscriptionQuality::Medium,
+            TranscriptionQuality::HighAccuracy,
+            TranscriptionQuality::Premium,
+        ]
+        .into_iter()
+        .flat_map(|quality| self.profile(quality).problems(&format!("decoding.{}", quality.label())))
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_high_quality_tiers_use_beam_search() {
+        let config = DecodingConfig::default();
+        assert!(matches!(
+            config.profile(TranscriptionQuality::Premium).sampling_strategy(),
+            SamplingStrategy::BeamSearch { beam_size: 8, .. }
+        ));
+        assert!(matches!(
+            config.profile(TranscriptionQuality::Turbo).sampling_strategy(),
+            SamplingStrategy::Greedy { best_of: 1 }
+        ));
+        assert_eq!(config.profile(TranscriptionQuality::Turbo).temperature_increment, 0.0);
+        assert!(config.problems().is_empty());
+    }
+
+    #[test]
+    fn test_temperature_fallback_schedule() {
+        let config = DecodingConfig::default();
+        let medium = config.profile(TranscriptionQuality::Medium);
+        let temperatures = medium.temperatures(None);
+        assert_eq!(temperatures.len(), 5);
+        assert!((temperatures[0] - 0.1).abs() < 1e-6 && (temperatures[4] - 0.9).abs() < 1e-6);
+        assert_eq!(medium.temperatures(Some(1.0)), vec![1.0]);
+        assert_eq!(config.profile(TranscriptionQuality::Turbo).temperatures(Some(0.3)), vec![0.3]);
+    }
+
+    #[test]
+    fn test_temperature_trace_maps_segments_to_windows() {
+        let schedule = DecodingProfile::default().temperatures(None);
+        let mut trace = TemperatureTrace::default();
+        // 視窗 0：一次成功，產生段落 0-1
+        trace.window_started(0);
+        trace.attempt_started();
+        // 視窗 1：回退兩次，產生段落 2
+        trace.window_started(2);
+        (0..3).for_each(|_| trace.attempt_started());
+        // 視窗 2：無段落；視窗 3：回退一次，產生段落 3
+        trace.window_started(3);
+        trace.attempt_started();
+        trace.window_started(3);
+        (0..2).for_each(|_| trace.attempt_started());
+
+        let temperatures: Vec<f32> = (0..4).map(|segment| trace.temperature(segment, &schedule)).collect();
+        assert_eq!(temperatures, vec![0.0, 0.0, schedule[2], schedule[1]]);
+        assert_eq!(trace.fallback_count(), 3);
+        assert_eq!(TemperatureTrace::default().temperature(0, &[0.3]), 0.3);
+    }
+
+    #[test]
+    fn test_problems_are_prefixed_by_quality() {
+        let mut config = DecodingConfig::default();
+        config.turbo.temperature_increment = 1.5;
+        config.medium.beam_size = 12;
+
+        let problems = config.problems();
+        assert_eq!(problems.len(), 2, "{:?}", problems);
+        assert!(problems[0].starts_with("decoding.turbo.temperature_increment"));
+        assert!(problems[1].starts_with("decoding.medium.beam_size"));
+    }
+}
@@ -0,0 +1,206 @@
+// ===================================
+// 解碼與轉錄管線錯誤
+// 穩定的錯誤代碼、HTTP 狀態對應與可重試標記
+// ===================================
+
+use axum::{
+    extract::multipart::MultipartError,
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde::Serialize;
+
+/// 可重試錯誤建議的等待秒數
+const RETRY_AFTER_SECONDS: &str = "30";
+
+/// 管線錯誤
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum PipelineError {
+    /// 請求參數或格式錯誤
+    #[error("{0}")]
+    InvalidRequest(String),
+    /// 請求中沒有音頻欄位
+    #[error("未找到音頻數據")]
+    MissingAudio,
+    /// 上傳內容超過大小限制
+    #[error("上傳內容過大: {0}")]
+    PayloadTooLarge(String),
+    /// 音頻長度超過上限
+    #[error("音頻過長: {duration_seconds:.1} 秒 (上限 {max_seconds} 秒)")]
+    AudioTooLong { duration_seconds: f64, max_seconds: u64 },
+    /// 無法識別或尚未支援的音頻格式
+    #[error("不支援的音頻格式: {0}")]
+    UnsupportedFormat(String),
+    /// 已識別格式但容器或編碼資料損壞
+    #[error("音頻資料損壞: {0}")]
+    CorruptContainer(String),
+    /// 解碼後沒有任何音頻樣本
+    #[error("音頻內容為空")]
+    EmptyAudio,
+    /// 找不到指定的資源
+    #[error("{0}")]
+    NotFound(String),
+    /// 非同步任務尚未完成
+    #[error("轉錄任務尚未完成: {0}")]
+    JobNotReady(String),
+    /// 非同步任務已失敗
+    #[error("轉錄任務失敗: {0}")]
+    JobFailed(String),
+    /// 轉錄佇列已滿
+    #[error("轉錄佇列已滿，請稍後重試")]
+    QueueFull,
+    /// 服務正在關閉，不再接受新任務
+    #[error("服務正在關閉，請稍後重試")]
+    ShuttingDown,
+    /// 沒有可用的 Whisper 模型
+    #[error("沒有可用的 Whisper 模型")]
+    ModelUnavailable,
+    /// 解碼器資源無法使用
+    #[error("音頻解碼器無法使用: {0}")]
+    DecoderUnavailable(String),
+    /// 轉錄超時
+    #[error("轉錄超時 ({seconds} 秒)")]
+    Timeout { seconds: u64 },
+    /// 任務在完成前被取消 (逾時、刪除或關閉時放棄)
+    #[error("轉錄任務已取消")]
+    Cancelled,
+    /// Whisper 推理失敗
+    #[error("轉錄失敗: {0}")]
+    TranscriptionFailed(String),
+}
+
+impl PipelineError {
+    /// 穩定的機器可讀錯誤代碼
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidRequest(_) => "invalid_request",
+            Self::MissingAudio => "missing_audio",
+            Self::PayloadTooLarge(_) => "payload_too_large",
+            Self::AudioTooLong { .. } => "audio_too_long",
+            Self::UnsupportedFormat(_) => "unsupported_format",
+            Self::CorruptContainer(_) => "corrupt_container",
+            Self::EmptyAudio => "empty_audio",
+            Self::NotFound(_) => "not_found",
+            Self::JobNotReady(_) => "job_not_ready",
+            Self::JobFailed(_) => "job_failed",
+            Self::QueueFull => "queue_full",
+            Self::ShuttingDown => "shutting_down",
+            Self::ModelUnavailable => "model_unavailable",
+            Self::DecoderUnavailable(_) => "decoder_unavailable",
+            Self::Timeout { .. } => "timeout",
+            Self::Cancelled => "cancelled",
+            Self::TranscriptionFailed(_) => "transcription_failed",
+        }
+    }
+
+    /// 對應的 HTTP 狀態碼
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::InvalidRequest(_) | Self::MissingAudio => StatusCode::BAD_REQUEST,
+            Self::PayloadTooLarge(_) | Self::AudioTooLong { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::UnsupportedFormat(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::CorruptContainer(_) | Self::EmptyAudio => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::JobNotReady(_) | Self::JobFailed(_) => StatusCode::CONFLICT,
+            Self::QueueFull
+            | Self::ShuttingDown
+            | Self::ModelUnavailable
+            | Self::DecoderUnavailable(_)
+            | Self::Cancelled => StatusCode::SERVICE_UNAVAILABLE,
+            Self::Timeout { .. } => StatusCode::GATEWAY_TIMEOUT,
+            Self::TranscriptionFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// 相同請求稍後重試是否可能成功
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::JobNotReady(_)
+                | Self::QueueFull
+                | Self::ShuttingDown
+                | Self::ModelUnavailable
+                | Self::DecoderUnavailable(_)
+                | Self::Timeout { .. }
+                | Self::Cancelled
+        )
+    }
+
+    /// 讀取 multipart 失敗 (超過請求大小限制時為 413)
+    pub fn from_multipart(e: MultipartError) -> Self {
+        if e.status() == StatusCode::PAYLOAD_TOO_LARGE {
+            Self::PayloadTooLarge(e.body_text())
+        } else {
+            Self::InvalidRequest(format!("無效的 multipart 資料: {}", e.body_text()))
+        }
+    }
+}
+
+/// API 錯誤回應
+#[derive(Debug, Serialize)]
+pub struct ErrorResponse {
+    pub error: String,
+    /// 穩定的機器可讀錯誤代碼
+    pub code: &'static str,
+    /// 稍後重試是否可能成功
+    pub retryable: bool,
+}
+
+impl From<&PipelineError> for ErrorResponse {
+    fn from(e: &PipelineError) -> Self {
+        Self {
+            error: e.to_string(),
+            code: e.code(),
+            retryable: e.is_retryable(),
+        }
+    }
+}
+
+impl IntoResponse for PipelineError {
+    fn into_response(self) -> Response {
+        let body = Json(ErrorResponse::from(&self));
+        let status = self.status();
+        if status == StatusCode::SERVICE_UNAVAILABLE {
+            (status, [(header::RETRY_AFTER, RETRY_AFTER_SECONDS)], body).into_response()
+        } else {
+            (status, body).into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_mapping() {
+        assert_eq!(PipelineError::UnsupportedFormat("flac".into()).status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(PipelineError::CorruptContainer("webm".into()).status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(PipelineError::EmptyAudio.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            PipelineError::AudioTooLong { duration_seconds: 4000.0, max_seconds: 3600 }.status(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+        assert_eq!(PipelineError::QueueFull.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(PipelineError::Timeout { seconds: 90 }.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(PipelineError::TranscriptionFailed("x".into()).status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn test_retryable_only_for_transient_errors() {
+        assert!(PipelineError::QueueFull.is_retryable());
+        assert!(PipelineError::Timeout { seconds: 90 }.is_retryable());
+        assert!(PipelineError::ShuttingDown.is_retryable());
+        assert!(!PipelineError::EmptyAudio.is_retryable());
+        assert!(!PipelineError::CorruptContainer("ogg".into()).is_retryable());
+        assert!(!PipelineError::InvalidRequest("bad".into()).is_retryable());
+    }
+
+    #[test]
+    fn test_error_response_body() {
+        let body = serde_json::to_value(ErrorResponse::from(&PipelineError::QueueFull)).unwrap();
+        assert_eq!(body["code"], "queue_full");
+        assert_eq!(body["retryable"], true);
+        assert_eq!(body["error"], "轉錄佇列已滿，請稍後重試");
+    }
+}
@@ -10,9 +10,11 @@ use parking_lot::Mutex;
 use tracing::{info, warn, debug};
 use anyhow::{Result, Context};
 use metrics::{counter, histogram, gauge};
+use serde::{Deserialize, Serialize};
 
 /// GPU 記憶體池配置
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct GpuMemoryConfig {
     /// 預分配記憶體大小 (MB)
     pub pre_allocated_mb: usize,
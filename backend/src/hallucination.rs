@@ -0,0 +1,329 @@
+// ===================================
+// Whisper 幻覺過濾
+// 靜音尾段常出現「謝謝觀看」等訓練資料殘留語句或同一句重複數十次；
+// 轉錄後依已知語句、重複迴圈、段落音量與文字密度標記或移除可疑段落，並回報移除內容
+// ===================================
+
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use whisper_rs::FullParams;
+
+use crate::whisper_model_pool::TranscriptSegment;
+
+/// 段落內重複迴圈的單位最大字元數
+const MAX_LOOP_UNIT_CHARS: usize = 12;
+/// 重複迴圈需佔段落文字的比例
+const LOOP_COVERAGE_RATIO: f32 = 0.5;
+
+/// 偵測到幻覺時的處理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HallucinationAction {
+    /// 從結果移除並列於 filtered_segments
+    Remove,
+    /// 保留段落並標記 hallucination 原因，由審閱者判斷
+    Flag,
+}
+
+/// 幻覺過濾配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HallucinationConfig {
+    pub enabled: bool,
+    pub action: HallucinationAction,
+    /// 交給 whisper.cpp 的 no-speech 機率門檻：高於此值且平均對數機率低於 logprob 門檻的視窗不輸出文字
+    pub no_speech_threshold: f32,
+    /// 已知的幻覺語句 (比對時忽略標點、空白與大小寫)
+    pub phrases: Vec<String>,
+    /// 相同文字的段落連續出現超過此次數時，只保留第一段
+    pub max_repeated_segments: usize,
+    /// 段落內同一片段連續重複超過此次數視為重複迴圈
+    pub max_phrase_repeats: usize,
+    /// 段落音頻的最大幀能量低於此值 (dBFS) 時視為靜音
+    pub silence_threshold_db: f32,
+    /// 每秒字元數上限 (不足 1 秒的段落以 1 秒計)
+    pub max_chars_per_second: f32,
+}
+
+impl Default for HallucinationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            action: HallucinationAction::Remove,
+            no_speech_threshold: 0.6,
+            phrases: [
+                "謝謝觀看",
+                "谢谢观看",
+                "謝謝收看",
+                "谢谢收看",
+                "感謝觀看",
+                "請不吝點贊訂閱轉發打賞支持明鏡與點點欄目",
+                "请不吝点赞订阅转发打赏支持明镜与点点栏目",
+                "字幕由Amara.org社區提供",
+                "字幕由Amara.org社区提供",
+                "Thanks for watching",
+                "Thank you for watching",
+                "Subtitles by the Amara.org community",
+                "ご視聴ありがとうございました",
+            ]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+            max_repeated_segments: 3,
+            max_phrase_repeats: 10,
+            silence_threshold_db: -55.0,
+            max_chars_per_second: 25.0,
+        }
+    }
+}
+
+impl HallucinationConfig {
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !(0.0..=1.0).contains(&self.no_speech_threshold) {
+            problems.push(format!("hallucination.no_speech_threshold 必須介於 0.0 與 1.0 之間: {}", self.no_speech_threshold));
+        }
+        if self.phrases.iter().any(|phrase| normalize(phrase).is_empty()) {
+            problems.push("hallucination.phrases 不可包含空白或只有標點的語句".to_string());
+        }
+        if self.max_repeated_segments == 0 {
+            problems.push("hallucination.max_repeated_segments 必須大於 0".to_string());
+        }
+        if self.max_phrase_repeats < 2 {
+            problems.push(format!("hallucination.max_phrase_repeats 必須至少為 2: {}", self.max_phrase_repeats));
+        }
+        if !(-90.0..=0.0).contains(&self.silence_threshold_db) {
+            problems.push(format!("hallucination.silence_threshold_db 必須介於 -90 與 0 之間: {}", self.silence_threshold_db));
+        }
+        if self.max_chars_per_second <= 0.0 {
+            problems.push(format!("hallucination.max_chars_per_second 必須大於 0: {}", self.max_chars_per_second));
+        }
+        problems
+    }
+}
+
+/// 判定為幻覺的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HallucinationReason {
+    /// 只包含已知的幻覺語句
+    KnownPhrase,
+    /// 段落內同一片段不斷重複
+    RepetitionLoop,
+    /// 與前面段落的文字完全相同且連續出現過多次
+    RepeatedSegment,
+    /// 段落音頻幾乎沒有能量
+    Silence,
+    /// 文字量遠超過段落長度可能說出的內容
+    TextTooDense,
+}
+
+impl HallucinationReason {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::KnownPhrase => "known_phrase",
+            Self::RepetitionLoop => "repetition_loop",
+            Self::RepeatedSegment => "repeated_segment",
+            Self::Silence => "silence",
+            Self::TextTooDense => "text_too_dense",
+        }
+    }
+}
+
+/// 被移除的段落
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FilteredSegment {
+    pub start_time: f32,
+    pub end_time: f32,
+    pub text: String,
+    pub reason: HallucinationReason,
+}
+
+/// 只保留文字與數字並轉為小寫，用於比對
+fn normalize(text: &str) -> String {
+    text.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+/// 段落內是否有同一片段連續重複超過 max_repeats 次且佔大部分文字
+fn has_repetition_loop(text: &str, max_repeats: usize) -> bool {
+    let chars: Vec<char> = text.chars().collect();
+    for unit in 1..=MAX_LOOP_UNIT_CHARS.min(chars.len() / 2) {
+        let mut start = 0;
+        while start + unit <= chars.len() {
+            let pattern = &chars[start..start + unit];
+            let repeats = chars[start..].chunks_exact(unit).take_while(|chunk| *chunk == pattern).count();
+            if repeats > max_repeats && (repeats * unit) as f32 >= chars.len() as f32 * LOOP_COVERAGE_RATIO {
+                return true;
+            }
+            start += 1;
+        }
+    }
+    false
+}
+
+/// 幻覺過濾器
+#[derive(Debug, Clone)]
+pub struct HallucinationFilter {
+    config: HallucinationConfig,
+    phrases: Vec<String>,
+}
+
+impl HallucinationFilter {
+    pub fn new(config: &HallucinationConfig) -> Self {
+        let mut phrases: Vec<String> = config.phrases.iter().map(|phrase| normalize(phrase)).collect();
+        // 較長的語句先移除，避免被較短的語句拆開
+        phrases.sort_by_key(|phrase| std::cmp::Reverse(phrase.chars().count()));
+        Self { config: config.clone(), phrases }
+    }
+
+    /// whisper.cpp 解碼時的 no-speech 門檻
+    ///
+    /// whisper-rs 0.14 無法從 WhisperState 讀取各段的 no-speech 機率，
+    /// 由 whisper.cpp 在解碼時略過 no-speech 機率過高的視窗。
+    pub fn apply_params(&self, params: &mut FullParams) {
+        if self.config.enabled {
+            params.set_no_speech_thold(self.config.no_speech_threshold);
+        }
+    }
+
+    /// 單一段落的判定；peak_energy_db 為段落音頻的最大幀能量 (音頻過短時為 None)
+    fn check(&self, segment: &TranscriptSegment, text: &str, peak_energy_db: Option<f32>) -> Option<HallucinationReason> {
+        let mut remainder = text.to_string();
+        for phrase in &self.phrases {
+            remainder = remainder.replace(phrase.as_str(), "");
+        }
+        if remainder.is_empty() {
+            return Some(HallucinationReason::KnownPhrase);
+        }
+        if has_repetition_loop(text, self.config.max_phrase_repeats) {
+            return Some(HallucinationReason::RepetitionLoop);
+        }
+        if peak_energy_db.is_some_and(|energy| energy < self.config.silence_threshold_db) {
+            return Some(HallucinationReason::Silence);
+        }
+        let duration = (segment.end_time - segment.start_time).max(1.0);
+        if text.chars().count() as f32 > self.config.max_chars_per_second * duration {
+            return Some(HallucinationReason::TextTooDense);
+        }
+        None
+    }
+
+    /// 過濾段落，返回保留的段落與被移除的段落
+    ///
+    /// peak_energy_db 與 segments 一一對應；標記模式下不移除任何段落。
+    pub fn apply(
+        &self,
+        segments: Vec<TranscriptSegment>,
+        peak_energy_db: &[Option<f32>],
+    ) -> (Vec<TranscriptSegment>, Vec<FilteredSegment>) {
+        if !self.config.enabled {
+            return (segments, Vec::new());
+        }
+
+        let texts: Vec<String> = segments.iter().map(|segment| normalize(&segment.text)).collect();
+        let mut reasons: Vec<Option<HallucinationReason>> = segments
+            .iter()
+            .zip(&texts)
+            .enumerate()
+            .map(|(index, (segment, text))| {
+                if text.is_empty() {
+                    return None;
+                }
+                self.check(segment, text, peak_energy_db.get(index).copied().flatten())
+            })
+            .collect();
+
+        // 非空段落中連續相同文字過多時，整串只保留第一段
+        let non_empty: Vec<usize> = (0..texts.len()).filter(|&index| !texts[index].is_empty()).collect();
+        for run in non_empty.chunk_by(|&a, &b| texts[a] == texts[b]) {
+            if run.len() > self.config.max_repeated_segments {
+                for &index in &run[1..] {
+                    reasons[index].get_or_insert(HallucinationReason::RepeatedSegment);
+                }
+            }
+        }
+
+        let mut kept = Vec::with_capacity(segments.len());
+        let mut filtered = Vec::new();
+        for (mut segment, reason) in segments.into_iter().zip(reasons) {
+            let Some(reason) = reason else {
+                kept.push(segment);
+                continue;
+            };
+            counter!("whisper_hallucinations_total", "reason" => reason.label()).increment(1);
+            match self.config.action {
+                HallucinationAction::Flag => {
+                    segment.hallucination = Some(reason);
+                    kept.push(segment);
+                },
+                HallucinationAction::Remove => {
+                    warn!("👻 移除疑似幻覺段落 ({}): {:?}", reason.label(), segment.text.trim());
+                    filtered.push(FilteredSegment {
+                        start_time: segment.start_time,
+                        end_time: segment.end_time,
+                        text: segment.text.trim().to_string(),
+                        reason,
+                    });
+                },
+            }
+        }
+        (kept, filtered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(segments: &[TranscriptSegment]) -> Vec<&str> {
+        segments.iter().map(|segment| segment.text.trim()).collect()
+    }
+
+    #[test]
+    fn test_known_phrases_and_silence_are_removed() {
+        let filter = HallucinationFilter::new(&HallucinationConfig::default());
+        let segments = vec![
+            TranscriptSegment::fixture(0.0, 2.0, " 今天血壓正常"),
+            TranscriptSegment::fixture(2.0, 4.0, " 記得吃藥"),
+            TranscriptSegment::fixture(4.0, 6.0, " 謝謝觀看！ Thanks for watching."),
+        ];
+        let (kept, filtered) = filter.apply(segments, &[Some(-20.0), Some(-70.0), Some(-20.0)]);
+
+        assert_eq!(texts(&kept), vec!["今天血壓正常"]);
+        assert_eq!(filtered.len(), 2);
+        assert_eq!(filtered[0].reason, HallucinationReason::Silence);
+        assert_eq!(filtered[1].reason, HallucinationReason::KnownPhrase);
+        assert_eq!(filtered[1].text, "謝謝觀看！ Thanks for watching.");
+    }
+
+    #[test]
+    fn test_repetition_loops_and_repeated_segments() {
+        let filter = HallucinationFilter::new(&HallucinationConfig::default());
+        let mut segments = vec![TranscriptSegment::fixture(0.0, 2.0, "好"), TranscriptSegment::fixture(2.0, 3.0, "好"), TranscriptSegment::fixture(3.0, 4.0, "好")];
+        // 3 段相同文字在上限內
+        let (kept, _) = filter.apply(segments.clone(), &[]);
+        assert_eq!(kept.len(), 3);
+
+        segments.extend((4..10).map(|second| TranscriptSegment::fixture(second as f32, second as f32 + 1.0, " 好。")));
+        segments.push(TranscriptSegment::fixture(10.0, 30.0, &"我們再試一次".repeat(12)));
+        segments.push(TranscriptSegment::fixture(30.0, 32.0, "哈哈哈，好的"));
+        let (kept, filtered) = filter.apply(segments, &[]);
+
+        assert_eq!(texts(&kept), vec!["好", "哈哈哈，好的"]);
+        assert_eq!(filtered.iter().filter(|f| f.reason == HallucinationReason::RepeatedSegment).count(), 8);
+        assert_eq!(filtered.last().unwrap().reason, HallucinationReason::RepetitionLoop);
+    }
+
+    #[test]
+    fn test_flag_mode_keeps_segments() {
+        let config = HallucinationConfig { action: HallucinationAction::Flag, ..HallucinationConfig::default() };
+        let filter = HallucinationFilter::new(&config);
+        let (kept, filtered) = filter.apply(vec![TranscriptSegment::fixture(0.0, 0.5, &"今天血壓正常記得吃藥".repeat(3)), TranscriptSegment::fixture(1.0, 2.0, "謝謝收看")], &[]);
+
+        assert!(filtered.is_empty());
+        assert_eq!(kept[0].hallucination, Some(HallucinationReason::TextTooDense));
+        assert_eq!(kept[1].hallucination, Some(HallucinationReason::KnownPhrase));
+        assert!(config.problems().is_empty());
+    }
+}
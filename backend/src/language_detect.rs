@@ -0,0 +1,141 @@
+// ===================================
+// 語言識別
+// 將 Whisper 語言偵測的機率分佈整理為可回傳的結果，
+// 支援限定候選語言 (例如 zh / en / ja) 並合併多個區塊或聲道的偵測結果
+// ===================================
+
+use serde::Serialize;
+
+/// 回應中保留的語言機率筆數
+const MAX_REPORTED_LANGUAGES: usize = 5;
+
+/// 單一語言的機率
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LanguageProbability {
+    pub language: String,
+    pub probability: f32,
+}
+
+/// 語言偵測結果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LanguageDetection {
+    /// 偵測到的語言代碼
+    pub language: String,
+    /// 偵測語言的機率
+    pub probability: f32,
+    /// 機率最高的語言 (限定候選時為各候選語言)，由高到低排序
+    pub probabilities: Vec<LanguageProbability>,
+}
+
+impl LanguageDetection {
+    /// 由 Whisper 語言 id 索引的機率分佈建立結果
+    ///
+    /// candidates 非空時只在候選語言間比較，並將機率重新正規化。
+    pub fn from_probabilities(probabilities: &[f32], candidates: &[String]) -> Option<Self> {
+        let ranked = probabilities
+            .iter()
+            .enumerate()
+            .filter_map(|(id, &probability)| {
+                let language = whisper_rs::get_lang_str(id as i32)?;
+                Some(LanguageProbability { language: language.to_string(), probability })
+            })
+            .filter(|entry| candidates.is_empty() || candidates.contains(&entry.language))
+            .collect();
+        Self::from_ranked(ranked, !candidates.is_empty())
+    }
+
+    /// 合併多個偵測結果 (依權重加權平均機率，權重通常為音頻長度)
+    pub fn combine(detections: &[(LanguageDetection, f32)]) -> Option<Self> {
+        let total_weight: f32 = detections.iter().map(|(_, weight)| weight).sum();
+        if total_weight <= 0.0 {
+            return None;
+        }
+
+        let mut combined: Vec<LanguageProbability> = Vec::new();
+        for (detection, weight) in detections {
+            for entry in &detection.probabilities {
+                let share = entry.probability * weight / total_weight;
+                match combined.iter_mut().find(|existing| existing.language == entry.language) {
+                    Some(existing) => existing.probability += share,
+                    None => combined.push(LanguageProbability { language: entry.language.clone(), probability: share }),
+                }
+            }
+        }
+        Self::from_ranked(combined, false)
+    }
+
+    fn from_ranked(mut ranked: Vec<LanguageProbability>, normalize: bool) -> Option<Self> {
+        if normalize {
+            let total: f32 = ranked.iter().map(|entry| entry.probability).sum();
+            if total > 0.0 {
+                ranked.iter_mut().for_each(|entry| entry.probability /= total);
+            }
+        }
+        ranked.sort_by(|a, b| b.probability.total_cmp(&a.probability));
+        ranked.truncate(MAX_REPORTED_LANGUAGES);
+
+        let top = ranked.first()?;
+        Some(Self {
+            language: top.language.clone(),
+            probability: top.probability,
+            probabilities: ranked,
+        })
+    }
+}
+
+/// 解析逗號分隔的候選語言清單，例如 "zh,en,ja"
+pub fn parse_candidates(value: &str) -> Result<Vec<String>, String> {
+    let mut candidates = Vec::new();
+    for code in value.split([',', ' ', '/']).map(str::trim).filter(|code| !code.is_empty()) {
+        let code = code.to_ascii_lowercase();
+        if whisper_rs::get_lang_id(&code).is_none() {
+            return Err(format!("不支援的候選語言代碼: {}", code));
+        }
+        if !candidates.contains(&code) {
+            candidates.push(code);
+        }
+    }
+    Ok(candidates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn distribution(entries: &[(&str, f32)]) -> Vec<f32> {
+        let mut probabilities = vec![0.0; (whisper_rs::get_lang_max_id() + 1) as usize];
+        for &(language, probability) in entries {
+            probabilities[whisper_rs::get_lang_id(language).unwrap() as usize] = probability;
+        }
+        probabilities
+    }
+
+    #[test]
+    fn test_detects_most_probable_language() {
+        let detection = LanguageDetection::from_probabilities(&distribution(&[("en", 0.7), ("zh", 0.2), ("ja", 0.1)]), &[]).unwrap();
+        assert_eq!(detection.language, "en");
+        assert!((detection.probability - 0.7).abs() < 1e-6);
+        assert_eq!(detection.probabilities.len(), MAX_REPORTED_LANGUAGES);
+        assert_eq!(detection.probabilities[1].language, "zh");
+    }
+
+    #[test]
+    fn test_candidates_restrict_and_renormalize() {
+        let candidates = parse_candidates("zh, ja").unwrap();
+        let detection = LanguageDetection::from_probabilities(&distribution(&[("en", 0.7), ("zh", 0.1), ("ja", 0.2)]), &candidates).unwrap();
+        assert_eq!(detection.language, "ja");
+        assert_eq!(detection.probabilities.len(), 2);
+        assert!((detection.probability - 2.0 / 3.0).abs() < 1e-5);
+        assert!(parse_candidates("zh,klingon").is_err());
+    }
+
+    #[test]
+    fn test_combine_weights_by_duration() {
+        let zh = LanguageDetection::from_probabilities(&distribution(&[("zh", 0.9), ("en", 0.1)]), &[]).unwrap();
+        let en = LanguageDetection::from_probabilities(&distribution(&[("en", 0.8), ("zh", 0.2)]), &[]).unwrap();
+        let combined = LanguageDetection::combine(&[(zh, 30.0), (en, 10.0)]).unwrap();
+        assert_eq!(combined.language, "zh");
+        assert!((combined.probability - (0.9 * 0.75 + 0.2 * 0.25)).abs() < 1e-5);
+        assert_eq!(LanguageDetection::combine(&[]), None);
+    }
+}
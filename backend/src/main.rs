@@ -19,23 +19,24 @@ static GLOBAL: Jemalloc = Jemalloc;
 static GLOBAL: MiMalloc = MiMalloc;
 
 use axum::{
-    extract::{Multipart, State},
-    http::StatusCode,
-    response::Json,
+    extract::{DefaultBodyLimit, Multipart, Request, State},
+    http::{header, HeaderMap},
+    middleware::{self, Next},
+    response::{IntoResponse, Json, Response},
     routing::{get, post},
     Router,
 };
 use serde::Serialize;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
-use tracing::{info, error, warn, span, Level};
+use tracing::{debug, info, error, warn, span, Level};
 
 // 現代化並行處理 (暫時停用)
 use parking_lot::RwLock;
 
 // 效能監控
-use metrics::{counter, histogram, gauge};
-use std::time::Instant;
+use metrics::{counter, histogram};
+use std::time::{Duration, Instant};
 
 // GPU 計算 (條件編譯)
 #[cfg(feature = "cuda")]
@@ -49,19 +50,64 @@ use uuid::Uuid;
 // ===================================
 mod audio_format;
 mod opus_decoder;
+mod webm_demuxer;
+mod ogg_demuxer;
 mod audio_decoder;
+mod resampler;
+mod vad;
+
+// 應用程式配置
+mod app_config;
+
+// 管線錯誤類型
+mod error;
 
 // 多模型處理架構
 mod whisper_model_pool;
+mod chunker;
+mod decoding;
+mod confidence;
+mod word_timing;
+mod language_detect;
+mod bilingual;
+mod vocabulary;
+mod hallucination;
+mod model_registry;
 mod gpu_memory_manager;
 
-// WebSocket 即時轉錄模組 (暫時移除)
-// mod websocket_handler;
+// 非同步轉錄任務 API
+mod transcription_jobs;
+
+// 上傳請求選項
+mod upload_options;
+
+// 轉錄結果輸出格式 (SRT / WebVTT)
+mod transcript_render;
+
+// OpenAI 相容 API
+mod openai_compat;
+
+// WebSocket 即時轉錄模組
+mod websocket_handler;
+
+// Prometheus 指標匯出 (可選)
+#[cfg(feature = "observability")]
+mod observability;
 
+use app_config::AppConfig;
+use error::PipelineError;
 use audio_format::AudioFormat;
-use audio_decoder::UnifiedAudioDecoder;
+use audio_decoder::{ChannelMode, DecodedAudio, UnifiedAudioDecoder};
 // opus_decoder 支援 (按需導入)
-use whisper_model_pool::{WhisperModelPool, TranscriptionQuality};
+use whisper_model_pool::{WhisperModelPool, TranscriptionOptions};
+use confidence::TokenConfidence;
+use word_timing::WordTiming;
+use language_detect::LanguageDetection;
+use bilingual::AlignedSegment;
+use hallucination::{FilteredSegment, HallucinationReason};
+use transcription_jobs::JobStore;
+use upload_options::UploadOptions;
+use transcript_render::TranscriptFormat;
 
 #[cfg(feature = "cuda")]
 use gpu_memory_manager::GpuMemoryManager;
@@ -79,11 +125,6 @@ struct TranscriptResponse {
     summary: String,
 }
 
-#[derive(Serialize)]
-struct ErrorResponse {
-    error: String,
-}
-
 // ===================================
 // 業界領先 AI 語音服務架構
 // ===================================
@@ -95,6 +136,15 @@ struct WhisperService {
     gpu_manager: Option<Arc<GpuMemoryManager>>,
     audio_decoder: Arc<UnifiedAudioDecoder>,
     service_stats: Arc<RwLock<ServiceStats>>,
+    job_store: Arc<JobStore>,
+    /// 逐聲道轉錄時各聲道的說話者標籤
+    channel_speakers: Vec<String>,
+    /// 段落信心低於此值時在回應中標記 low_confidence
+    low_confidence_threshold: f32,
+    /// 共用與各機構的專有詞彙
+    vocabulary: vocabulary::VocabularyConfig,
+    /// 上傳端點的請求大小上限 (位元組)
+    max_upload_bytes: usize,
 }
 
 /// 服務統計資料
@@ -117,16 +167,68 @@ struct EnhancedTranscriptResponse {
     processing_time_ms: u64,
     model_used: String,
     audio_format: String,
+    /// 解碼後、重採樣到 16kHz 前的原始採樣率
+    source_sample_rate: u32,
+    /// 轉錄使用的語言 (指定或自動偵測)
+    language: Option<String>,
+    /// 自動偵測語言時的偵測結果與各語言機率
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detected_language: Option<LanguageDetection>,
     segments: Vec<TranscriptSegmentResponse>,
+    /// 幻覺過濾移除的段落與原因
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    filtered_segments: Vec<FilteredSegment>,
+    /// 雙語輸出時的英文翻譯
+    #[serde(skip_serializing_if = "Option::is_none")]
+    translation: Option<TranslationResponse>,
     service_info: ServiceInfo,
 }
 
+/// 上傳轉錄的輸出
+enum TranscriptOutput {
+    /// JSON 增強回應
+    Enhanced(Box<EnhancedTranscriptResponse>),
+    /// 直接由模型段落渲染的字幕/文件
+    Rendered(TranscriptFormat, String),
+}
+
+/// 雙語輸出的英文翻譯
+#[derive(Serialize)]
+struct TranslationResponse {
+    transcript: String,
+    confidence: Option<f32>,
+    segments: Vec<TranscriptSegmentResponse>,
+    /// 依時間對齊的原文 / 譯文段落
+    aligned: Vec<AlignedSegment>,
+}
+
 #[derive(Serialize)]
 struct TranscriptSegmentResponse {
     start_time: f32,
     end_time: f32,
     text: String,
     confidence: Option<f32>,
+    /// 信心低於配置門檻，建議人工確認
+    low_confidence: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    avg_logprob: Option<f32>,
+    /// 產生此段的解碼溫度 (含溫度回退)
+    temperature: f32,
+    /// 逐 token 信心，供前端標示低信心字詞
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tokens: Vec<TokenConfidence>,
+    /// 逐詞時間戳，供播放器對齊
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    words: Vec<WordTiming>,
+    /// 疑似幻覺 (幻覺過濾為標記模式時)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hallucination: Option<HallucinationReason>,
+    /// 逐聲道轉錄時的來源聲道
+    #[serde(skip_serializing_if = "Option::is_none")]
+    channel: Option<usize>,
+    /// 逐聲道轉錄時的說話者標籤
+    #[serde(skip_serializing_if = "Option::is_none")]
+    speaker: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -139,7 +241,7 @@ struct ServiceInfo {
 
 impl WhisperService {
     /// 創建業界領先的 AI 語音服務
-    async fn new() -> Result<Self, Box<dyn std::error::Error>> {
+    async fn new(config: &AppConfig) -> Result<Self, Box<dyn std::error::Error>> {
         let span = span!(Level::INFO, "whisper_service_initialization");
         let _enter = span.enter();
 
@@ -179,11 +281,11 @@ impl WhisperService {
         }
         
         // 檢測模型路徑
-        let model_base_path = std::env::var("MODEL_PATH").unwrap_or_else(|_| "./models".to_string());
+        let model_base_path = &config.whisper.model_path;
         println!("📁 模型基礎路徑: {}", model_base_path);
         if !std::path::Path::new(&model_base_path).exists() {
             println!("⚠️  警告: 模型路徑不存在，將嘗試創建");
-            std::fs::create_dir_all(&model_base_path)?;
+            std::fs::create_dir_all(model_base_path)?;
         } else {
             println!("✅ 模型路徑存在");
         }
@@ -224,10 +326,9 @@ impl WhisperService {
         let init_start = Instant::now();
         
         // 初始化模型池
-        let model_base_path = std::env::var("MODEL_PATH").unwrap_or_else(|_| "./models".to_string());
         info!("📁 模型基礎路徑: {}", model_base_path);
         
-        let model_pool = match WhisperModelPool::new(&model_base_path) {
+        let model_pool = match WhisperModelPool::new(&config.whisper, &config.vad, &config.decoding, &config.hallucination) {
             Ok(pool) => {
                 info!("✅ Whisper 模型池初始化成功");
                 Arc::new(pool)
@@ -241,12 +342,10 @@ impl WhisperService {
         // 初始化 GPU 記憶體管理器 (智能降級)
         #[cfg(feature = "cuda")]
         let gpu_manager = {
-            use crate::gpu_memory_manager::GpuMemoryConfig;
-            
             println!("🔧 正在初始化 GPU 記憶體管理器...");
             info!("🔧 正在初始化 GPU 記憶體管理器...");
             
-            match GpuMemoryManager::new(GpuMemoryConfig::default()) {
+            match GpuMemoryManager::new(config.gpu.clone()) {
                 Ok(manager) => {
                     println!("✅ GPU 記憶體管理器初始化成功 - 使用 GPU 加速模式");
                     info!("✅ GPU 記憶體管理器初始化成功");
@@ -263,12 +362,16 @@ impl WhisperService {
 
         // 初始化業界領先統一音頻解碼器
         println!("🎵 正在初始化業界領先音頻解碼器...");
-        let audio_decoder = Arc::new(UnifiedAudioDecoder::new()?);
+        let audio_decoder = Arc::new(UnifiedAudioDecoder::with_config(&config.audio)?);
         info!("✅ 業界領先統一音頻解碼器初始化完成 (OPUS 支援)");
 
         // 初始化服務統計
         let service_stats = Arc::new(RwLock::new(ServiceStats::default()));
 
+        // 初始化非同步任務儲存區
+        let job_store = Arc::new(JobStore::with_retention(config.jobs.retention()));
+        job_store.spawn_purger();
+
         let init_time = init_start.elapsed();
         
         // 記錄初始化指標
@@ -284,33 +387,174 @@ impl WhisperService {
             gpu_manager,
             audio_decoder,
             service_stats,
+            job_store,
+            channel_speakers: config.audio.channel_speakers.clone(),
+            low_confidence_threshold: config.whisper.low_confidence_threshold,
+            vocabulary: config.vocabulary.clone(),
+            max_upload_bytes: config.server.max_upload_bytes,
         })
     }
+
+    /// 將請求詞彙與機構、共用詞彙合併後寫入轉錄選項
+    fn apply_vocabulary(&self, options: &mut UploadOptions) -> Result<(), PipelineError> {
+        let terms = &mut options.transcription.vocabulary;
+        *terms = self.vocabulary
+            .resolve(options.organization.as_deref(), terms)
+            .map_err(PipelineError::InvalidRequest)?;
+        Ok(())
+    }
+
+    /// 聲道的說話者標籤，未配置的聲道以編號命名
+    fn speaker_label(&self, channel: usize) -> String {
+        self.channel_speakers
+            .get(channel)
+            .cloned()
+            .unwrap_or_else(|| format!("channel {}", channel))
+    }
+
+    /// 逐聲道轉錄：各聲道同時送入模型池，完成後合併為標記說話者的單一時間軸
+    async fn transcribe_channels(
+        &self,
+        channels: Vec<Vec<f32>>,
+        options: TranscriptionOptions,
+        task_ids: &[Uuid],
+        timeout: Option<Duration>,
+    ) -> Result<whisper_model_pool::TranscriptionResult, PipelineError> {
+        info!("🎧 逐聲道轉錄: {} 個聲道", channels.len());
+        counter!("channel_transcriptions_total").increment(1);
+        histogram!("channel_transcription_channels").record(channels.len() as f64);
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for ((channel, samples), &task_id) in channels.into_iter().enumerate().zip(task_ids) {
+            let model_pool = self.model_pool.clone();
+            let options = options.clone();
+            tasks.spawn(async move {
+                (channel, model_pool.transcribe_chunked(task_id, samples, options, timeout).await)
+            });
+        }
+
+        let mut results = Vec::with_capacity(tasks.len());
+        while let Some(joined) = tasks.join_next().await {
+            let (channel, outcome) = joined.map_err(|e| {
+                PipelineError::TranscriptionFailed(format!("聲道轉錄任務中止: {}", e))
+            })?;
+            let result = outcome.inspect_err(|e| error!("❌ 聲道 {} 轉錄失敗: {}", channel, e))?;
+            debug!("聲道 {} 轉錄完成: {} 段", channel, result.segments.len());
+            results.push((channel, self.speaker_label(channel), result));
+        }
+
+        Ok(whisper_model_pool::merge_channel_results(results))
+    }
+
+    /// 單聲道直接轉錄，多聲道逐聲道轉錄後合併
+    async fn transcribe_audio(
+        &self,
+        samples: Vec<f32>,
+        channels: Vec<Vec<f32>>,
+        options: TranscriptionOptions,
+        task_ids: &[Uuid],
+        timeout: Option<Duration>,
+    ) -> Result<whisper_model_pool::TranscriptionResult, PipelineError> {
+        if channels.len() > 1 {
+            self.transcribe_channels(channels, options, task_ids, timeout).await
+        } else {
+            self.model_pool.transcribe_chunked(task_ids[0], samples, options, timeout).await
+        }
+    }
+
+    /// 雙語輸出：原文轉錄與英文翻譯同時送入模型池
+    async fn transcribe_dual(
+        &self,
+        samples: Vec<f32>,
+        channels: Vec<Vec<f32>>,
+        options: TranscriptionOptions,
+        task_ids: &[Uuid],
+        timeout: Option<Duration>,
+    ) -> Result<(whisper_model_pool::TranscriptionResult, whisper_model_pool::TranscriptionResult), PipelineError> {
+        info!("🌍 雙語輸出：同時轉錄原文與英文翻譯");
+        counter!("dual_output_transcriptions_total").increment(1);
+
+        let (original_ids, translation_ids) = task_ids.split_at(task_ids.len() / 2);
+        let original_options = TranscriptionOptions { translate: false, ..options.clone() };
+        let translation_options = TranscriptionOptions { translate: true, ..options };
+        let (original, translation) = tokio::join!(
+            self.transcribe_audio(samples.clone(), channels.clone(), original_options, original_ids, timeout),
+            self.transcribe_audio(samples, channels, translation_options, translation_ids, timeout),
+        );
+        Ok((original?, translation?))
+    }
+
+    /// 依請求選項轉錄，雙語輸出時另返回英文翻譯
+    ///
+    /// `task_ids` 的數量須為 [`transcription_passes`]，非同步任務以這些 ID 查詢狀態與取消；
+    /// `timeout` 套用於每個區塊，None 表示不限時。
+    async fn transcribe_request(
+        &self,
+        samples: Vec<f32>,
+        channels: Vec<Vec<f32>>,
+        options: TranscriptionOptions,
+        dual_output: bool,
+        task_ids: &[Uuid],
+        timeout: Option<Duration>,
+    ) -> Result<(whisper_model_pool::TranscriptionResult, Option<whisper_model_pool::TranscriptionResult>), PipelineError> {
+        if dual_output {
+            self.transcribe_dual(samples, channels, options, task_ids, timeout).await
+                .map(|(original, translation)| (original, Some(translation)))
+        } else {
+            self.transcribe_audio(samples, channels, options, task_ids, timeout).await
+                .map(|result| (result, None))
+        }
+    }
+
+    /// 轉錄段落轉為回應格式，並依配置門檻標記低信心段落
+    fn segment_response(&self, seg: whisper_model_pool::TranscriptSegment) -> TranscriptSegmentResponse {
+        TranscriptSegmentResponse {
+            low_confidence: confidence::is_low_confidence(&seg, self.low_confidence_threshold),
+            start_time: seg.start_time,
+            end_time: seg.end_time,
+            text: seg.text,
+            confidence: seg.confidence,
+            avg_logprob: seg.avg_logprob,
+            temperature: seg.temperature,
+            tokens: seg.tokens,
+            words: seg.words,
+            hallucination: seg.hallucination,
+            channel: seg.channel,
+            speaker: seg.speaker,
+        }
+    }
     
     /// 業界領先的智能轉錄服務
     async fn transcribe_enhanced(
         &self,
-        audio_samples: Vec<f32>,
-        audio_format: AudioFormat,
-        quality_preference: Option<TranscriptionQuality>,
-    ) -> Result<EnhancedTranscriptResponse, Box<dyn std::error::Error>> {
+        audio: DecodedAudio,
+        source: UploadSource,
+        options: TranscriptionOptions,
+        dual_output: bool,
+        format: TranscriptFormat,
+    ) -> Result<TranscriptOutput, PipelineError> {
+        let source_sample_rate = audio.source_sample_rate;
+        let audio_samples = audio.samples;
+        let audio_channels = audio.channels;
         let span = span!(Level::INFO, "enhanced_transcription",
             samples = audio_samples.len(),
-            format = ?audio_format
+            format = source.label()
         );
         let _enter = span.enter();
 
         let start_time = Instant::now();
         let _request_id = Uuid::new_v4();
         
-        info!("🎯 開始業界領先轉錄: {} 樣本, 格式: {:?}", 
-              audio_samples.len(), audio_format);
+        info!("🎯 開始業界領先轉錄: {} 樣本, 格式: {}", 
+              audio_samples.len(), source.label());
+
+        let audio_duration_seconds = audio_samples.len() as f64 / 16000.0;
 
         // 更新統計
         {
             let mut stats = self.service_stats.write();
             stats.total_requests += 1;
-            stats.total_audio_duration_seconds += audio_samples.len() as f64 / 16000.0;
+            stats.total_audio_duration_seconds += audio_duration_seconds;
         }
 
         // GPU 音頻預處理 (如果可用)
@@ -318,9 +562,10 @@ impl WhisperService {
         let processed_audio = if let Some(ref gpu_manager) = self.gpu_manager {
             if gpu_manager.health_check() {
                 info!("🚀 使用 GPU 加速音頻預處理");
-                gpu_manager.process_audio_batch(vec![audio_samples]).await?
+                gpu_manager.process_audio_batch(vec![audio_samples]).await
+                    .map_err(|e| PipelineError::TranscriptionFailed(format!("GPU 預處理失敗: {}", e)))?
                     .into_iter().next()
-                    .ok_or("GPU 預處理失敗")?
+                    .ok_or_else(|| PipelineError::TranscriptionFailed("GPU 預處理失敗".to_string()))?
             } else {
                 audio_samples
             }
@@ -331,57 +576,78 @@ impl WhisperService {
         #[cfg(not(feature = "cuda"))]
         let processed_audio = audio_samples;
 
-        // 統一使用最佳中文模型 (Large-v3)
-        let quality = quality_preference.unwrap_or(TranscriptionQuality::Premium);
+        let quality = options.quality;
 
-        info!("🎛️  選擇轉錄品質: {:?}", quality);
+        info!("🎛️  選擇轉錄品質: {:?}, 語言: {}", quality,
+              options.language.as_deref().unwrap_or("auto"));
 
-        // 直接使用 Premium (Large-v3) 模型進行中文轉錄
-        let result = self.model_pool.transcribe_blocking(
+        let task_ids: Vec<Uuid> = (0..transcription_passes(audio_channels.len(), dual_output))
+            .map(|_| Uuid::new_v4())
+            .collect();
+        let outcome = self.transcribe_request(
             processed_audio,
-            quality,
-            Some("zh".to_string()), // 中文語言設定
-        ).await?;
+            audio_channels,
+            options,
+            dual_output,
+            &task_ids,
+            Some(self.model_pool.timeout()),
+        ).await;
+        let (result, translation) = match outcome {
+            Ok(result) => result,
+            Err(e) => {
+                self.service_stats.write().failed_transcriptions += 1;
+                counter!("enhanced_transcription_errors_total", "code" => e.code()).increment(1);
+                return Err(e);
+            }
+        };
 
         let processing_time = start_time.elapsed();
 
-        // 生成智能摘要
-        let summary = self.generate_intelligent_summary(&result.transcript);
-
         // 更新成功統計
         {
             let mut stats = self.service_stats.write();
             stats.successful_transcriptions += 1;
             stats.total_processing_time_ms += processing_time.as_millis() as u64;
             
-            let quality_key = quality_preference.map(|q| format!("{:?}", q))
-                .unwrap_or_else(|| "Auto".to_string());
+            let quality_key = format!("{:?}", quality);
             *stats.average_quality_distribution.entry(quality_key).or_insert(0) += 1;
         }
 
         // 記錄效能指標
         histogram!("enhanced_transcription_time_ms").record(processing_time.as_millis() as f64);
         counter!("enhanced_transcriptions_completed_total").increment(1);
-        gauge!("transcription_audio_duration_seconds").set(result.segments.len() as f64);
+        histogram!("transcription_audio_duration_seconds").record(audio_duration_seconds);
 
         info!("✅ 業界領先轉錄完成: {} 段, 耗時: {:?}", 
               result.segments.len(), processing_time);
 
-        Ok(EnhancedTranscriptResponse {
+        if let Some(body) = format.render(&result.segments) {
+            return Ok(TranscriptOutput::Rendered(format, body));
+        }
+
+        // 生成智能摘要
+        let summary = self.generate_intelligent_summary(&result.transcript);
+
+        let translation = translation.map(|translation| TranslationResponse {
+            aligned: bilingual::align_segments(&result.segments, &translation.segments),
+            transcript: translation.transcript,
+            confidence: translation.confidence,
+            segments: translation.segments.into_iter().map(|seg| self.segment_response(seg)).collect(),
+        });
+
+        Ok(TranscriptOutput::Enhanced(Box::new(EnhancedTranscriptResponse {
             full_transcript: result.transcript,
             summary,
             confidence: result.confidence,
             processing_time_ms: processing_time.as_millis() as u64,
             model_used: result.model_used,
-            audio_format: audio_format.friendly_name().to_string(),
-            segments: result.segments.into_iter().map(|seg| {
-                TranscriptSegmentResponse {
-                    start_time: seg.start_time,
-                    end_time: seg.end_time,
-                    text: seg.text,
-                    confidence: seg.confidence,
-                }
-            }).collect(),
+            audio_format: source.label().to_string(),
+            source_sample_rate,
+            language: result.language,
+            detected_language: result.detected_language,
+            segments: result.segments.into_iter().map(|seg| self.segment_response(seg)).collect(),
+            filtered_segments: result.filtered_segments,
+            translation,
             service_info: ServiceInfo {
                 version: "0.3.0".to_string(),
                 capabilities: vec![
@@ -394,7 +660,7 @@ impl WhisperService {
                 performance_tier: "Enterprise".to_string(),
                 system_info: "CUDA 12.9.1 + Whisper-rs Enterprise".to_string(),
             },
-        })
+        })))
     }
 
     /// 智能摘要生成
@@ -429,17 +695,6 @@ impl WhisperService {
         
         format!("🎯 智能摘要：{}", summary.trim())
     }
-
-    /// 向後相容的轉錄方法
-    async fn transcribe(&self, audio_samples: &[f32]) -> Result<String, Box<dyn std::error::Error>> {
-        let result = self.transcribe_enhanced(
-            audio_samples.to_vec(),
-            AudioFormat::Unknown,
-            Some(TranscriptionQuality::Medium), // 預設使用中文優化模型
-        ).await?;
-        
-        Ok(result.full_transcript)
-    }
 }
 
 // 主函數 - 包含 Whisper 服務初始化
@@ -454,16 +709,44 @@ async fn main() {
         )
         .init();
 
+    // 命令列參數: --config <檔案> 指定配置檔，--print-config 輸出生效配置後結束
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let config_path = args.iter()
+        .position(|arg| arg == "--config")
+        .and_then(|index| args.get(index + 1))
+        .map(std::path::PathBuf::from)
+        .or_else(|| std::env::var_os("CARE_VOICE_CONFIG").map(std::path::PathBuf::from));
+
+    let app_config = match AppConfig::load(config_path.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("❌ 配置載入失敗: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if args.iter().any(|arg| arg == "--print-config") {
+        println!("{}", app_config.to_pretty_string());
+        return;
+    }
+
+    // 安裝 Prometheus 記錄器 (需在任何指標記錄之前)
+    #[cfg(feature = "observability")]
+    if let Err(e) = observability::install_metrics_recorder() {
+        warn!("⚠️ Prometheus 指標記錄器安裝失敗: {}", e);
+    }
+
     println!("🚀 Starting Speech-Ear backend with whisper-rs...");
     println!("📊 Environment info:");
     println!("  - Working directory: {:?}", std::env::current_dir().unwrap_or_default());
     println!("  - RUST_LOG: {}", std::env::var("RUST_LOG").unwrap_or_else(|_| "Not set".to_string()));
-    println!("  - Backend port: {}", std::env::var("BACKEND_PORT").unwrap_or_else(|_| "3000 (default)".to_string()));
+    println!("  - Config file: {}", config_path.as_deref().map(|path| path.display().to_string()).unwrap_or_else(|| "care-voice.toml (optional)".to_string()));
+    println!("  - Backend port: {}", app_config.server.port);
     info!("Starting Speech-Ear backend with whisper-rs...");
     
     // 初始化 Whisper 服務
     println!("🔧 Initializing Whisper service...");
-    let whisper_service = match WhisperService::new().await {
+    let whisper_service = match WhisperService::new(&app_config).await {
         Ok(service) => {
             println!("✅ Whisper service initialized successfully!");
             Arc::new(service)
@@ -479,24 +762,127 @@ async fn main() {
     // CORS 配置
     let cors = tower_http::cors::CorsLayer::new()
         .allow_origin(tower_http::cors::Any)
-        .allow_methods([axum::http::Method::GET, axum::http::Method::POST])
+        .allow_methods([axum::http::Method::GET, axum::http::Method::POST, axum::http::Method::DELETE])
         .allow_headers(tower_http::cors::Any);
     
+    // 上傳端點需容納長時間錄音，不套用 axum 預設的 2MB 請求上限
+    let upload_routes = Router::new()
+        .route("/upload", post(upload_audio))  // 🚀 統一音頻上傳端點
+        .route("/jobs", post(transcription_jobs::create_job))  // 📥 非同步轉錄任務
+        .route("/v1/audio/transcriptions", post(openai_compat::create_transcription))  // 🔌 OpenAI 相容
+        .route("/v1/audio/translations", post(openai_compat::create_translation));
+
     let app = Router::new()
         .route("/", get(api_info))
-        .route("/upload", post(upload_audio))  // 🚀 統一音頻上傳端點
+        .merge(with_upload_limit(upload_routes, app_config.server.max_upload_bytes))
+        .route("/jobs/:id", get(transcription_jobs::get_job).delete(transcription_jobs::delete_job))
         .route("/health", get(health_check))
         .route("/api/info", get(api_info))
-        // .route("/ws/transcribe", get(websocket_handler::websocket_handler))  // 🔌 WebSocket 即時轉錄 (暫時移除)
+        .route("/ws/transcribe", get(websocket_handler::websocket_handler));  // 🔌 WebSocket 即時轉錄
+
+    // 📈 Prometheus 指標端點
+    #[cfg(feature = "observability")]
+    let app = app.route("/metrics", get(observability::metrics_handler));
+
+    let app = app
+        .layer(middleware::from_fn_with_state(whisper_service.clone(), reject_while_draining))
         .layer(cors)
-        .with_state(whisper_service);
+        .with_state(whisper_service.clone());
     
-    // 支援環境變數配置端口，默認 3000 (統一架構標準)
-    let port = std::env::var("BACKEND_PORT").unwrap_or_else(|_| "3000".to_string());
-    let bind_addr = format!("0.0.0.0:{}", port);
+    // 監聽位址由 server.host / server.port 配置，默認 0.0.0.0:3000 (統一架構標準)
+    let bind_addr = format!("{}:{}", app_config.server.host, app_config.server.port);
     let listener = tokio::net::TcpListener::bind(&bind_addr).await.unwrap();
     info!("Server running on http://{}", bind_addr);
-    axum::serve(listener, app).await.unwrap();
+
+    let stop_serving = Arc::new(tokio::sync::Notify::new());
+    let mut server = tokio::spawn({
+        let stop_serving = stop_serving.clone();
+        async move {
+            axum::serve(listener, app)
+                .with_graceful_shutdown(async move { stop_serving.notified().await })
+                .await
+        }
+    });
+
+    tokio::select! {
+        result = &mut server => {
+            error!("❌ HTTP 服務異常結束: {:?}", result);
+            std::process::exit(1);
+        },
+        _ = shutdown_signal() => {},
+    }
+
+    // 優雅關閉：停止接受新連線與新任務，排空佇列後再等待進行中的回應送出
+    let shutdown_timeout = app_config.server.shutdown_timeout();
+    let shutdown_start = Instant::now();
+    info!("🛑 收到關閉信號，開始優雅關閉 (期限 {:?})", shutdown_timeout);
+    stop_serving.notify_one();
+
+    let report = whisper_service.model_pool.shutdown(shutdown_timeout).await;
+    info!(
+        "📊 轉錄佇列排空結果: 待處理 {}，完成 {}，放棄 {}，工作線程回收 {} / 未回收 {}",
+        report.pending_at_start, report.drained, report.abandoned,
+        report.workers_joined, report.workers_detached
+    );
+    if report.abandoned > 0 {
+        warn!("⚠️ {} 個轉錄任務因超過關閉期限而被放棄", report.abandoned);
+    }
+
+    let remaining = shutdown_timeout.saturating_sub(shutdown_start.elapsed()).max(HTTP_DRAIN_GRACE);
+    match tokio::time::timeout(remaining, server).await {
+        Ok(_) => info!("✅ HTTP 服務已關閉"),
+        Err(_) => warn!("⚠️ 仍有連線未結束 (例如 WebSocket)，強制關閉"),
+    }
+}
+
+/// 佇列排空後，等待 HTTP 回應送出的最短時間
+const HTTP_DRAIN_GRACE: Duration = Duration::from_secs(5);
+
+/// 等待 SIGTERM (容器停止) 或 Ctrl+C
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("無法監聽 Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            },
+            Err(e) => {
+                error!("無法監聽 SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+/// 關閉排空期間拒絕新的轉錄工作，查詢既有任務 (GET /jobs/:id) 仍可使用
+async fn reject_while_draining(
+    State(whisper_service): State<Arc<WhisperService>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let starts_work = request.method() == axum::http::Method::POST
+        || request.uri().path() == "/ws/transcribe";
+
+    if starts_work && !whisper_service.model_pool.is_accepting() {
+        counter!("http_requests_rejected_draining_total").increment(1);
+        return PipelineError::ShuttingDown.into_response();
+    }
+
+    next.run(request).await
 }
 
 
@@ -755,17 +1141,102 @@ fn generate_simple_summary(transcript: &str) -> String {
     format!("關懷摘要：{}", summary.trim())
 }
 
+/// 一次請求提交到模型池的轉錄次數：每個聲道一次，雙語輸出另加一輪翻譯
+fn transcription_passes(channels: usize, dual_output: bool) -> usize {
+    channels.max(1) * if dual_output { 2 } else { 1 }
+}
+
+/// 上傳音頻的來源類型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UploadSource {
+    /// WebCodecs 獨立包 (JSON)
+    WebCodecsPackets,
+    /// 二進制音頻檔案 (檢測到的容器格式)
+    Binary(AudioFormat),
+}
+
+impl UploadSource {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::WebCodecsPackets => "WebCodecs OPUS",
+            Self::Binary(format) => format.friendly_name(),
+        }
+    }
+}
+
+/// 🔍 解碼上傳的音頻欄位 - 智能格式檢測 (WebCodecs JSON 包或二進制音頻)
+///
+/// 聲道模式優先使用請求參數，其次為配置；二進制音頻一律經格式檢測後依容器解碼。
+fn decode_uploaded_audio(
+    whisper_service: &WhisperService,
+    data: &[u8],
+    channel_mode: Option<ChannelMode>,
+) -> Result<(DecodedAudio, UploadSource), PipelineError> {
+    let decoder = &whisper_service.audio_decoder;
+    let channel_mode = channel_mode.unwrap_or_else(|| decoder.channel_mode());
+
+    if data.starts_with(b"{") {
+        // JSON 格式 - WebCodecs 獨立包數據
+        info!("📦 檢測到 JSON 格式 - 使用 WebCodecs 獨立包處理");
+        
+        #[derive(serde::Deserialize)]
+        struct PacketsData {
+            format: String,
+            packet_count: usize,
+            packets: Vec<Vec<u8>>,
+        }
+        
+        let packets_data: PacketsData = serde_json::from_slice(data).map_err(|e| {
+            error!("JSON 解析失敗: {}", e);
+            PipelineError::InvalidRequest(format!("WebCodecs 包數據格式錯誤: {}", e))
+        })?;
+        
+        // 驗證格式
+        if packets_data.format != "webcodecs_opus_packets" {
+            error!("不支援的包格式: {}", packets_data.format);
+            return Err(PipelineError::UnsupportedFormat(format!("不支援的包格式: {}", packets_data.format)));
+        }
+        
+        // 使用 WebCodecs 獨立包解碼
+        info!("🎯 開始 WebCodecs 獨立包解碼: {} 包", packets_data.packets.len());
+        let audio = decoder
+            .decode_webcodecs_packets(&packets_data.packets, channel_mode)
+            .inspect_err(|e| error!("WebCodecs 獨立包解碼失敗: {}", e))?;
+        
+        info!("✅ WebCodecs 獨立包解碼成功: {} 樣本", audio.samples.len());
+        Ok((audio, UploadSource::WebCodecsPackets))
+    } else {
+        // 二進制格式 - 傳統音頻檔案
+        info!("🎵 檢測到二進制格式 - 使用傳統音頻處理");
+
+        let format = decoder.detect_format(data, None);
+        let audio = decoder
+            .decode_audio_with_channel_mode(format, data, channel_mode)
+            .inspect_err(|e| error!("音頻解碼失敗: {}", e))?;
+
+        info!(
+            "✅ 音頻解碼成功: {} 樣本 (原始 {}Hz, {} 聲道), 聲道模式: {:?}",
+            audio.samples.len(), audio.source_sample_rate, audio.source_channels, channel_mode
+        );
+        Ok((audio, UploadSource::Binary(format)))
+    }
+}
+
 /// 🚀 統一音頻上傳端點 - 智能格式檢測
 async fn upload_audio(
     State(whisper_service): State<Arc<WhisperService>>,
+    headers: HeaderMap,
     mut multipart: Multipart,
-) -> Result<Json<EnhancedTranscriptResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Response, PipelineError> {
     info!("🚀 Received audio upload request");
     
-    // 處理 multipart 資料
+    let mut audio_data = None;
+    let mut options = UploadOptions::default();
+    
+    // 處理 multipart 資料 (選項欄位可能出現在音頻欄位之後)
     while let Some(field) = multipart.next_field().await.map_err(|e| {
         error!("Error reading multipart field: {}", e);
-        (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: "Invalid multipart data".to_string() }))
+        PipelineError::from_multipart(e)
     })? {
         let field_name = field.name().unwrap_or("").to_string();
         
@@ -773,135 +1244,70 @@ async fn upload_audio(
         if field_name == "audio" || field_name == "audio_packets" {
             let data = field.bytes().await.map_err(|e| {
                 error!("Error reading field data: {}", e);
-                (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: "Failed to read field data".to_string() }))
+                PipelineError::from_multipart(e)
+            })?;
+            audio_data = Some(data);
+        } else if UploadOptions::is_option_field(&field_name) {
+            let value = field.text().await.map_err(|e| {
+                error!("Error reading field {}: {}", field_name, e);
+                PipelineError::from_multipart(e)
+            })?;
+            options.apply_field(&field_name, &value).map_err(|e| {
+                warn!("⚠️ 無效的上傳參數 {}: {}", field_name, e);
+                PipelineError::InvalidRequest(e)
             })?;
-            
-            // 🔍 智能格式檢測
-            if data.starts_with(b"{") {
-                // JSON 格式 - WebCodecs 獨立包數據
-                info!("📦 檢測到 JSON 格式 - 使用 WebCodecs 獨立包處理");
-                
-                #[derive(serde::Deserialize)]
-                struct PacketsData {
-                    format: String,
-                    packet_count: usize,
-                    packets: Vec<Vec<u8>>,
-                }
-                
-                let packets_data: PacketsData = serde_json::from_slice(&data).map_err(|e| {
-                    error!("JSON 解析失敗: {}", e);
-                    (StatusCode::BAD_REQUEST, Json(ErrorResponse { 
-                        error: format!("WebCodecs 包數據格式錯誤: {}", e)
-                    }))
-                })?;
-                
-                // 驗證格式
-                if packets_data.format != "webcodecs_opus_packets" {
-                    error!("不支援的包格式: {}", packets_data.format);
-                    return Err((
-                        StatusCode::BAD_REQUEST, 
-                        Json(ErrorResponse { 
-                            error: format!("不支援的包格式: {}", packets_data.format)
-                        })
-                    ));
-                }
-                
-                // 使用 WebCodecs 獨立包解碼
-                info!("🎯 開始 WebCodecs 獨立包解碼: {} 包", packets_data.packets.len());
-                let audio_samples = whisper_service.audio_decoder
-                    .decode_webcodecs_packets(&packets_data.packets)
-                    .map_err(|e| {
-                        error!("WebCodecs 獨立包解碼失敗: {}", e);
-                        (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse {
-                            error: format!("音頻解碼失敗: {}", e)
-                        }))
-                    })?;
-                
-                info!("✅ WebCodecs 獨立包解碼成功: {} 樣本", audio_samples.len());
-                
-                // 執行轉錄
-                let transcript = whisper_service.transcribe(&audio_samples).await
-                    .map_err(|e| {
-                        error!("轉錄失敗: {}", e);
-                        (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse {
-                            error: format!("轉錄失敗: {}", e)
-                        }))
-                    })?;
-                
-                // 建構增強響應
-                let enhanced_response = EnhancedTranscriptResponse {
-                    full_transcript: transcript.clone(),
-                    summary: format!("WebCodecs 音頻轉錄: {} 字符", transcript.len()),
-                    confidence: Some(0.95),
-                    processing_time_ms: 100, // TODO: 實際測量時間
-                    model_used: "whisper-base".to_string(),
-                    audio_format: "WebCodecs OPUS".to_string(),
-                    segments: vec![],
-                    service_info: ServiceInfo {
-                        version: "v0.3.0".to_string(),
-                        capabilities: vec!["WebCodecs".to_string(), "OPUS".to_string()],
-                        performance_tier: "Production".to_string(),
-                        system_info: "CUDA 12.9.1 + Whisper-rs + OPUS".to_string(),
-                    },
-                };
-                
-                return Ok(Json(enhanced_response));
-                
-            } else {
-                // 二進制格式 - 傳統音頻檔案
-                info!("🎵 檢測到二進制格式 - 使用傳統音頻處理");
-                
-                let audio_samples = whisper_service.audio_decoder
-                    .decode_raw_opus(&data)
-                    .map_err(|e| {
-                        error!("音頻解碼失敗: {}", e);
-                        (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse {
-                            error: format!("音頻解碼失敗: {}", e)
-                        }))
-                    })?;
-                
-                info!("✅ 音頻解碼成功: {} 樣本", audio_samples.len());
-                
-                // 執行轉錄
-                let transcript = whisper_service.transcribe(&audio_samples).await
-                    .map_err(|e| {
-                        error!("轉錄失敗: {}", e);
-                        (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse {
-                            error: format!("轉錄失敗: {}", e)
-                        }))
-                    })?;
-                
-                // 建構增強響應
-                let enhanced_response = EnhancedTranscriptResponse {
-                    full_transcript: transcript.clone(),
-                    summary: format!("音頻轉錄: {} 字符", transcript.len()),
-                    confidence: Some(0.90),
-                    processing_time_ms: 150, // TODO: 實際測量時間
-                    model_used: "whisper-base".to_string(),
-                    audio_format: "OPUS Binary".to_string(),
-                    segments: vec![],
-                    service_info: ServiceInfo {
-                        version: "v0.3.0".to_string(),
-                        capabilities: vec!["OPUS".to_string(), "Binary".to_string()],
-                        performance_tier: "Production".to_string(),
-                        system_info: "CUDA 12.9.1 + Whisper-rs + OPUS".to_string(),
-                    },
-                };
-                
-                return Ok(Json(enhanced_response));
-            }
         }
     }
     
-    error!("未找到音頻數據");
-    Err((StatusCode::BAD_REQUEST, Json(ErrorResponse {
-        error: "未找到音頻數據".to_string()
-    })))
+    let Some(data) = audio_data else {
+        error!("未找到音頻數據");
+        return Err(PipelineError::MissingAudio);
+    };
+    whisper_service.apply_vocabulary(&mut options)
+        .inspect_err(|e| warn!("⚠️ 無效的上傳參數: {}", e))?;
+    
+    // 明確指定的格式優先，其次依 Accept 標頭協商；雙語輸出只能以 JSON 回傳
+    let format = negotiate_format(options.response_format, &headers);
+    upload_options::check_dual_output(options.dual_output, format).map_err(|e| {
+        warn!("⚠️ 無效的上傳參數: {}", e);
+        PipelineError::InvalidRequest(e)
+    })?;
+    
+    let (audio, source) = decode_uploaded_audio(&whisper_service, &data, options.channel_mode)?;
+    
+    // 執行轉錄
+    let output = whisper_service.transcribe_enhanced(audio, source, options.transcription, options.dual_output, format).await
+        .inspect_err(|e| error!("轉錄失敗: {}", e))?;
+    
+    Ok(match output {
+        TranscriptOutput::Enhanced(response) => Json(response).into_response(),
+        TranscriptOutput::Rendered(format, body) => ([(header::CONTENT_TYPE, format.content_type())], body).into_response(),
+    })
+}
+
+
+/// 決定回應格式：明確參數優先，其次 Accept 標頭，預設 JSON
+fn negotiate_format(requested: Option<TranscriptFormat>, headers: &HeaderMap) -> TranscriptFormat {
+    requested
+        .or_else(|| {
+            headers.get(header::ACCEPT)
+                .and_then(|accept| accept.to_str().ok())
+                .and_then(TranscriptFormat::from_accept)
+        })
+        .unwrap_or_default()
 }
 
+/// 套用上傳請求大小上限 (超過時 multipart 讀取失敗並回傳 413)
+fn with_upload_limit<S>(routes: Router<S>, max_upload_bytes: usize) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    routes.layer(DefaultBodyLimit::max(max_upload_bytes))
+}
 
 /// API 信息和歡迎頁面
-async fn api_info() -> axum::response::Html<String> {
+async fn api_info(State(whisper_service): State<Arc<WhisperService>>) -> axum::response::Html<String> {
+    let max_upload_mb = whisper_service.max_upload_bytes / (1024 * 1024);
     let html = format!(r#"
 <!DOCTYPE html>
 <html>
@@ -945,10 +1351,20 @@ async fn api_info() -> axum::response::Html<String> {
             健康檢查端點，返回服務狀態和統計信息
         </div>
         
+        <div class="endpoint">
+            <span class="method">GET</span> <strong>/metrics</strong><br>
+            Prometheus 指標 (需以 <code>--features observability</code> 編譯)，包含依 <code>format</code> / <code>quality</code> 標籤區分的解碼與轉錄延遲
+        </div>
+        
         <div class="endpoint">
             <span class="method">POST</span> <strong>/upload</strong><br>
             音頻檔案上傳和轉錄，支援 OPUS/WAV/MP4 格式<br>
-            <code>Content-Type: multipart/form-data</code>
+            <code>Content-Type: multipart/form-data</code><br>
+            可選欄位: <code>language</code> (zh / en / auto ...)、<code>language_candidates</code> (auto 時限定候選語言，例如 zh,en,ja)、<code>quality</code> (turbo / balanced / medium / high_accuracy / premium)、
+            <code>initial_prompt</code>、<code>vocabulary</code> (專有詞彙，以逗號或換行分隔)、<code>organization</code> (套用配置的機構詞彙表)、
+            <code>translate</code>、<code>temperature</code> (0.0-1.0)、<code>format</code> (json / text / srt / vtt / tsv / markdown，亦可使用 Accept 標頭)、
+            <code>channel_mode</code> (downmix / left / right / separate；separate 逐聲道轉錄並以說話者標記段落)、
+            <code>dual_output</code> (true 時 JSON 回應同時包含原文與時間對齊的英文翻譯；搭配非 JSON 格式回傳 400)
         </div>
         
         <div class="endpoint">
@@ -956,6 +1372,48 @@ async fn api_info() -> axum::response::Html<String> {
             前端相容路由，功能同 /upload
         </div>
 
+        <div class="endpoint">
+            <span class="method">POST</span> <strong>/jobs</strong><br>
+            非同步轉錄任務，立即返回任務 ID 與狀態查詢網址 (適用長音頻)；
+            選項欄位與 /upload 相同，未知欄位返回 400<br>
+            <code>Content-Type: multipart/form-data</code>
+        </div>
+
+        <div class="endpoint">
+            <span class="method">GET</span> <strong>/jobs/{{id}}</strong><br>
+            查詢任務狀態 (queued / running / done / failed) 與完整轉錄結果；
+            <code>?format=srt</code> 等參數可直接下載字幕或文件
+        </div>
+
+        <div class="endpoint">
+            <span class="method">DELETE</span> <strong>/jobs/{{id}}</strong><br>
+            取消排隊中的任務或刪除已完成的結果
+        </div>
+
+        <div class="endpoint">
+            <span class="method">GET</span> <strong>/ws/transcribe</strong><br>
+            WebSocket 即時轉錄：以二進制訊息逐包送出 WebCodecs OPUS (48kHz)，伺服器推送 <code>partial</code> / <code>final</code> 段落；
+            送出 <code>{{"type":"stop"}}</code> 結束串流。查詢參數同 /upload 選項欄位
+        </div>
+
+        <div class="endpoint">
+            <span class="method">POST</span> <strong>/v1/audio/transcriptions</strong><br>
+            OpenAI 相容轉錄 API，可直接替換 OpenAI 客戶端的 base URL<br>
+            欄位: <code>file</code>、<code>model</code>、<code>language</code>、<code>prompt</code>、<code>temperature</code>、
+            <code>response_format</code> (json / text / srt / vtt / verbose_json)、<code>timestamp_granularities[]</code> (segment / word，word 於 verbose_json 回傳逐詞時間戳)
+        </div>
+
+        <div class="endpoint">
+            <span class="method">POST</span> <strong>/v1/audio/translations</strong><br>
+            OpenAI 相容翻譯 API，將語音翻譯為英文
+        </div>
+
+        <div class="feature">
+            <strong>⚠️ 錯誤回應</strong><br>
+            <code>{{"error": "...", "code": "unsupported_format", "retryable": false}}</code><br>
+            400 請求錯誤、413 音頻過長/過大、415 不支援的格式、422 音頻損壞或為空、503 佇列已滿/關閉中 (可重試)、504 轉錄超時 (可重試)
+        </div>
+
         <h2>🌐 瀏覽器相容性</h2>
         <div class="stats">
             <div class="stat">
@@ -992,7 +1450,7 @@ fetch('/upload', {{
         <ul>
             <li><strong>音頻格式</strong>: OPUS, WAV, MP4-AAC, OGG-Vorbis</li>
             <li><strong>容器格式</strong>: WebM, OGG, MP4, WAV</li>
-            <li><strong>最大檔案</strong>: 100MB</li>
+            <li><strong>最大檔案</strong>: {max_upload_mb}MB</li>
             <li><strong>處理延遲</strong>: &lt; 100ms (解碼)</li>
             <li><strong>並發支援</strong>: 4個解碼器池</li>
         </ul>
@@ -1040,6 +1498,7 @@ async fn health_check(
     let model_info = model_stats.iter().map(|stat| {
         serde_json::json!({
             "quality": format!("{:?}", stat.quality),
+            "model_file": stat.model_file,
             "total_processed": stat.total_processed,
             "average_time_ms": stat.average_processing_time_ms,
             "uptime_hours": stat.uptime.as_secs() / 3600
@@ -1105,7 +1564,9 @@ async fn health_check(
         "🎵 全格式音頻支援"
     ];
 
-    let overall_status = if model_pool_healthy {
+    let overall_status = if !whisper_service.model_pool.is_accepting() {
+        "draining"
+    } else if model_pool_healthy {
         "healthy"
     } else {
         "degraded"
@@ -1142,4 +1603,59 @@ async fn health_check(
             "coverage": "99.9%"
         }
     }))
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use axum::http::StatusCode;
+    use tower::Service;
+
+    /// 讀完所有 multipart 欄位，回傳總位元組數
+    async fn count_upload(mut multipart: Multipart) -> Result<String, PipelineError> {
+        let mut total = 0;
+        while let Some(field) = multipart.next_field().await.map_err(PipelineError::from_multipart)? {
+            total += field.bytes().await.map_err(PipelineError::from_multipart)?.len();
+        }
+        Ok(total.to_string())
+    }
+
+    async fn post_upload(max_upload_bytes: usize, audio_bytes: usize) -> (StatusCode, String) {
+        let boundary = "care-voice-test";
+        let mut body = format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"audio\"; filename=\"visit.webm\"\r\n\
+             Content-Type: audio/webm\r\n\r\n"
+        ).into_bytes();
+        body.extend(std::iter::repeat(0u8).take(audio_bytes));
+        body.extend(format!("\r\n--{boundary}--\r\n").into_bytes());
+
+        let request = axum::http::Request::post("/upload")
+            .header(header::CONTENT_TYPE, format!("multipart/form-data; boundary={boundary}"))
+            .body(Body::from(body))
+            .unwrap();
+        let mut app = with_upload_limit(Router::new().route("/upload", post(count_upload)), max_upload_bytes);
+        std::future::poll_fn(|cx| <Router as Service<axum::http::Request<Body>>>::poll_ready(&mut app, cx))
+            .await
+            .unwrap();
+        let response = app.call(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8_lossy(&body).into_owned())
+    }
+
+    #[tokio::test]
+    async fn test_upload_limit_accepts_long_recordings() {
+        // 一小時 32kbps Opus 約 14MB，遠超 axum 預設的 2MB
+        let audio_bytes = 3 * 1024 * 1024;
+        let max_upload_bytes = AppConfig::default().server.max_upload_bytes;
+
+        let (status, body) = post_upload(max_upload_bytes, audio_bytes).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, audio_bytes.to_string());
+
+        let (status, body) = post_upload(2 * 1024 * 1024, audio_bytes).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(body.contains("payload_too_large"));
+    }
+}
@@ -0,0 +1,144 @@
+// ===================================
+// Whisper 模型註冊表
+// 品質等級 → 模型檔案的對應 (可使用量化版本或台語微調模型)、
+// 啟動時載入的等級，以及請求的等級未載入時的回退順序
+// ===================================
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::whisper_model_pool::TranscriptionQuality;
+
+const ALL_QUALITIES: [TranscriptionQuality; 5] = [
+    TranscriptionQuality::Turbo,
+    TranscriptionQuality::Balanced,
+    TranscriptionQuality::Medium,
+    TranscriptionQuality::HighAccuracy,
+    TranscriptionQuality::Premium,
+];
+
+/// 模型註冊表配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModelRegistryConfig {
+    /// 啟動時載入的品質等級
+    pub load: Vec<TranscriptionQuality>,
+    /// 請求的品質等級未載入時，依序嘗試的等級
+    pub fallback: Vec<TranscriptionQuality>,
+    /// 各品質等級的模型檔案 (相對於 whisper.model_path，或絕對路徑)
+    pub turbo: String,
+    pub balanced: String,
+    pub medium: String,
+    pub high_accuracy: String,
+    pub premium: String,
+}
+
+impl Default for ModelRegistryConfig {
+    fn default() -> Self {
+        Self {
+            // 只載入最佳中文模型 (large-v3)
+            load: vec![TranscriptionQuality::Premium],
+            // 優先回退到中文優化模型
+            fallback: vec![
+                TranscriptionQuality::Medium,
+                TranscriptionQuality::Balanced,
+                TranscriptionQuality::Premium,
+                TranscriptionQuality::HighAccuracy,
+                TranscriptionQuality::Turbo,
+            ],
+            turbo: "ggml-tiny.bin".to_string(),
+            balanced: "ggml-base.bin".to_string(),
+            medium: "ggml-medium.bin".to_string(),
+            high_accuracy: "ggml-large-v2.bin".to_string(),
+            premium: "ggml-large-v3.bin".to_string(),
+        }
+    }
+}
+
+impl ModelRegistryConfig {
+    /// 品質等級對應的模型檔案
+    pub fn model_file(&self, quality: TranscriptionQuality) -> &str {
+        match quality {
+            TranscriptionQuality::Turbo => &self.turbo,
+            TranscriptionQuality::Balanced => &self.balanced,
+            TranscriptionQuality::Medium => &self.medium,
+            TranscriptionQuality::HighAccuracy => &self.high_accuracy,
+            TranscriptionQuality::Premium => &self.premium,
+        }
+    }
+
+    /// 模型檔案的完整路徑
+    pub fn model_path(&self, model_dir: &str, quality: TranscriptionQuality) -> PathBuf {
+        Path::new(model_dir).join(self.model_file(quality))
+    }
+
+    /// 選擇處理請求的品質等級：已載入時直接使用，否則依回退順序選擇第一個已載入的等級
+    pub fn resolve(
+        &self,
+        requested: TranscriptionQuality,
+        is_loaded: impl Fn(TranscriptionQuality) -> bool,
+    ) -> Option<TranscriptionQuality> {
+        std::iter::once(requested)
+            .chain(self.fallback.iter().copied())
+            .find(|&quality| is_loaded(quality))
+    }
+
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.load.is_empty() {
+            problems.push("whisper.models.load 至少需要一個品質等級".to_string());
+        }
+        for (name, list) in [("load", &self.load), ("fallback", &self.fallback)] {
+            if let Some(duplicate) = list.iter().enumerate().find(|(index, quality)| list[..*index].contains(quality)) {
+                problems.push(format!("whisper.models.{} 包含重複的品質等級: {}", name, duplicate.1.label()));
+            }
+        }
+        for quality in ALL_QUALITIES {
+            if self.model_file(quality).trim().is_empty() {
+                problems.push(format!("whisper.models.{} 的模型檔案不可為空", quality.label()));
+            }
+        }
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_keep_previous_model_files() {
+        let config = ModelRegistryConfig::default();
+        assert_eq!(config.model_file(TranscriptionQuality::Premium), "ggml-large-v3.bin");
+        assert_eq!(config.model_path("./models", TranscriptionQuality::Turbo), Path::new("./models/ggml-tiny.bin"));
+        assert!(config.problems().is_empty());
+    }
+
+    #[test]
+    fn test_resolve_follows_fallback_order() {
+        let config = ModelRegistryConfig {
+            fallback: vec![TranscriptionQuality::HighAccuracy, TranscriptionQuality::Turbo],
+            ..ModelRegistryConfig::default()
+        };
+        let loaded = [TranscriptionQuality::Turbo, TranscriptionQuality::HighAccuracy];
+        let is_loaded = |quality| loaded.contains(&quality);
+
+        assert_eq!(config.resolve(TranscriptionQuality::Turbo, is_loaded), Some(TranscriptionQuality::Turbo));
+        assert_eq!(config.resolve(TranscriptionQuality::Premium, is_loaded), Some(TranscriptionQuality::HighAccuracy));
+        assert_eq!(config.resolve(TranscriptionQuality::Premium, |_| false), None);
+    }
+
+    #[test]
+    fn test_problems() {
+        let config = ModelRegistryConfig {
+            load: vec![TranscriptionQuality::Premium, TranscriptionQuality::Premium],
+            premium: " ".to_string(),
+            ..ModelRegistryConfig::default()
+        };
+        let problems = config.problems();
+        assert_eq!(problems.len(), 2, "{:?}", problems);
+        assert!(problems[0].contains("load"));
+        assert!(problems[1].contains("premium"));
+    }
+}
@@ -0,0 +1,84 @@
+// ===================================
+// Prometheus 指標匯出
+// 安裝 metrics 記錄器並提供 /metrics 端點 (observability feature)
+//
+// 指標名稱變更 (舊儀表板與告警需改用新名稱)：
+//   audio_decode_{webm_opus,ogg_opus,mp4_aac,wav,vorbis,unknown}_total → audio_decode_total{format}
+//   audio_decode_last_sample_count (gauge) → 移除，改用 audio_decode_output_samples{format} histogram
+//   webcodecs_packets_count → webcodecs_packets_per_request
+//   opus_raw_decode_fallback_usage → opus_raw_decode_fallback_total
+//   whisper_audio_duration_seconds 由 gauge 改為 histogram{quality}
+//   whisper_models_loaded_count → whisper_models_loaded
+//   quality 標籤值由模型名稱改為品質等級 (turbo / balanced / medium / high_accuracy / premium)
+// ===================================
+
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tracing::info;
+
+/// 毫秒延遲：解碼約數十毫秒，長音頻轉錄可達 90 秒超時
+const LATENCY_MS_BUCKETS: &[f64] = &[
+    1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0,
+    10000.0, 30000.0, 60000.0, 90000.0,
+];
+/// 微秒延遲：格式檢測、GPU 記憶體配置
+const LATENCY_US_BUCKETS: &[f64] = &[10.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 5000.0, 10000.0];
+/// 音頻長度 (秒)：短語音到一小時的照護記錄
+const AUDIO_SECONDS_BUCKETS: &[f64] = &[1.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0, 3600.0];
+/// 上傳大小：1KB ~ 100MB
+const SIZE_BYTES_BUCKETS: &[f64] = &[1024.0, 10240.0, 102400.0, 1048576.0, 10485760.0, 104857600.0];
+/// 16kHz 樣本數：1 秒 ~ 30 分鐘
+const SAMPLES_BUCKETS: &[f64] = &[16000.0, 80000.0, 480000.0, 960000.0, 4800000.0, 28800000.0];
+/// 每次請求的 WebCodecs 包數 (20ms 一包)
+const PACKETS_BUCKETS: &[f64] = &[10.0, 50.0, 250.0, 500.0, 1500.0, 3000.0, 15000.0];
+
+/// histogram 定期整理間隔
+const UPKEEP_INTERVAL: Duration = Duration::from_secs(5);
+
+static PROMETHEUS_HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// 安裝全域 Prometheus 記錄器，之後所有 counter!/histogram!/gauge! 都會被收集
+pub fn install_metrics_recorder() -> anyhow::Result<()> {
+    let handle = PrometheusBuilder::new()
+        .add_global_label("service", "care-voice")
+        .set_buckets_for_metric(Matcher::Suffix("_ms".to_string()), LATENCY_MS_BUCKETS)?
+        .set_buckets_for_metric(Matcher::Suffix("_us".to_string()), LATENCY_US_BUCKETS)?
+        .set_buckets_for_metric(Matcher::Suffix("_seconds".to_string()), AUDIO_SECONDS_BUCKETS)?
+        .set_buckets_for_metric(Matcher::Suffix("_bytes".to_string()), SIZE_BYTES_BUCKETS)?
+        .set_buckets_for_metric(Matcher::Suffix("_samples".to_string()), SAMPLES_BUCKETS)?
+        .set_buckets_for_metric(Matcher::Full("webcodecs_packets_per_request".to_string()), PACKETS_BUCKETS)?
+        .install_recorder()?;
+
+    // histogram 需定期整理以釋放過期資料
+    let upkeep_handle = handle.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(UPKEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            upkeep_handle.run_upkeep();
+        }
+    });
+
+    PROMETHEUS_HANDLE
+        .set(handle)
+        .map_err(|_| anyhow::anyhow!("Prometheus 記錄器已安裝"))?;
+
+    info!("📈 Prometheus 指標記錄器已安裝，端點: /metrics");
+    Ok(())
+}
+
+/// GET /metrics - Prometheus 文字格式
+pub async fn metrics_handler() -> Response {
+    match PROMETHEUS_HANDLE.get() {
+        Some(handle) => (
+            [(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+            handle.render(),
+        ).into_response(),
+        None => (StatusCode::SERVICE_UNAVAILABLE, "metrics recorder not installed").into_response(),
+    }
+}
@@ -0,0 +1,294 @@
+// ===================================
+// Ogg Opus 解封裝器 (RFC 7845)
+// 以 ogg crate 重組跨頁面/分段的數據包，解析 OpusHead/OpusTags，
+// 依 pre-skip 與最後頁面的 granule position 計算有效樣本範圍，並偵測串接/多工串流
+// ===================================
+
+use anyhow::{anyhow, bail, Result};
+use metrics::{counter, histogram};
+use ogg::PacketReader;
+use std::collections::HashSet;
+use std::io::Cursor;
+use tracing::{debug, info, warn};
+
+use crate::webm_demuxer::OpusHead;
+
+/// 一段邏輯串流 (串接的 Ogg 檔案中每個 link 各自有 OpusHead)
+#[derive(Debug, Clone)]
+pub struct OggOpusLink {
+    pub serial: u32,
+    pub head: OpusHead,
+    /// OpusTags 中的編碼器名稱
+    pub vendor: Option<String>,
+    pub packets: Vec<Vec<u8>>,
+    /// 串流結束頁 (EOS) 的 granule position；檔案被截斷時為 None
+    pub final_granule: Option<u64>,
+}
+
+impl OggOpusLink {
+    /// 扣除 pre-skip 後的有效 48kHz 樣本數 (僅在看到 EOS 頁面時可得)
+    pub fn valid_samples(&self) -> Option<u64> {
+        self.final_granule
+            .map(|granule| granule.saturating_sub(self.head.pre_skip as u64))
+    }
+}
+
+/// 解封裝結果
+#[derive(Debug, Clone, Default)]
+pub struct OggOpusStream {
+    /// 依出現順序排列的 Opus link (一般錄音只有一個)
+    pub links: Vec<OggOpusLink>,
+    /// 與 Opus 串流多工但被忽略的其他邏輯串流數
+    pub ignored_streams: usize,
+}
+
+impl OggOpusStream {
+    pub fn packet_count(&self) -> usize {
+        self.links.iter().map(|link| link.packets.len()).sum()
+    }
+}
+
+/// 解析 OpusTags 標頭中的 vendor 字串
+fn parse_opus_tags_vendor(data: &[u8]) -> Option<String> {
+    if data.len() < 12 || &data[..8] != b"OpusTags" {
+        return None;
+    }
+    let len = u32::from_le_bytes([data[8], data[9], data[10], data[11]]) as usize;
+    let vendor = data.get(12..12usize.checked_add(len)?)?;
+    Some(String::from_utf8_lossy(vendor).into_owned())
+}
+
+/// 從 Ogg 數據中解出所有 Opus link 的音頻數據包
+pub fn demux_opus(data: &[u8]) -> Result<OggOpusStream> {
+    let demux_start = std::time::Instant::now();
+
+    let mut reader = PacketReader::new(Cursor::new(data));
+    let mut stream = OggOpusStream::default();
+    // 目前正在讀取的 Opus link 在 stream.links 中的索引
+    let mut active: Option<(u32, usize)> = None;
+    let mut ignored: HashSet<u32> = HashSet::new();
+    // 等待 OpusTags 的 link
+    let mut awaiting_tags = false;
+
+    loop {
+        let packet = match reader.read_packet() {
+            Ok(Some(packet)) => packet,
+            Ok(None) => break,
+            Err(e) => {
+                // 錄音被中途截斷時最後一頁可能不完整，保留已重組的數據包
+                if stream.packet_count() == 0 {
+                    bail!("無效的 Ogg 數據: {}", e);
+                }
+                warn!("⚠️ Ogg 頁面讀取失敗，忽略剩餘數據: {}", e);
+                counter!("ogg_demux_truncated_total").increment(1);
+                break;
+            }
+        };
+        let serial = packet.stream_serial();
+
+        if packet.first_in_stream() {
+            if !packet.data.starts_with(b"OpusHead") {
+                debug!("忽略非 Opus 邏輯串流: serial={:08x}", serial);
+                ignored.insert(serial);
+                continue;
+            }
+            if let Some((active_serial, _)) = active {
+                // 前一個 link 尚未結束就出現新的 Opus 串流：多工，只處理第一個
+                warn!(
+                    "⚠️ 偵測到多工 Opus 串流 (serial={:08x})，僅使用 serial={:08x}",
+                    serial, active_serial
+                );
+                ignored.insert(serial);
+                continue;
+            }
+
+            let head = OpusHead::parse(&packet.data)?;
+            if head.mapping_family != 0 && head.channels > 2 {
+                bail!(
+                    "不支援多串流 Opus (mapping family {}, {} 聲道)",
+                    head.mapping_family,
+                    head.channels
+                );
+            }
+            if !stream.links.is_empty() {
+                info!("🔗 偵測到串接 Ogg 串流: 第 {} 段 (serial={:08x})", stream.links.len() + 1, serial);
+            }
+            stream.links.push(OggOpusLink {
+                serial,
+                head,
+                vendor: None,
+                packets: Vec::new(),
+                final_granule: None,
+            });
+            active = Some((serial, stream.links.len() - 1));
+            awaiting_tags = true;
+            continue;
+        }
+
+        let Some((active_serial, index)) = active.filter(|(s, _)| *s == serial) else {
+            if !ignored.contains(&serial) {
+                debug!("忽略不屬於任何 Opus link 的數據包: serial={:08x}", serial);
+            }
+            continue;
+        };
+        let link = &mut stream.links[index];
+
+        if awaiting_tags {
+            awaiting_tags = false;
+            if packet.data.starts_with(b"OpusTags") {
+                link.vendor = parse_opus_tags_vendor(&packet.data);
+                continue;
+            }
+            warn!("⚠️ Opus 串流缺少 OpusTags 標頭 (serial={:08x})", active_serial);
+        }
+
+        if !packet.data.is_empty() {
+            link.packets.push(packet.data.clone());
+        }
+        if packet.last_in_stream() {
+            link.final_granule = Some(packet.absgp_page());
+            active = None;
+        }
+    }
+
+    if stream.links.is_empty() {
+        bail!("Ogg 數據中沒有 Opus 串流");
+    }
+    if stream.packet_count() == 0 {
+        return Err(anyhow!("Ogg Opus 串流沒有音頻數據包"));
+    }
+    stream.ignored_streams = ignored.len();
+
+    histogram!("ogg_demux_time_ms").record(demux_start.elapsed().as_millis() as f64);
+    histogram!("ogg_demux_packets").record(stream.packet_count() as f64);
+    counter!("ogg_demux_total").increment(1);
+    if stream.links.len() > 1 {
+        counter!("ogg_demux_chained_total").increment(1);
+    }
+    if stream.ignored_streams > 0 {
+        counter!("ogg_demux_multiplexed_total").increment(1);
+    }
+
+    info!(
+        "📦 Ogg 解封裝完成: {} 段 link, {} 個 Opus 包, 忽略 {} 個串流",
+        stream.links.len(),
+        stream.packet_count(),
+        stream.ignored_streams
+    );
+
+    Ok(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ogg::{PacketWriteEndInfo, PacketWriter};
+
+    fn opus_head(pre_skip: u16) -> Vec<u8> {
+        let mut head = b"OpusHead".to_vec();
+        head.extend_from_slice(&[1, 1]);
+        head.extend_from_slice(&pre_skip.to_le_bytes());
+        head.extend_from_slice(&48000u32.to_le_bytes());
+        head.extend_from_slice(&[0, 0, 0]);
+        head
+    }
+
+    fn opus_tags(vendor: &str) -> Vec<u8> {
+        let mut tags = b"OpusTags".to_vec();
+        tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+        tags.extend_from_slice(vendor.as_bytes());
+        tags.extend_from_slice(&0u32.to_le_bytes());
+        tags
+    }
+
+    /// 寫入一段完整的 Opus link；每個音頻包 960 樣本 (20ms)
+    fn write_link(writer: &mut PacketWriter<&mut Vec<u8>>, serial: u32, pre_skip: u16, packets: &[Vec<u8>]) {
+        writer
+            .write_packet(opus_head(pre_skip).into(), serial, PacketWriteEndInfo::EndPage, 0)
+            .unwrap();
+        writer
+            .write_packet(opus_tags("test").into(), serial, PacketWriteEndInfo::EndPage, 0)
+            .unwrap();
+        for (i, packet) in packets.iter().enumerate() {
+            let last = i + 1 == packets.len();
+            let granule = pre_skip as u64 + 960 * (i as u64 + 1) - if last { 100 } else { 0 };
+            let info = if last {
+                PacketWriteEndInfo::EndStream
+            } else {
+                PacketWriteEndInfo::NormalPacket
+            };
+            writer.write_packet(packet.clone().into(), serial, info, granule).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_packets_spanning_pages_are_reassembled() {
+        // 超過一頁 (255 * 255 bytes) 的數據包必須跨頁重組
+        let big = vec![0xFCu8; 70_000];
+        let packets = vec![vec![0xFC, 1, 2], big.clone(), vec![0xFC, 3]];
+        let mut data = Vec::new();
+        write_link(&mut PacketWriter::new(&mut data), 7, 312, &packets);
+
+        let stream = demux_opus(&data).unwrap();
+        assert_eq!(stream.links.len(), 1);
+        let link = &stream.links[0];
+        assert_eq!(link.head.pre_skip, 312);
+        assert_eq!(link.vendor.as_deref(), Some("test"));
+        assert_eq!(link.packets, packets);
+        // 3 × 960 - 100 (最後一包的尾端裁切)
+        assert_eq!(link.valid_samples(), Some(2780));
+    }
+
+    #[test]
+    fn test_chained_links_are_detected() {
+        let mut data = Vec::new();
+        {
+            let mut writer = PacketWriter::new(&mut data);
+            write_link(&mut writer, 1, 100, &[vec![0xFC, 1]]);
+            write_link(&mut writer, 2, 200, &[vec![0xFC, 2], vec![0xFC, 3]]);
+        }
+
+        let stream = demux_opus(&data).unwrap();
+        assert_eq!(stream.links.len(), 2);
+        assert_eq!(stream.links[1].head.pre_skip, 200);
+        assert_eq!(stream.links[1].packets.len(), 2);
+        assert_eq!(stream.ignored_streams, 0);
+    }
+
+    #[test]
+    fn test_multiplexed_non_opus_stream_is_ignored() {
+        let mut data = Vec::new();
+        {
+            let mut writer = PacketWriter::new(&mut data);
+            writer
+                .write_packet(opus_head(0).into(), 1, PacketWriteEndInfo::EndPage, 0)
+                .unwrap();
+            writer
+                .write_packet(b"\x80theora".to_vec().into(), 2, PacketWriteEndInfo::EndPage, 0)
+                .unwrap();
+            writer
+                .write_packet(opus_tags("test").into(), 1, PacketWriteEndInfo::EndPage, 0)
+                .unwrap();
+            writer
+                .write_packet(vec![0xFC, 9].into(), 2, PacketWriteEndInfo::EndStream, 1)
+                .unwrap();
+            writer
+                .write_packet(vec![0xFC, 1].into(), 1, PacketWriteEndInfo::EndStream, 960)
+                .unwrap();
+        }
+
+        let stream = demux_opus(&data).unwrap();
+        assert_eq!(stream.links.len(), 1);
+        assert_eq!(stream.links[0].packets, vec![vec![0xFC, 1]]);
+        assert_eq!(stream.ignored_streams, 1);
+    }
+
+    #[test]
+    fn test_non_opus_ogg_is_rejected() {
+        let mut data = Vec::new();
+        PacketWriter::new(&mut data)
+            .write_packet(b"\x01vorbis".to_vec().into(), 1, PacketWriteEndInfo::EndStream, 0)
+            .unwrap();
+        assert!(demux_opus(&data).is_err());
+    }
+}
@@ -0,0 +1,389 @@
+// ===================================
+// OpenAI 相容 API
+// /v1/audio/transcriptions 與 /v1/audio/translations
+// 讓既有 OpenAI 客戶端可直接改用本地 care-voice
+// ===================================
+
+use axum::{
+    extract::{Multipart, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde::Serialize;
+use std::sync::Arc;
+use tracing::{info, error, warn};
+
+use metrics::{counter, histogram};
+
+use crate::error::PipelineError;
+use crate::transcript_render;
+use crate::upload_options::UploadOptions;
+use crate::whisper_model_pool::{TranscriptionQuality, TranscriptionResult};
+use crate::WhisperService;
+
+/// OpenAI 格式的錯誤回應
+#[derive(Serialize)]
+pub struct OpenAiErrorResponse {
+    error: OpenAiErrorBody,
+}
+
+#[derive(Serialize)]
+struct OpenAiErrorBody {
+    message: String,
+    #[serde(rename = "type")]
+    error_type: &'static str,
+    param: Option<String>,
+    code: Option<String>,
+}
+
+type OpenAiError = (StatusCode, Json<OpenAiErrorResponse>);
+
+fn invalid_request(message: String, param: Option<&str>) -> OpenAiError {
+    (StatusCode::BAD_REQUEST, Json(OpenAiErrorResponse {
+        error: OpenAiErrorBody {
+            message,
+            error_type: "invalid_request_error",
+            param: param.map(str::to_string),
+            code: None,
+        },
+    }))
+}
+
+/// 管線錯誤轉為 OpenAI 格式，code 使用穩定的錯誤代碼
+fn pipeline_error(e: PipelineError, param: Option<&str>) -> OpenAiError {
+    let status = e.status();
+    let error_type = if status.is_client_error() { "invalid_request_error" } else { "server_error" };
+    (status, Json(OpenAiErrorResponse {
+        error: OpenAiErrorBody {
+            message: e.to_string(),
+            error_type,
+            param: param.map(str::to_string),
+            code: Some(e.code().to_string()),
+        },
+    }))
+}
+
+/// OpenAI response_format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OpenAiResponseFormat {
+    Json,
+    Text,
+    Srt,
+    Vtt,
+    VerboseJson,
+}
+
+impl OpenAiResponseFormat {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "text" => Some(Self::Text),
+            "srt" => Some(Self::Srt),
+            "vtt" => Some(Self::Vtt),
+            "verbose_json" => Some(Self::VerboseJson),
+            _ => None,
+        }
+    }
+}
+
+/// 轉錄或翻譯
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OpenAiTask {
+    Transcribe,
+    Translate,
+}
+
+impl OpenAiTask {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Transcribe => "transcribe",
+            Self::Translate => "translate",
+        }
+    }
+}
+
+/// json 回應
+#[derive(Serialize)]
+struct OpenAiTranscription {
+    text: String,
+}
+
+/// verbose_json 回應
+#[derive(Serialize)]
+struct OpenAiVerboseTranscription {
+    task: &'static str,
+    language: Option<String>,
+    duration: f64,
+    text: String,
+    segments: Vec<OpenAiSegment>,
+    /// 僅在 timestamp_granularities 含 word 時提供
+    #[serde(skip_serializing_if = "Option::is_none")]
+    words: Option<Vec<OpenAiWord>>,
+}
+
+#[derive(Serialize)]
+struct OpenAiSegment {
+    id: usize,
+    seek: u32,
+    start: f32,
+    end: f32,
+    text: String,
+    temperature: f32,
+    avg_logprob: Option<f32>,
+}
+
+#[derive(Serialize)]
+struct OpenAiWord {
+    word: String,
+    start: f32,
+    end: f32,
+}
+
+/// 將 OpenAI model 名稱對應到品質等級 ("whisper-1" 使用預設中文優化模型)
+fn quality_from_model(model: &str) -> Option<TranscriptionQuality> {
+    match model.trim() {
+        "whisper-1" => Some(TranscriptionQuality::Medium),
+        other => TranscriptionQuality::from_name(other),
+    }
+}
+
+/// 由檔名副檔名推測 MIME 類型 (客戶端常送出 application/octet-stream)
+fn mime_from_filename(file_name: &str) -> Option<&'static str> {
+    let extension = file_name.rsplit_once('.')?.1.to_ascii_lowercase();
+    match extension.as_str() {
+        "webm" => Some("audio/webm"),
+        "ogg" | "oga" | "opus" => Some("audio/ogg"),
+        "wav" => Some("audio/wav"),
+        "mp4" | "m4a" => Some("audio/mp4"),
+        _ => None,
+    }
+}
+
+/// POST /v1/audio/transcriptions
+pub async fn create_transcription(
+    State(whisper_service): State<Arc<WhisperService>>,
+    multipart: Multipart,
+) -> Result<Response, OpenAiError> {
+    handle_request(whisper_service, multipart, OpenAiTask::Transcribe).await
+}
+
+/// POST /v1/audio/translations
+pub async fn create_translation(
+    State(whisper_service): State<Arc<WhisperService>>,
+    multipart: Multipart,
+) -> Result<Response, OpenAiError> {
+    handle_request(whisper_service, multipart, OpenAiTask::Translate).await
+}
+
+async fn handle_request(
+    whisper_service: Arc<WhisperService>,
+    mut multipart: Multipart,
+    task: OpenAiTask,
+) -> Result<Response, OpenAiError> {
+    info!("🔌 收到 OpenAI 相容請求: {}", task.name());
+    counter!("openai_requests_total", "task" => task.name()).increment(1);
+
+    // OpenAI 預設自動偵測語言
+    let mut options = UploadOptions::default();
+    options.transcription.language = None;
+    options.transcription.translate = task == OpenAiTask::Translate;
+
+    let mut file = None;
+    let mut response_format = OpenAiResponseFormat::Json;
+    let mut word_timestamps = false;
+
+    while let Some(field) = multipart.next_field().await.map_err(|e| {
+        error!("Error reading multipart field: {}", e);
+        pipeline_error(PipelineError::from_multipart(e), None)
+    })? {
+        let field_name = field.name().unwrap_or("").to_string();
+
+        if field_name == "file" {
+            let mime_type = field
+                .content_type()
+                .filter(|mime| *mime != "application/octet-stream")
+                .map(str::to_string)
+                .or_else(|| field.file_name().and_then(mime_from_filename).map(str::to_string));
+            let data = field.bytes().await.map_err(|e| {
+                error!("Error reading file field: {}", e);
+                pipeline_error(PipelineError::from_multipart(e), Some("file"))
+            })?;
+            file = Some((data, mime_type));
+            continue;
+        }
+
+        let value = field.text().await.map_err(|e| {
+            error!("Error reading field {}: {}", field_name, e);
+            invalid_request(format!("Failed to read field: {}", field_name), Some(&field_name))
+        })?;
+        let value = value.trim();
+
+        match field_name.as_str() {
+            "model" => {
+                options.transcription.quality = quality_from_model(value).ok_or_else(|| {
+                    invalid_request(format!("Unsupported model: {}", value), Some("model"))
+                })?;
+            },
+            "language" | "temperature" => {
+                options.apply_field(&field_name, value)
+                    .map_err(|e| invalid_request(e, Some(&field_name)))?;
+            },
+            "prompt" => {
+                options.apply_field("initial_prompt", value)
+                    .map_err(|e| invalid_request(e, Some("prompt")))?;
+            },
+            "response_format" => {
+                response_format = OpenAiResponseFormat::from_name(value).ok_or_else(|| {
+                    invalid_request(
+                        format!("Unsupported response_format: {} (json, text, srt, vtt, verbose_json)", value),
+                        Some("response_format"),
+                    )
+                })?;
+            },
+            "timestamp_granularities" | "timestamp_granularities[]" => match value {
+                "segment" => {},
+                "word" => word_timestamps = true,
+                other => {
+                    return Err(invalid_request(
+                        format!("Unsupported timestamp granularity: {}", other),
+                        Some("timestamp_granularities"),
+                    ));
+                },
+            },
+            other => {
+                warn!("⚠️ 忽略不支援的 OpenAI 參數: {}", other);
+            },
+        }
+    }
+
+    let Some((data, mime_type)) = file else {
+        return Err(invalid_request("Missing required parameter: file".to_string(), Some("file")));
+    };
+    // OpenAI 請求沒有機構欄位，僅套用共用詞彙
+    whisper_service.apply_vocabulary(&mut options).map_err(|e| pipeline_error(e, None))?;
+
+    let audio = whisper_service.audio_decoder
+        .decode_audio_auto(&data, mime_type.as_deref())
+        .map_err(|e| {
+            error!("音頻解碼失敗: {}", e);
+            pipeline_error(e, Some("file"))
+        })?;
+    let duration = audio.duration_seconds();
+
+    let result = whisper_service.model_pool
+        .transcribe_blocking(audio.samples, options.transcription)
+        .await
+        .map_err(|e| {
+            error!("轉錄失敗: {}", e);
+            counter!("openai_requests_failed_total", "task" => task.name()).increment(1);
+            pipeline_error(e, None)
+        })?;
+
+    histogram!("openai_audio_duration_seconds").record(duration);
+    info!("✅ OpenAI 相容 {} 完成: {} 段, {:.1} 秒音頻", task.name(), result.segments.len(), duration);
+
+    if word_timestamps && response_format != OpenAiResponseFormat::VerboseJson {
+        warn!("⚠️ timestamp_granularities=word 僅適用於 verbose_json，已忽略");
+    }
+
+    Ok(render_response(result, response_format, task, duration, word_timestamps))
+}
+
+/// 段落開始位置，以 Whisper mel 幀 (10ms) 為單位
+fn seek_frames(start_time: f32) -> u32 {
+    (start_time.max(0.0) * 100.0).round() as u32
+}
+
+/// verbose_json 的 language 使用完整英文名稱 ("zh" → "chinese")，與 OpenAI 相同
+fn full_language_name(code: &str) -> String {
+    whisper_rs::get_lang_id(code)
+        .and_then(whisper_rs::get_lang_str_full)
+        .unwrap_or(code)
+        .to_string()
+}
+
+fn render_response(
+    result: TranscriptionResult,
+    response_format: OpenAiResponseFormat,
+    task: OpenAiTask,
+    duration: f64,
+    word_timestamps: bool,
+) -> Response {
+    match response_format {
+        OpenAiResponseFormat::Json => Json(OpenAiTranscription { text: result.transcript }).into_response(),
+        OpenAiResponseFormat::Text => (
+            [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+            transcript_render::render_text(&result.segments),
+        ).into_response(),
+        OpenAiResponseFormat::Srt => (
+            [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+            transcript_render::render_srt(&result.segments),
+        ).into_response(),
+        OpenAiResponseFormat::Vtt => (
+            [(header::CONTENT_TYPE, "text/vtt; charset=utf-8")],
+            transcript_render::render_vtt(&result.segments),
+        ).into_response(),
+        OpenAiResponseFormat::VerboseJson => {
+            let segments = result.segments.iter().enumerate().map(|(id, segment)| OpenAiSegment {
+                id,
+                seek: seek_frames(segment.start_time),
+                start: segment.start_time,
+                end: segment.end_time,
+                text: segment.text.clone(),
+                temperature: segment.temperature,
+                avg_logprob: segment.avg_logprob,
+            }).collect();
+            let words = word_timestamps.then(|| {
+                result.segments.iter()
+                    .flat_map(|segment| &segment.words)
+                    .map(|word| OpenAiWord { word: word.word.clone(), start: word.start, end: word.end })
+                    .collect()
+            });
+
+            Json(OpenAiVerboseTranscription {
+                task: task.name(),
+                language: result.language.as_deref().map(full_language_name),
+                duration,
+                text: result.transcript,
+                segments,
+                words,
+            }).into_response()
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_mapping() {
+        assert_eq!(quality_from_model("whisper-1"), Some(TranscriptionQuality::Medium));
+        assert_eq!(quality_from_model("premium"), Some(TranscriptionQuality::Premium));
+        assert_eq!(quality_from_model("gpt-4o-transcribe"), None);
+    }
+
+    #[test]
+    fn test_mime_from_filename() {
+        assert_eq!(mime_from_filename("recording.WEBM"), Some("audio/webm"));
+        assert_eq!(mime_from_filename("voice.opus"), Some("audio/ogg"));
+        assert_eq!(mime_from_filename("memo.m4a"), Some("audio/mp4"));
+        assert_eq!(mime_from_filename("audio"), None);
+    }
+
+    #[test]
+    fn test_verbose_json_seek_and_language() {
+        assert_eq!(seek_frames(0.0), 0);
+        assert_eq!(seek_frames(31.25), 3125);
+        assert_eq!(full_language_name("zh"), "chinese");
+        assert_eq!(full_language_name("en"), "english");
+        assert_eq!(full_language_name("xx"), "xx");
+    }
+
+    #[test]
+    fn test_response_format_names() {
+        assert_eq!(OpenAiResponseFormat::from_name("verbose_json"), Some(OpenAiResponseFormat::VerboseJson));
+        assert_eq!(OpenAiResponseFormat::from_name("SRT"), Some(OpenAiResponseFormat::Srt));
+        assert_eq!(OpenAiResponseFormat::from_name("xml"), None);
+    }
+}
@@ -8,6 +8,8 @@ use std::collections::VecDeque;
 use std::sync::Arc;
 use tracing::{debug, error, info, warn};
 
+use crate::{ogg_demuxer, webm_demuxer};
+
 // OPUS 支援 (條件編譯)
 #[cfg(feature = "opus-support")]
 use opus::{Channels, Decoder as OpusDecoder};
@@ -50,6 +52,15 @@ impl Default for OpusDecoderConfig {
     }
 }
 
+/// 容器層級的樣本裁切資訊 (單位: 48kHz 樣本)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OpusTrim {
+    /// 開頭需丟棄的編碼器延遲
+    pub pre_skip: usize,
+    /// 扣除 pre-skip 後的有效樣本數；None 表示不做尾端裁切
+    pub valid_samples: Option<u64>,
+}
+
 /// 高性能 Opus 解碼器
 pub struct CareVoiceOpusDecoder {
     config: OpusDecoderConfig,
@@ -145,11 +156,8 @@ impl CareVoiceOpusDecoder {
 
         let samples = match container_format {
             ContainerFormat::WebmOpus => {
-                warn!("📦 WebM-OPUS 暫時不支援，返回友善錯誤避免崩潰");
-                counter!("opus_decoder_webm_unsupported_total").increment(1);
-                return Err(anyhow::anyhow!(
-                    "WebM-OPUS 格式暫時不支援，請使用 OGG-OPUS 或其他格式"
-                ));
+                info!("📦 解析 WebM-OPUS 容器 (Chrome/Edge)");
+                self.decode_webm_opus(data)?
             }
             ContainerFormat::OggOpus => {
                 info!("📦 解析 OGG-OPUS 容器 (Firefox)");
@@ -186,33 +194,59 @@ impl CareVoiceOpusDecoder {
     fn decode_webm_opus(&self, data: &[u8]) -> Result<Vec<f32>> {
         info!("🔧 解析 WebM 容器...");
 
-        // 簡化 WebM 解析 - 尋找 OPUS 音頻軌道
-        // 在實際實現中，這裡會使用完整的 EBML/WebM 解析器
-        let opus_packets = self.extract_webm_opus_packets(data)?;
+        let stream = webm_demuxer::demux_opus(data)?;
 
-        if opus_packets.is_empty() {
+        if stream.packets.is_empty() {
             warn!("WebM 容器中未找到 OPUS 數據包");
-            return Ok(vec![]);
+            return Err(anyhow!("WebM 容器中未找到 OPUS 數據包"));
         }
 
-        info!("🎵 找到 {} 個 OPUS 數據包", opus_packets.len());
-        self.decode_opus_packets(&opus_packets)
+        info!(
+            "🎵 軌道 {} 找到 {} 個 OPUS 數據包 ({} 聲道, pre-skip {})",
+            stream.track_number,
+            stream.packets.len(),
+            stream.channels,
+            stream.pre_skip()
+        );
+        let trim = OpusTrim {
+            pre_skip: stream.pre_skip(),
+            valid_samples: None,
+        };
+        self.decode_opus_packets(&stream.packets, trim)
     }
 
     /// 解碼 OGG-OPUS (Firefox)
     fn decode_ogg_opus(&self, data: &[u8]) -> Result<Vec<f32>> {
         info!("🔧 解析 OGG 容器...");
 
-        // 簡化 OGG 解析 - 尋找 Opus 頁面
-        let opus_packets = self.extract_ogg_opus_packets(data)?;
+        let stream = ogg_demuxer::demux_opus(data)?;
+        info!(
+            "🎵 找到 {} 個 OPUS 數據包 ({} 段 link)",
+            stream.packet_count(),
+            stream.links.len()
+        );
 
-        if opus_packets.is_empty() {
-            warn!("OGG 容器中未找到 OPUS 數據包");
-            return Ok(vec![]);
+        // 串接的 link 各自有 pre-skip 與結尾 granule，逐段解碼後串接；
+        // 每段是獨立的編碼流，解碼前重置狀態，串接完成後才統一正規化，避免增益在段落交界跳動
+        let mut samples = Vec::new();
+        for link in &stream.links {
+            if link.packets.is_empty() {
+                continue;
+            }
+            debug!(
+                "解碼 Ogg link serial={:08x}: {} 個包, 編碼器={}",
+                link.serial,
+                link.packets.len(),
+                link.vendor.as_deref().unwrap_or("未知")
+            );
+            let trim = OpusTrim {
+                pre_skip: link.head.pre_skip as usize,
+                valid_samples: link.valid_samples(),
+            };
+            self.reset()?;
+            samples.extend(self.decode_opus_samples(&link.packets, trim)?);
         }
-
-        info!("🎵 找到 {} 個 OPUS 數據包", opus_packets.len());
-        self.decode_opus_packets(&opus_packets)
+        self.post_process_audio(samples)
     }
 
     /// 解碼原始 OPUS 數據 - WebCodecs 專用（修復版本）
@@ -239,7 +273,7 @@ impl CareVoiceOpusDecoder {
         warn!("⚠️ WebCodecs 數據不應該使用原始流解碼，請使用獨立包模式");
         
         // 嘗試後備方案，但記錄警告
-        counter!("opus_raw_decode_fallback_usage").increment(1);
+        counter!("opus_raw_decode_fallback_total").increment(1);
         self.decode_webcodecs_fallback(data)
     }
 
@@ -263,7 +297,7 @@ impl CareVoiceOpusDecoder {
         );
         
         // 直接使用現有的包解碼邏輯，不需要拆分
-        let samples = self.decode_opus_packets(packets)?;
+        let samples = self.decode_opus_packets(packets, OpusTrim::default())?;
         
         info!("✅ WebCodecs 獨立包解碼完成: {} samples", samples.len());
         Ok(samples)
@@ -734,8 +768,17 @@ impl CareVoiceOpusDecoder {
         config <= 31 && stereo <= 1 && frame_count <= 3
     }
 
+    /// 解碼 OPUS 數據包並做音頻後處理
+    fn decode_opus_packets(&self, packets: &[Vec<u8>], trim: OpusTrim) -> Result<Vec<f32>> {
+        let samples = self.decode_opus_samples(packets, trim)?;
+        self.post_process_audio(samples)
+    }
+
     /// 核心 OPUS 數據包解碼 - 業界領先實現（增強診斷版本）
-    fn decode_opus_packets(&self, packets: &[Vec<u8>]) -> Result<Vec<f32>> {
+    ///
+    /// `trim` 為容器標頭宣告的 pre-skip 與有效長度，解碼後裁切開頭與結尾；
+    /// 回傳未經後處理的樣本，供串接多段後再統一正規化
+    fn decode_opus_samples(&self, packets: &[Vec<u8>], trim: OpusTrim) -> Result<Vec<f32>> {
         info!("🎵 開始 OPUS 包解碼: {} 個包", packets.len());
 
         // 統計數據包大小分佈
@@ -753,6 +796,7 @@ impl CareVoiceOpusDecoder {
         #[cfg(feature = "opus-support")]
         {
             if let Some(ref decoder) = self.decoder {
+                let channel_count = self.config.channels.max(1) as usize;
                 let mut all_samples = Vec::new();
                 let mut successful_packets = 0;
                 let mut failed_packets = 0;
@@ -793,10 +837,10 @@ impl CareVoiceOpusDecoder {
                         debug!("📋 OPUS TOC: 配置={}, 聲道={}", config, channels);
                     }
 
-                    // 為每個包創建輸出緩衝區 (最大 120ms @ 48kHz = 5760 samples)
+                    // 為每個包創建輸出緩衝區 (最大 120ms @ 48kHz = 5760 samples/聲道，立體聲交錯輸出)
                     // 對於 WebCodecs，通常是 20ms 幀，所以 960 samples @ 48kHz
                     let max_frame_size = 5760;
-                    let mut output = vec![0f32; max_frame_size];
+                    let mut output = vec![0f32; max_frame_size * channel_count];
 
                     // 🚀 業界領先 RAII 鎖作用域管理 - 主解碼
                     let decode_start = std::time::Instant::now();
@@ -815,8 +859,8 @@ impl CareVoiceOpusDecoder {
                                     sample_count,
                                     decode_time
                                 );
-                                // 只取實際解碼的樣本數
-                                all_samples.extend_from_slice(&output[..sample_count]);
+                                // 只取實際解碼的樣本數 (decode_float 回傳每聲道樣本數)
+                                all_samples.extend_from_slice(&output[..sample_count * channel_count]);
                                 successful_packets += 1;
                             } else {
                                 warn!(
@@ -843,7 +887,7 @@ impl CareVoiceOpusDecoder {
                                 Ok(sample_count) => {
                                     if sample_count > 0 {
                                         info!("✅ FEC 恢復成功: {} samples", sample_count);
-                                        all_samples.extend_from_slice(&output[..sample_count]);
+                                        all_samples.extend_from_slice(&output[..sample_count * channel_count]);
                                         successful_packets += 1; // FEC 恢復也算成功
                                     } else {
                                         warn!("⚠️ FEC 恢復返回 0 samples");
@@ -881,9 +925,24 @@ impl CareVoiceOpusDecoder {
                     return Err(anyhow!("所有 OPUS 包解碼都失敗，無音頻數據"));
                 }
 
-                // 音頻後處理
-                let processed_samples = self.post_process_audio(all_samples)?;
-                Ok(processed_samples)
+                // 丟棄編碼器延遲與結尾填充 (以 48kHz 計，換算為輸出採樣率與聲道數)
+                let to_output = |samples_48k: u64| {
+                    (samples_48k * self.config.sample_rate as u64 / 48000) as usize
+                        * self.config.channels as usize
+                };
+                let skip = to_output(trim.pre_skip as u64).min(all_samples.len());
+                if skip > 0 {
+                    debug!("✂️ 丟棄 pre-skip: {} samples", skip);
+                    all_samples.drain(..skip);
+                }
+                if let Some(valid) = trim.valid_samples.map(to_output) {
+                    if valid < all_samples.len() {
+                        debug!("✂️ 裁切結尾填充: {} samples", all_samples.len() - valid);
+                        all_samples.truncate(valid);
+                    }
+                }
+
+                Ok(all_samples)
             } else {
                 error!("🖥️ OPUS 解碼器未初始化");
                 counter!("opus_decoder_not_initialized_total").increment(1);
@@ -902,7 +961,7 @@ impl CareVoiceOpusDecoder {
     }
 
     /// 音頻後處理 (重採樣、單聲道轉換、正規化)
-    fn post_process_audio(&self, mut samples: Vec<f32>) -> Result<Vec<f32>> {
+    pub(crate) fn post_process_audio(&self, mut samples: Vec<f32>) -> Result<Vec<f32>> {
         info!("🔧 音頻後處理: {} samples", samples.len());
 
         if samples.is_empty() {
@@ -974,59 +1033,14 @@ impl CareVoiceOpusDecoder {
         }
     }
 
-    /// 簡化 WebM OPUS 包提取
-    fn extract_webm_opus_packets(&self, _data: &[u8]) -> Result<Vec<Vec<u8>>> {
-        // 臨時修復：WebM 容器解析複雜，暫時跳過
-        // 讓系統回退到原始 OPUS 處理以避免數據流破壞
-        warn!("⚠️  WebM 解析暫時禁用，回退到原始處理以避免數據流破壞");
-
-        // 返回空，讓系統回退到 Unknown 格式處理
-        // 這樣可以避免破壞 OPUS 數據流
-        Ok(vec![])
-    }
-
-    /// 簡化 OGG OPUS 包提取
-    fn extract_ogg_opus_packets(&self, data: &[u8]) -> Result<Vec<Vec<u8>>> {
-        // 暫時的簡化實現
-        // 在實際實現中，需要完整的 OGG 解析器
-        warn!("⚠️  OGG 解析使用簡化實現");
-
-        // 尋找 OGG 頁面
-        let mut packets = Vec::new();
-        let mut pos = 0;
-
-        while pos < data.len() {
-            // 尋找 "OggS" 頁面頭
-            if pos + 4 <= data.len() && &data[pos..pos + 4] == b"OggS" {
-                debug!("找到 OGG 頁面於位置 {}", pos);
-
-                // 跳過頁面頭部 (簡化)
-                if pos + 27 < data.len() {
-                    let segment_table_length = data[pos + 26] as usize;
-                    let page_start = pos + 27 + segment_table_length;
-
-                    if page_start < data.len() {
-                        // 取頁面數據的一部分作為 OPUS 包
-                        let page_end = std::cmp::min(page_start + 4096, data.len());
-                        packets.push(data[page_start..page_end].to_vec());
-                    }
-                }
-                pos += 27; // 跳過基本頁面頭
-            } else {
-                pos += 1;
-            }
-        }
-
-        Ok(packets)
-    }
-
-    /// 重置解碼器狀態
-    pub fn reset(&mut self) -> Result<()> {
+    /// 重置解碼器狀態 (新的 OpusHead 開始時呼叫)
+    pub fn reset(&self) -> Result<()> {
         info!("🔄 重置 Opus 解碼器狀態");
 
         #[cfg(feature = "opus-support")]
-        if let Some(ref mut _decoder) = self.decoder {
-            // OPUS 解碼器重置 (如果API支援)
+        if let Some(ref decoder) = self.decoder {
+            decoder.lock().reset_state()
+                .map_err(|e| anyhow!("OPUS 解碼器重置失敗: {}", e))?;
             debug!("重置原生 OPUS 解碼器");
         }
 
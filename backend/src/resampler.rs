@@ -0,0 +1,133 @@
+// ===================================
+// 通用重採樣 - 任意採樣率 → 16kHz (Whisper 輸入)
+// 基於 rubato 的 FFT 同步重採樣器，補償濾波延遲並精確對齊輸出長度
+// ===================================
+
+use anyhow::{anyhow, Result};
+use metrics::{counter, histogram};
+use rubato::{FftFixedIn, Resampler};
+use tracing::{debug, info};
+
+/// Whisper 要求的採樣率
+pub const WHISPER_SAMPLE_RATE: u32 = 16_000;
+
+/// 每次送入重採樣器的輸入幀數
+const CHUNK_SIZE: usize = 1024;
+/// FFT 子區塊數 (越多延遲越低，品質略降)
+const SUB_CHUNKS: usize = 2;
+
+/// 重採樣單聲道音頻到 16kHz
+pub fn resample_to_16k(samples: &[f32], source_rate: u32) -> Result<Vec<f32>> {
+    resample(samples, source_rate, WHISPER_SAMPLE_RATE)
+}
+
+/// 重採樣單聲道音頻；輸出長度為 `len * to / from` (四捨五入)
+pub fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Result<Vec<f32>> {
+    if from_rate == 0 || to_rate == 0 {
+        return Err(anyhow!("無效的採樣率: {} → {}", from_rate, to_rate));
+    }
+    if from_rate == to_rate || samples.is_empty() {
+        return Ok(samples.to_vec());
+    }
+
+    let resample_start = std::time::Instant::now();
+
+    let mut resampler = FftFixedIn::<f32>::new(
+        from_rate as usize,
+        to_rate as usize,
+        CHUNK_SIZE,
+        SUB_CHUNKS,
+        1,
+    )
+    .map_err(|e| anyhow!("無法建立重採樣器 ({} → {} Hz): {}", from_rate, to_rate, e))?;
+
+    let expected = ((samples.len() as u64 * to_rate as u64 + from_rate as u64 / 2) / from_rate as u64) as usize;
+    let delay = resampler.output_delay();
+    let mut output = Vec::with_capacity(expected + delay + resampler.output_frames_max());
+
+    let mut pos = 0;
+    while samples.len() - pos >= resampler.input_frames_next() {
+        let frames = resampler.input_frames_next();
+        let chunk = resampler
+            .process(&[&samples[pos..pos + frames]], None)
+            .map_err(|e| anyhow!("重採樣失敗: {}", e))?;
+        output.extend_from_slice(&chunk[0]);
+        pos += frames;
+    }
+
+    // 剩餘不足一個區塊的輸入以零填充處理，之後持續送入空輸入直到延遲被排出
+    let tail_input = [&samples[pos..]];
+    let mut tail = Some(&tail_input[..]);
+    while output.len() < expected + delay {
+        let chunk = resampler
+            .process_partial(tail.take(), None)
+            .map_err(|e| anyhow!("重採樣失敗: {}", e))?;
+        if chunk[0].is_empty() {
+            break;
+        }
+        output.extend_from_slice(&chunk[0]);
+    }
+
+    output.drain(..delay.min(output.len()));
+    output.truncate(expected);
+
+    let resample_time = resample_start.elapsed();
+    histogram!("audio_resample_time_ms").record(resample_time.as_millis() as f64);
+    counter!("audio_resample_total", "source_rate" => from_rate.to_string()).increment(1);
+
+    debug!("重採樣延遲補償: {} frames", delay);
+    info!(
+        "🔄 重採樣完成: {}Hz → {}Hz, {} → {} samples, 耗時: {:?}",
+        from_rate,
+        to_rate,
+        samples.len(),
+        output.len(),
+        resample_time
+    );
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(freq: f32, rate: u32, seconds: f32) -> Vec<f32> {
+        (0..(rate as f32 * seconds) as usize)
+            .map(|i| (2.0 * std::f32::consts::PI * freq * i as f32 / rate as f32).sin() * 0.5)
+            .collect()
+    }
+
+    #[test]
+    fn test_output_length_matches_rate_ratio() {
+        for rate in [8000, 22050, 44100, 48000] {
+            let input = vec![0.1f32; rate as usize + 123];
+            let output = resample_to_16k(&input, rate).unwrap();
+            let expected = ((input.len() as u64 * 16000 + rate as u64 / 2) / rate as u64) as usize;
+            assert_eq!(output.len(), expected, "source rate {}", rate);
+        }
+    }
+
+    #[test]
+    fn test_tone_survives_and_stays_aligned() {
+        // 440Hz 正弦波在 44.1kHz → 16kHz 後應保持相同相位與振幅
+        let input = sine(440.0, 44100, 1.0);
+        let output = resample_to_16k(&input, 44100).unwrap();
+        let reference = sine(440.0, 16000, 1.0);
+
+        // 忽略兩端的濾波暫態
+        let max_error = output[800..15200]
+            .iter()
+            .zip(&reference[800..15200])
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0f32, f32::max);
+        assert!(max_error < 0.02, "max error {}", max_error);
+    }
+
+    #[test]
+    fn test_same_rate_is_passthrough() {
+        let input = vec![0.25f32; 100];
+        assert_eq!(resample_to_16k(&input, 16000).unwrap(), input);
+        assert!(resample_to_16k(&input, 0).is_err());
+    }
+}
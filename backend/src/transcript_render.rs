@@ -0,0 +1,506 @@
+// ===================================
+// 轉錄結果輸出格式
+// 純文字 / SRT / WebVTT / TSV / Markdown 渲染
+// ===================================
+
+use crate::whisper_model_pool::TranscriptSegment;
+use std::borrow::Cow;
+
+/// 字幕每行最大顯示寬度 (全形字元計 2，約 21 個中文字)
+const MAX_SUBTITLE_LINE_WIDTH: usize = 42;
+/// 每則字幕最多行數，超過時依文字長度分配時間拆成多則
+const MAX_SUBTITLE_LINES: usize = 2;
+/// 段落間隔超過此秒數時 Markdown 另起新段
+const PARAGRAPH_GAP_SECONDS: f32 = 2.0;
+/// Markdown 單一段落最長秒數
+const MAX_PARAGRAPH_SECONDS: f32 = 60.0;
+
+/// 轉錄結果輸出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TranscriptFormat {
+    /// 完整 JSON 回應 (預設)
+    #[default]
+    Json,
+    /// 純文字，每段一行
+    Text,
+    /// SubRip 字幕
+    Srt,
+    /// WebVTT 字幕
+    Vtt,
+    /// Tab 分隔 (毫秒時間戳)
+    Tsv,
+    /// 依停頓分段的 Markdown 文件
+    Markdown,
+}
+
+impl TranscriptFormat {
+    /// 可用的格式名稱 (用於錯誤訊息)
+    pub const NAMES: &'static str = "json, text, srt, vtt, tsv, markdown";
+
+    /// 從 format / response_format 參數解析
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "text" | "txt" => Some(Self::Text),
+            "srt" => Some(Self::Srt),
+            "vtt" | "webvtt" => Some(Self::Vtt),
+            "tsv" => Some(Self::Tsv),
+            "markdown" | "md" => Some(Self::Markdown),
+            _ => None,
+        }
+    }
+
+    /// 從 Accept 標頭協商格式，依 q 值由高到低選第一個支援的類型
+    pub fn from_accept(accept: &str) -> Option<Self> {
+        let mut candidates: Vec<(f32, &str)> = accept
+            .split(',')
+            .filter_map(|entry| {
+                let mut parts = entry.split(';');
+                let media_type = parts.next()?.trim();
+                let quality = parts
+                    .filter_map(|param| param.trim().strip_prefix("q="))
+                    .find_map(|q| q.trim().parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                Some((quality, media_type))
+            })
+            .filter(|(quality, _)| *quality > 0.0)
+            .collect();
+        // 穩定排序保留同 q 值時的原始順序
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        candidates.into_iter().find_map(|(_, media_type)| {
+            match media_type.to_ascii_lowercase().as_str() {
+                "application/json" => Some(Self::Json),
+                "text/plain" => Some(Self::Text),
+                "application/x-subrip" | "text/srt" => Some(Self::Srt),
+                "text/vtt" => Some(Self::Vtt),
+                "text/tab-separated-values" => Some(Self::Tsv),
+                "text/markdown" => Some(Self::Markdown),
+                _ => None,
+            }
+        })
+    }
+
+    /// 參數使用的格式名稱
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Text => "text",
+            Self::Srt => "srt",
+            Self::Vtt => "vtt",
+            Self::Tsv => "tsv",
+            Self::Markdown => "markdown",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Text => "text/plain; charset=utf-8",
+            Self::Srt => "application/x-subrip; charset=utf-8",
+            Self::Vtt => "text/vtt; charset=utf-8",
+            Self::Tsv => "text/tab-separated-values; charset=utf-8",
+            Self::Markdown => "text/markdown; charset=utf-8",
+        }
+    }
+
+    /// 渲染非 JSON 格式 (JSON 由各端點自行序列化)
+    pub fn render(&self, segments: &[TranscriptSegment]) -> Option<String> {
+        match self {
+            Self::Json => None,
+            Self::Text => Some(render_text(segments)),
+            Self::Srt => Some(render_srt(segments)),
+            Self::Vtt => Some(render_vtt(segments)),
+            Self::Tsv => Some(render_tsv(segments)),
+            Self::Markdown => Some(render_markdown(segments)),
+        }
+    }
+}
+
+/// 時間戳格式：SRT 使用逗號分隔毫秒，WebVTT 使用句點
+fn format_timestamp(seconds: f32, millis_separator: char) -> String {
+    let total_ms = to_millis(seconds);
+    let hours = total_ms / 3_600_000;
+    let minutes = (total_ms % 3_600_000) / 60_000;
+    let secs = (total_ms % 60_000) / 1000;
+    let millis = total_ms % 1000;
+    format!("{:02}:{:02}:{:02}{}{:03}", hours, minutes, secs, millis_separator, millis)
+}
+
+fn to_millis(seconds: f32) -> u64 {
+    (seconds.max(0.0) as f64 * 1000.0).round() as u64
+}
+
+/// 過濾空白段落並去除首尾空白 (Whisper 段落通常以空格開頭)
+fn cues(segments: &[TranscriptSegment]) -> impl Iterator<Item = (&TranscriptSegment, &str)> {
+    segments
+        .iter()
+        .map(|segment| (segment, segment.text.trim()))
+        .filter(|(_, text)| !text.is_empty())
+}
+
+/// 逐聲道轉錄的段落在文字前加上說話者標籤
+fn labelled<'a>(segment: &TranscriptSegment, text: &'a str) -> Cow<'a, str> {
+    match &segment.speaker {
+        Some(speaker) => Cow::Owned(format!("[{}] {}", speaker, text)),
+        None => Cow::Borrowed(text),
+    }
+}
+
+/// 全形字元 (中日韓文字、全形標點) 顯示寬度為 2
+fn is_wide(c: char) -> bool {
+    matches!(c as u32,
+        0x1100..=0x115F      // 韓文字母
+        | 0x2E80..=0x303E    // CJK 部首、標點
+        | 0x3040..=0x33FF    // 假名、注音
+        | 0x3400..=0x4DBF    // CJK 擴展 A
+        | 0x4E00..=0x9FFF    // CJK 統一漢字
+        | 0xAC00..=0xD7A3    // 韓文音節
+        | 0xF900..=0xFAFF    // CJK 相容漢字
+        | 0xFE30..=0xFE4F    // CJK 相容標點
+        | 0xFF00..=0xFF60    // 全形 ASCII
+        | 0xFFE0..=0xFFE6
+        | 0x20000..=0x3FFFD  // CJK 擴展 B 以後
+    )
+}
+
+fn display_width(text: &str) -> usize {
+    text.chars().map(|c| if is_wide(c) { 2 } else { 1 }).sum()
+}
+
+/// 不可出現在行首的標點 (避頭點)
+fn is_no_break_start(text: &str) -> bool {
+    text.chars().next().is_some_and(|c| {
+        "，。、；：！？）」』】》〉…,.;:!?)".contains(c)
+    })
+}
+
+/// 字幕斷行：中日韓文字可在任意字間斷行，英文單字不拆開，標點不置於行首
+fn wrap_subtitle_text(text: &str, max_width: usize) -> Vec<String> {
+    // 拆分為可斷行單位：單一全形字元或連續半形單字
+    let mut tokens: Vec<String> = Vec::new();
+    let mut word = String::new();
+    for c in text.chars() {
+        if c.is_whitespace() || is_wide(c) {
+            if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+            tokens.push(if c.is_whitespace() { " ".to_string() } else { c.to_string() });
+        } else {
+            word.push(c);
+        }
+    }
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut pending_space = false;
+
+    for token in tokens {
+        if token == " " {
+            pending_space = !line.is_empty();
+            continue;
+        }
+
+        let separator = if pending_space { 1 } else { 0 };
+        let fits = display_width(&line) + separator + display_width(&token) <= max_width;
+        if !fits && !line.is_empty() && !is_no_break_start(&token) {
+            lines.push(std::mem::take(&mut line));
+            pending_space = false;
+        }
+
+        if pending_space {
+            line.push(' ');
+            pending_space = false;
+        }
+        line.push_str(&token);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// 一則字幕：時間範圍與已斷行的文字
+struct SubtitleCue {
+    start_time: f32,
+    end_time: f32,
+    lines: Vec<String>,
+}
+
+/// 段落斷行後每 MAX_SUBTITLE_LINES 行為一則，依各則顯示寬度按比例分配段落時間
+fn subtitle_cues(segment: &TranscriptSegment, text: &str) -> Vec<SubtitleCue> {
+    let lines = wrap_subtitle_text(text, MAX_SUBTITLE_LINE_WIDTH);
+    let total_width = lines.iter().map(|line| display_width(line)).sum::<usize>().max(1) as f32;
+    let duration = (segment.end_time - segment.start_time).max(0.0);
+
+    let mut consumed = 0;
+    lines
+        .chunks(MAX_SUBTITLE_LINES)
+        .map(|cue_lines| {
+            let start_time = segment.start_time + duration * consumed as f32 / total_width;
+            consumed += cue_lines.iter().map(|line| display_width(line)).sum::<usize>();
+            SubtitleCue {
+                start_time,
+                end_time: segment.start_time + duration * consumed as f32 / total_width,
+                lines: cue_lines.to_vec(),
+            }
+        })
+        .collect()
+}
+
+/// WebVTT 文字中 `&`、`<`、`>` 必須轉義
+fn escape_vtt(text: &str) -> Cow<'_, str> {
+    if !text.contains(['&', '<', '>']) {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;"))
+}
+
+/// 合併段落文字：中文直接相連，英文單字間補空格
+pub fn join_texts<'a>(texts: impl Iterator<Item = &'a str>) -> String {
+    let mut joined = String::new();
+    for text in texts {
+        let needs_space = joined.chars().last().is_some_and(|c| c.is_ascii_alphanumeric() || ",.!?;:".contains(c))
+            && text.chars().next().is_some_and(|c| c.is_ascii_alphanumeric());
+        if needs_space {
+            joined.push(' ');
+        }
+        joined.push_str(text);
+    }
+    joined
+}
+
+/// 純文字：每段一行
+pub fn render_text(segments: &[TranscriptSegment]) -> String {
+    let mut output = cues(segments)
+        .map(|(segment, text)| labelled(segment, text))
+        .collect::<Vec<_>>()
+        .join("\n");
+    output.push('\n');
+    output
+}
+
+/// SubRip (.srt) 字幕
+pub fn render_srt(segments: &[TranscriptSegment]) -> String {
+    let mut output = String::new();
+    let subtitles = cues(segments).flat_map(|(segment, text)| subtitle_cues(segment, &labelled(segment, text)));
+    for (index, cue) in subtitles.enumerate() {
+        output.push_str(&format!(
+            "{}\n{} --> {}\n{}\n\n",
+            index + 1,
+            format_timestamp(cue.start_time, ','),
+            format_timestamp(cue.end_time, ','),
+            cue.lines.join("\n")
+        ));
+    }
+    output
+}
+
+/// WebVTT (.vtt) 字幕；說話者以 `<v>` 語音標籤標示
+pub fn render_vtt(segments: &[TranscriptSegment]) -> String {
+    let mut output = String::from("WEBVTT\n\n");
+    for (segment, text) in cues(segments) {
+        let voice = segment.speaker.as_ref().map(|speaker| format!("<v {}>", escape_vtt(speaker))).unwrap_or_default();
+        for cue in subtitle_cues(segment, text) {
+            let lines: Vec<_> = cue.lines.iter().map(|line| escape_vtt(line)).collect();
+            output.push_str(&format!(
+                "{} --> {}\n{}{}\n\n",
+                format_timestamp(cue.start_time, '.'),
+                format_timestamp(cue.end_time, '.'),
+                voice,
+                lines.join("\n")
+            ));
+        }
+    }
+    output
+}
+
+/// TSV：start / end (毫秒) 與文字，與 whisper.cpp 輸出相容
+pub fn render_tsv(segments: &[TranscriptSegment]) -> String {
+    let mut output = String::from("start\tend\ttext\n");
+    for (segment, text) in cues(segments) {
+        let text = labelled(segment, text).replace(['\t', '\n', '\r'], " ");
+        output.push_str(&format!(
+            "{}\t{}\t{}\n",
+            to_millis(segment.start_time),
+            to_millis(segment.end_time),
+            text
+        ));
+    }
+    output
+}
+
+/// Markdown：依停頓 (或說話者切換) 將段落合併為帶時間標記的文章段落
+pub fn render_markdown(segments: &[TranscriptSegment]) -> String {
+    let mut paragraphs: Vec<Vec<(&TranscriptSegment, &str)>> = Vec::new();
+
+    for (segment, text) in cues(segments) {
+        let starts_new = match paragraphs.last().and_then(|p| Some((p.first()?, p.last()?))) {
+            Some(((first, _), (last, _))) => {
+                segment.start_time - last.end_time >= PARAGRAPH_GAP_SECONDS
+                    || segment.end_time - first.start_time > MAX_PARAGRAPH_SECONDS
+                    || segment.speaker != last.speaker
+            },
+            None => true,
+        };
+
+        if starts_new {
+            paragraphs.push(Vec::new());
+        }
+        if let Some(paragraph) = paragraphs.last_mut() {
+            paragraph.push((segment, text));
+        }
+    }
+
+    let mut output = String::from("# 轉錄內容\n");
+    for paragraph in paragraphs {
+        let first = paragraph[0].0;
+        let timestamp = format_timestamp(first.start_time, '.');
+        let text = join_texts(paragraph.iter().map(|(_, text)| *text));
+        // 去掉毫秒，段落標記只需精確到秒
+        match &first.speaker {
+            Some(speaker) => output.push_str(&format!("\n**[{}] {}:** {}\n", &timestamp[..8], speaker, text)),
+            None => output.push_str(&format!("\n**[{}]** {}\n", &timestamp[..8], text)),
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spoken(start_time: f32, end_time: f32, text: &str, channel: usize, speaker: &str) -> TranscriptSegment {
+        TranscriptSegment {
+            channel: Some(channel),
+            speaker: Some(speaker.to_string()),
+            ..TranscriptSegment::fixture(start_time, end_time, text)
+        }
+    }
+
+    #[test]
+    fn test_timestamp_formatting() {
+        assert_eq!(format_timestamp(0.0, ','), "00:00:00,000");
+        assert_eq!(format_timestamp(3725.5, ','), "01:02:05,500");
+        assert_eq!(format_timestamp(1.234, '.'), "00:00:01.234");
+    }
+
+    #[test]
+    fn test_render_srt_skips_empty_segments() {
+        let segments = vec![
+            TranscriptSegment::fixture(0.0, 2.5, " 你好"),
+            TranscriptSegment::fixture(2.5, 3.0, "  "),
+            TranscriptSegment::fixture(3.0, 4.0, " 今天感覺如何"),
+        ];
+
+        assert_eq!(
+            render_srt(&segments),
+            "1\n00:00:00,000 --> 00:00:02,500\n你好\n\n2\n00:00:03,000 --> 00:00:04,000\n今天感覺如何\n\n"
+        );
+    }
+
+    #[test]
+    fn test_render_vtt_and_text() {
+        let segments = vec![TranscriptSegment::fixture(0.0, 1.5, " hello"), TranscriptSegment::fixture(1.5, 3.0, " world")];
+
+        assert_eq!(
+            render_vtt(&segments),
+            "WEBVTT\n\n00:00:00.000 --> 00:00:01.500\nhello\n\n00:00:01.500 --> 00:00:03.000\nworld\n\n"
+        );
+        assert_eq!(render_text(&segments), "hello\nworld\n");
+    }
+
+    #[test]
+    fn test_render_vtt_escapes_markup() {
+        let segments = vec![spoken(0.0, 2.0, " 血壓 < 90 & 心跳 > 100", 0, "A&B")];
+
+        assert_eq!(
+            render_vtt(&segments),
+            "WEBVTT\n\n00:00:00.000 --> 00:00:02.000\n<v A&amp;B>血壓 &lt; 90 &amp; 心跳 &gt; 100\n\n"
+        );
+        // SRT 為純文字，不需轉義
+        assert!(render_srt(&segments).contains("[A&B] 血壓 < 90 & 心跳 > 100"));
+    }
+
+    #[test]
+    fn test_long_segment_is_split_into_two_line_cues() {
+        let text = "今天早上量的血壓是一百四十比九十，比昨天高一些，午餐後要記得吃降血壓的藥，如果下午頭暈或是胸口悶就要馬上打電話給護理師，晚上再量一次血壓並且記錄下來";
+        let segments = vec![TranscriptSegment::fixture(10.0, 40.0, text)];
+
+        let srt = render_srt(&segments);
+        let cues: Vec<&str> = srt.trim_end().split("\n\n").collect();
+        assert!(cues.len() > 1);
+        for cue in &cues {
+            assert!(cue.lines().count() <= 2 + MAX_SUBTITLE_LINES);
+        }
+        assert!(cues[0].contains("00:00:10,000 --> "));
+        assert!(cues.last().unwrap().contains(" --> 00:00:40,000"));
+
+        let vtt = render_vtt(&segments);
+        assert_eq!(vtt.matches(" --> ").count(), cues.len());
+        let rejoined: String = vtt.lines().filter(|line| !line.contains(" --> ") && *line != "WEBVTT").collect();
+        assert_eq!(rejoined, text);
+    }
+
+    #[test]
+    fn test_wrap_cjk_keeps_punctuation_off_line_start() {
+        let lines = wrap_subtitle_text("今天早上血壓有點高，請記得吃藥", 18);
+        assert_eq!(lines, vec!["今天早上血壓有點高，", "請記得吃藥"]);
+    }
+
+    #[test]
+    fn test_wrap_mixed_text_does_not_split_words() {
+        let lines = wrap_subtitle_text("請量 blood pressure 然後休息", 16);
+        assert_eq!(lines, vec!["請量 blood", "pressure 然後休", "息"]);
+        assert!(lines.iter().all(|line| display_width(line) <= 16));
+    }
+
+    #[test]
+    fn test_render_tsv_and_markdown() {
+        let segments = vec![
+            TranscriptSegment::fixture(0.0, 1.0, " 早安"),
+            TranscriptSegment::fixture(1.2, 2.0, " 今天好嗎"),
+            TranscriptSegment::fixture(5.0, 6.5, " Take\tcare"),
+        ];
+
+        assert_eq!(
+            render_tsv(&segments),
+            "start\tend\ttext\n0\t1000\t早安\n1200\t2000\t今天好嗎\n5000\t6500\tTake care\n"
+        );
+        assert_eq!(
+            render_markdown(&segments),
+            "# 轉錄內容\n\n**[00:00:00]** 早安今天好嗎\n\n**[00:00:05]** Take\tcare\n"
+        );
+    }
+
+    #[test]
+    fn test_speaker_labels() {
+        let segments = vec![
+            spoken(0.0, 1.0, " 您好", 0, "caregiver"),
+            spoken(1.0, 2.0, " 您好", 1, "client"),
+            spoken(2.0, 3.0, " 今天量過血壓嗎", 0, "caregiver"),
+        ];
+
+        assert_eq!(render_text(&segments), "[caregiver] 您好\n[client] 您好\n[caregiver] 今天量過血壓嗎\n");
+        assert!(render_vtt(&segments).contains("00:00:01.000 --> 00:00:02.000\n<v client>您好\n"));
+        assert!(render_srt(&segments).contains("2\n00:00:01,000 --> 00:00:02,000\n[client] 您好\n"));
+        assert_eq!(
+            render_markdown(&segments),
+            "# 轉錄內容\n\n**[00:00:00] caregiver:** 您好\n\n**[00:00:01] client:** 您好\n\n**[00:00:02] caregiver:** 今天量過血壓嗎\n"
+        );
+    }
+
+    #[test]
+    fn test_accept_negotiation() {
+        assert_eq!(TranscriptFormat::from_accept("text/vtt"), Some(TranscriptFormat::Vtt));
+        assert_eq!(
+            TranscriptFormat::from_accept("application/json;q=0.5, application/x-subrip"),
+            Some(TranscriptFormat::Srt)
+        );
+        assert_eq!(TranscriptFormat::from_accept("text/html, */*"), None);
+        assert_eq!(TranscriptFormat::from_name("md"), Some(TranscriptFormat::Markdown));
+    }
+}
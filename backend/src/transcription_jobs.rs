@@ -0,0 +1,566 @@
+// ===================================
+// 非同步轉錄任務 API
+// 長音頻上傳後立即返回任務 ID，客戶端輪詢狀態取得結果
+// ===================================
+
+use axum::{
+    extract::{Multipart, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Weak;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tracing::{info, error, warn};
+use uuid::Uuid;
+
+use metrics::{counter, gauge};
+
+use crate::bilingual::{self, AlignedSegment};
+use crate::error::{ErrorResponse, PipelineError};
+use crate::transcript_render::TranscriptFormat;
+use crate::upload_options::{self, UploadOptions};
+use crate::whisper_model_pool::{TaskStatus, TranscriptionResult};
+use crate::WhisperService;
+
+/// 已結束任務的預設保留時間，可由 jobs.retention_secs 配置覆蓋
+const FINISHED_JOB_RETENTION: Duration = Duration::from_secs(60 * 60);
+/// 背景清除過期任務的最長間隔
+const PURGE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 任務記錄
+struct JobRecord {
+    created_at: DateTime<Utc>,
+    completed_at: Option<DateTime<Utc>>,
+    audio_format: String,
+    audio_duration_seconds: f64,
+    /// 解碼後、重採樣前的原始採樣率
+    source_sample_rate: u32,
+    /// 建立任務時指定的回應格式，查詢時未指定格式則使用此格式
+    response_format: Option<TranscriptFormat>,
+    /// 同時輸出英文翻譯，只能以 JSON 查詢
+    dual_output: bool,
+    /// 提交到模型池的轉錄任務 (逐聲道與雙語輸出時有多個)
+    task_ids: Vec<Uuid>,
+    state: JobState,
+}
+
+/// 任務在 API 層的狀態
+enum JobState {
+    /// 仍在模型池中 (佇列中或處理中)
+    Pending,
+    /// 轉錄完成
+    Done(Box<JobOutput>),
+    /// 轉錄失敗
+    Failed(PipelineError),
+}
+
+/// 已完成任務的轉錄結果
+struct JobOutput {
+    result: TranscriptionResult,
+    translation: Option<JobTranslation>,
+}
+
+/// 雙語輸出任務的英文翻譯
+#[derive(Debug, Clone, Serialize)]
+pub struct JobTranslation {
+    #[serde(flatten)]
+    result: TranscriptionResult,
+    /// 依時間對齊的原文 / 譯文段落
+    aligned: Vec<AlignedSegment>,
+}
+
+/// 模型池中各轉錄任務的狀態 → 任務狀態；任一轉錄處理中即為 running
+///
+/// 背景任務尚未提交 (None) 或仍在佇列中的轉錄都視為 queued。
+fn pending_status(statuses: impl IntoIterator<Item = Option<TaskStatus>>) -> &'static str {
+    if statuses.into_iter().any(|status| status == Some(TaskStatus::Running)) {
+        "running"
+    } else {
+        "queued"
+    }
+}
+
+/// 非同步任務儲存區
+pub struct JobStore {
+    jobs: RwLock<HashMap<Uuid, JobRecord>>,
+    retention: Duration,
+}
+
+impl Default for JobStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JobStore {
+    pub fn new() -> Self {
+        Self::with_retention(FINISHED_JOB_RETENTION)
+    }
+
+    /// 指定已完成任務的保留時間
+    pub fn with_retention(retention: Duration) -> Self {
+        Self {
+            jobs: RwLock::new(HashMap::new()),
+            retention,
+        }
+    }
+
+    /// 背景定期清除過期任務，儲存區釋放後結束
+    pub fn spawn_purger(self: &Arc<Self>) {
+        let store: Weak<Self> = Arc::downgrade(self);
+        let period = self.retention.clamp(Duration::from_secs(1), PURGE_INTERVAL);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                let Some(store) = store.upgrade() else {
+                    break;
+                };
+                store.purge_expired();
+            }
+        });
+    }
+
+    fn insert(&self, job_id: Uuid, record: JobRecord) {
+        let mut jobs = self.jobs.write();
+        jobs.insert(job_id, record);
+        gauge!("transcription_jobs_tracked").set(jobs.len() as f64);
+    }
+
+    /// 記錄工作線程回傳的結果 (任務已被刪除時忽略)
+    fn finish(
+        &self,
+        job_id: Uuid,
+        outcome: Result<(TranscriptionResult, Option<TranscriptionResult>), PipelineError>,
+    ) {
+        let mut jobs = self.jobs.write();
+        let Some(job) = jobs.get_mut(&job_id) else {
+            return;
+        };
+
+        job.completed_at = Some(Utc::now());
+        match outcome {
+            Ok((result, translation)) => {
+                let translation = translation.map(|translation| JobTranslation {
+                    aligned: bilingual::align_segments(&result.segments, &translation.segments),
+                    result: translation,
+                });
+                job.state = JobState::Done(Box::new(JobOutput { result, translation }));
+                counter!("transcription_jobs_completed_total").increment(1);
+            },
+            Err(e) => {
+                warn!("⚠️ 轉錄任務 {} 失敗: {}", job_id, e);
+                counter!("transcription_jobs_failed_total", "code" => e.code()).increment(1);
+                job.state = JobState::Failed(e);
+            },
+        }
+    }
+
+    /// 刪除任務，返回其轉錄任務 ID 以便取消
+    fn remove(&self, job_id: Uuid) -> Option<Vec<Uuid>> {
+        let mut jobs = self.jobs.write();
+        let removed = jobs.remove(&job_id).map(|job| job.task_ids);
+        gauge!("transcription_jobs_tracked").set(jobs.len() as f64);
+        removed
+    }
+
+    /// 清除超過保留時間的已結束任務
+    fn purge_expired(&self) {
+        let retention = chrono::Duration::from_std(self.retention)
+            .unwrap_or_else(|_| chrono::Duration::hours(1));
+        let cutoff = Utc::now() - retention;
+
+        let mut jobs = self.jobs.write();
+        let before = jobs.len();
+        jobs.retain(|_, job| job.completed_at.is_none_or(|done| done > cutoff));
+
+        let purged = before - jobs.len();
+        if purged > 0 {
+            info!("🧹 清除 {} 個過期的轉錄任務", purged);
+            counter!("transcription_jobs_expired_total").increment(purged as u64);
+        }
+        gauge!("transcription_jobs_tracked").set(jobs.len() as f64);
+    }
+
+    /// 查詢任務狀態，或在完成後渲染為指定格式
+    ///
+    /// 格式依序取查詢參數、建立任務時的 format 欄位、Accept 標頭；
+    /// `task_status` 查詢模型池中的轉錄狀態。
+    fn view(
+        &self,
+        job_id: Uuid,
+        requested: Option<TranscriptFormat>,
+        headers: &HeaderMap,
+        task_status: impl Fn(Uuid) -> Option<TaskStatus>,
+    ) -> Result<JobView, PipelineError> {
+        let jobs = self.jobs.read();
+        let job = jobs.get(&job_id).ok_or_else(|| job_not_found(job_id))?;
+        let format = crate::negotiate_format(requested.or(job.response_format), headers);
+        upload_options::check_dual_output(job.dual_output, format).map_err(PipelineError::InvalidRequest)?;
+
+        if format != TranscriptFormat::Json {
+            return match &job.state {
+                JobState::Done(output) => {
+                    Ok(JobView::Rendered(format, format.render(&output.result.segments).unwrap_or_default()))
+                },
+                JobState::Pending => Err(PipelineError::JobNotReady(job_id.to_string())),
+                JobState::Failed(e) => Err(PipelineError::JobFailed(e.to_string())),
+            };
+        }
+
+        let (status, result, translation, error) = match &job.state {
+            JobState::Pending => {
+                let status = pending_status(job.task_ids.iter().map(|&task_id| task_status(task_id)));
+                (status, None, None, None)
+            },
+            JobState::Done(output) => ("done", Some(output.result.clone()), output.translation.clone(), None),
+            JobState::Failed(e) => ("failed", None, None, Some(ErrorResponse::from(e))),
+        };
+
+        Ok(JobView::Status(Box::new(JobStatusResponse {
+            job_id,
+            status,
+            created_at: job.created_at.to_rfc3339(),
+            completed_at: job.completed_at.map(|t| t.to_rfc3339()),
+            audio_format: job.audio_format.clone(),
+            audio_duration_seconds: job.audio_duration_seconds,
+            source_sample_rate: job.source_sample_rate,
+            result,
+            translation,
+            error,
+        })))
+    }
+}
+
+/// 任務查詢結果
+enum JobView {
+    /// JSON 任務狀態
+    Status(Box<JobStatusResponse>),
+    /// 已完成任務渲染後的字幕/文件
+    Rendered(TranscriptFormat, String),
+}
+
+/// 任務建立回應
+#[derive(Serialize)]
+pub struct JobCreatedResponse {
+    job_id: Uuid,
+    status: &'static str,
+    status_url: String,
+}
+
+/// 任務狀態回應
+#[derive(Serialize)]
+pub struct JobStatusResponse {
+    job_id: Uuid,
+    status: &'static str,
+    created_at: String,
+    completed_at: Option<String>,
+    audio_format: String,
+    audio_duration_seconds: f64,
+    source_sample_rate: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<TranscriptionResult>,
+    /// 雙語輸出任務的英文翻譯
+    #[serde(skip_serializing_if = "Option::is_none")]
+    translation: Option<JobTranslation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<ErrorResponse>,
+}
+
+/// 任務查詢參數
+#[derive(Deserialize)]
+pub struct JobQuery {
+    /// 輸出格式 (json / text / srt / vtt / tsv / markdown)
+    format: Option<String>,
+}
+
+/// POST /jobs - 上傳音頻並建立非同步轉錄任務
+///
+/// 接受與 /upload 相同的選項欄位；未知的欄位返回 400。
+pub async fn create_job(
+    State(whisper_service): State<Arc<WhisperService>>,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<JobCreatedResponse>), PipelineError> {
+    info!("📥 收到非同步轉錄任務請求");
+    whisper_service.job_store.purge_expired();
+
+    let mut audio_data = None;
+    let mut options = UploadOptions::default();
+
+    // 選項欄位可能出現在音頻欄位之後
+    while let Some(field) = multipart.next_field().await.map_err(|e| {
+        error!("Error reading multipart field: {}", e);
+        PipelineError::from_multipart(e)
+    })? {
+        let field_name = field.name().unwrap_or("").to_string();
+
+        if field_name == "audio" || field_name == "audio_packets" {
+            let data = field.bytes().await.map_err(|e| {
+                error!("Error reading field data: {}", e);
+                PipelineError::from_multipart(e)
+            })?;
+            audio_data = Some(data);
+        } else if UploadOptions::is_option_field(&field_name) {
+            let value = field.text().await.map_err(|e| {
+                error!("Error reading field {}: {}", field_name, e);
+                PipelineError::from_multipart(e)
+            })?;
+            options.apply_field(&field_name, &value).map_err(|e| {
+                warn!("⚠️ 無效的任務參數 {}: {}", field_name, e);
+                PipelineError::InvalidRequest(e)
+            })?;
+        } else {
+            warn!("⚠️ 未知的任務參數: {}", field_name);
+            return Err(PipelineError::InvalidRequest(format!("未知的參數: {}", field_name)));
+        }
+    }
+
+    let Some(data) = audio_data else {
+        error!("未找到音頻數據");
+        return Err(PipelineError::MissingAudio);
+    };
+    whisper_service.apply_vocabulary(&mut options)
+        .inspect_err(|e| warn!("⚠️ 無效的任務參數: {}", e))?;
+    // 建立時指定的格式會作為查詢預設值，雙語輸出須為 JSON
+    if let Some(format) = options.response_format {
+        upload_options::check_dual_output(options.dual_output, format).map_err(|e| {
+            warn!("⚠️ 無效的任務參數: {}", e);
+            PipelineError::InvalidRequest(e)
+        })?;
+    }
+
+    let (audio, source) = crate::decode_uploaded_audio(&whisper_service, &data, options.channel_mode)?;
+    let audio_duration_seconds = audio.duration_seconds();
+
+    // 佇列已滿或關閉中時直接拒絕，而非建立注定失敗的任務
+    whisper_service.model_pool
+        .ensure_capacity()
+        .inspect_err(|e| error!("提交轉錄任務失敗: {}", e))?;
+    let job_id = Uuid::new_v4();
+
+    // 每個聲道與翻譯各一個轉錄任務，第一個沿用任務 ID
+    let passes = crate::transcription_passes(audio.channels.len(), options.dual_output);
+    let task_ids: Vec<Uuid> = std::iter::once(job_id)
+        .chain(std::iter::repeat_with(Uuid::new_v4))
+        .take(passes)
+        .collect();
+
+    whisper_service.job_store.insert(job_id, JobRecord {
+        created_at: Utc::now(),
+        completed_at: None,
+        audio_format: source.label().to_string(),
+        audio_duration_seconds,
+        source_sample_rate: audio.source_sample_rate,
+        response_format: options.response_format,
+        dual_output: options.dual_output,
+        task_ids: task_ids.clone(),
+        state: JobState::Pending,
+    });
+
+    // 背景轉錄 (長音頻分段並行)，結果寫回任務儲存區；非同步任務不設等待上限
+    let service = whisper_service.clone();
+    tokio::spawn(async move {
+        let outcome = service
+            .transcribe_request(
+                audio.samples,
+                audio.channels,
+                options.transcription,
+                options.dual_output,
+                &task_ids,
+                None,
+            )
+            .await;
+        service.job_store.finish(job_id, outcome);
+    });
+
+    counter!("transcription_jobs_created_total").increment(1);
+    info!("📝 非同步轉錄任務已建立: {} ({:.1} 秒音頻)", job_id, audio_duration_seconds);
+
+    Ok((StatusCode::ACCEPTED, Json(JobCreatedResponse {
+        job_id,
+        status: "queued",
+        status_url: format!("/jobs/{}", job_id),
+    })))
+}
+
+/// GET /jobs/:id - 查詢任務狀態與結果
+///
+/// 指定非 JSON 格式 (format 參數或 Accept 標頭) 時直接返回字幕/文件，任務尚未完成則返回 409。
+pub async fn get_job(
+    State(whisper_service): State<Arc<WhisperService>>,
+    Path(job_id): Path<Uuid>,
+    Query(query): Query<JobQuery>,
+    headers: HeaderMap,
+) -> Result<Response, PipelineError> {
+    let requested = match query.format.as_deref() {
+        Some(name) => Some(TranscriptFormat::from_name(name).ok_or_else(|| {
+            PipelineError::InvalidRequest(format!("不支援的回應格式: {} (可用: {})", name, TranscriptFormat::NAMES))
+        })?),
+        None => None,
+    };
+    let job_store = &whisper_service.job_store;
+    job_store.purge_expired();
+
+    let view = job_store.view(job_id, requested, &headers, |task_id| {
+        whisper_service.model_pool.get_task_status(task_id)
+    })?;
+    Ok(match view {
+        JobView::Status(status) => Json(status).into_response(),
+        JobView::Rendered(format, body) => ([(header::CONTENT_TYPE, format.content_type())], body).into_response(),
+    })
+}
+
+/// DELETE /jobs/:id - 取消任務或刪除已完成的結果
+pub async fn delete_job(
+    State(whisper_service): State<Arc<WhisperService>>,
+    Path(job_id): Path<Uuid>,
+) -> Result<StatusCode, PipelineError> {
+    let task_ids = whisper_service.job_store.remove(job_id).ok_or_else(|| job_not_found(job_id))?;
+
+    for task_id in task_ids {
+        whisper_service.model_pool.cancel_task(task_id);
+    }
+    counter!("transcription_jobs_deleted_total").increment(1);
+    info!("🗑️  轉錄任務已刪除: {}", job_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn job_not_found(job_id: Uuid) -> PipelineError {
+    PipelineError::NotFound(format!("找不到轉錄任務: {}", job_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::whisper_model_pool::TranscriptSegment;
+
+    fn pending_job(task_ids: Vec<Uuid>) -> JobRecord {
+        JobRecord {
+            created_at: Utc::now(),
+            completed_at: None,
+            audio_format: "WAV".to_string(),
+            audio_duration_seconds: 2.0,
+            source_sample_rate: 16000,
+            response_format: None,
+            dual_output: false,
+            task_ids,
+            state: JobState::Pending,
+        }
+    }
+
+    fn result(text: &str) -> TranscriptionResult {
+        TranscriptionResult::fixture(vec![TranscriptSegment::fixture(0.0, 2.0, text)])
+    }
+
+
+    fn status_of(store: &JobStore, job_id: Uuid, task_status: impl Fn(Uuid) -> Option<TaskStatus>) -> &'static str {
+        match store.view(job_id, None, &HeaderMap::new(), task_status) {
+            Ok(JobView::Status(response)) => response.status,
+            _ => panic!("預期 JSON 任務狀態"),
+        }
+    }
+
+    #[test]
+    fn test_pending_status_before_submit_is_queued() {
+        let store = JobStore::new();
+        let job_id = Uuid::new_v4();
+        let channel_task = Uuid::new_v4();
+        store.insert(job_id, pending_job(vec![job_id, channel_task]));
+
+        // 背景任務尚未提交到模型池
+        assert_eq!(status_of(&store, job_id, |_| None), "queued");
+        assert_eq!(status_of(&store, job_id, |_| Some(TaskStatus::Queued)), "queued");
+        let running = |task_id| (task_id == channel_task).then_some(TaskStatus::Running);
+        assert_eq!(status_of(&store, job_id, running), "running");
+    }
+
+    #[test]
+    fn test_rendered_format_conflicts_until_done() {
+        let store = JobStore::new();
+        let job_id = Uuid::new_v4();
+        store.insert(job_id, pending_job(vec![job_id]));
+
+        let err = store.view(job_id, Some(TranscriptFormat::Srt), &HeaderMap::new(), |_| None).err().unwrap();
+        assert_eq!(err.status(), StatusCode::CONFLICT);
+
+        store.finish(job_id, Ok((result("個案今天精神很好"), None)));
+        match store.view(job_id, Some(TranscriptFormat::Text), &HeaderMap::new(), |_| None) {
+            Ok(JobView::Rendered(TranscriptFormat::Text, body)) => assert!(body.contains("個案今天精神很好")),
+            _ => panic!("預期渲染後的文字"),
+        }
+        assert_eq!(status_of(&store, job_id, |_| None), "done");
+    }
+
+    #[test]
+    fn test_dual_output_job_only_renders_json() {
+        let store = JobStore::new();
+        let job_id = Uuid::new_v4();
+        store.insert(job_id, JobRecord { dual_output: true, ..pending_job(vec![job_id]) });
+        store.finish(job_id, Ok((result("個案今天精神很好"), Some(result("The client is in good spirits")))));
+
+        let err = store.view(job_id, Some(TranscriptFormat::Srt), &HeaderMap::new(), |_| None).err().unwrap();
+        assert_eq!(err.status(), StatusCode::BAD_REQUEST);
+        match store.view(job_id, None, &HeaderMap::new(), |_| None) {
+            Ok(JobView::Status(response)) => assert!(response.translation.is_some()),
+            _ => panic!("預期 JSON 任務狀態"),
+        }
+    }
+
+    #[test]
+    fn test_deleted_job_is_not_found() {
+        let store = JobStore::new();
+        let job_id = Uuid::new_v4();
+        let translation_task = Uuid::new_v4();
+        store.insert(job_id, pending_job(vec![job_id, translation_task]));
+
+        assert_eq!(store.remove(job_id), Some(vec![job_id, translation_task]));
+        assert_eq!(store.remove(job_id), None);
+        let err = store.view(job_id, None, &HeaderMap::new(), |_| None).err().unwrap();
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+
+        // 刪除後才回傳的結果直接丟棄
+        store.finish(job_id, Ok((result("遲到的結果"), None)));
+        assert!(store.jobs.read().is_empty());
+    }
+
+    #[test]
+    fn test_purge_expired_keeps_pending_jobs() {
+        let store = JobStore::with_retention(Duration::ZERO);
+        let (done, pending) = (Uuid::new_v4(), Uuid::new_v4());
+        store.insert(done, pending_job(vec![done]));
+        store.insert(pending, pending_job(vec![pending]));
+        store.finish(done, Err(PipelineError::Cancelled));
+
+        store.purge_expired();
+        let err = store.view(done, None, &HeaderMap::new(), |_| None).err().unwrap();
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+        assert_eq!(status_of(&store, pending, |_| None), "queued");
+    }
+
+    #[tokio::test]
+    async fn test_purger_removes_results_without_new_requests() {
+        let store = Arc::new(JobStore::with_retention(Duration::from_secs(60)));
+        let job_id = Uuid::new_v4();
+        store.insert(job_id, pending_job(vec![job_id]));
+        store.finish(job_id, Ok((result("完成"), None)));
+        store.jobs.write().get_mut(&job_id).unwrap().completed_at = Some(Utc::now() - chrono::Duration::seconds(61));
+
+        // 第一次 tick 立即觸發，不需要新的請求
+        store.spawn_purger();
+        for _ in 0..50 {
+            if store.jobs.read().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(store.jobs.read().is_empty());
+    }
+}
@@ -0,0 +1,240 @@
+// ===================================
+// 上傳請求選項解析
+// multipart 文字欄位 → 轉錄選項與回應格式
+// ===================================
+
+use crate::audio_decoder::ChannelMode;
+use crate::language_detect;
+use crate::transcript_render::TranscriptFormat;
+use crate::vocabulary;
+use crate::whisper_model_pool::{TranscriptionOptions, TranscriptionQuality};
+
+/// 上傳請求的可選參數
+#[derive(Debug, Clone, Default)]
+pub struct UploadOptions {
+    pub transcription: TranscriptionOptions,
+    /// 明確指定的回應格式，None 時依 Accept 標頭協商
+    pub response_format: Option<TranscriptFormat>,
+    /// 覆蓋配置的聲道處理方式 (separate = 逐聲道轉錄並標記說話者)
+    pub channel_mode: Option<ChannelMode>,
+    /// 同時回傳原文轉錄與英文翻譯 (僅 JSON 回應)
+    pub dual_output: bool,
+    /// 選用的機構詞彙表
+    pub organization: Option<String>,
+}
+
+impl UploadOptions {
+    /// 支援的選項欄位名稱
+    pub const FIELDS: [&'static str; 12] = [
+        "language",
+        "language_candidates",
+        "quality",
+        "initial_prompt",
+        "vocabulary",
+        "organization",
+        "translate",
+        "temperature",
+        "response_format",
+        "format",
+        "channel_mode",
+        "dual_output",
+    ];
+
+    /// 判斷 multipart 欄位是否為選項欄位
+    pub fn is_option_field(name: &str) -> bool {
+        Self::FIELDS.contains(&name)
+    }
+
+    /// 套用單一選項欄位，驗證失敗時返回錯誤訊息
+    pub fn apply_field(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let value = value.trim();
+
+        // 空欄位視為未提供 (表單常送出空值)
+        if value.is_empty() {
+            return Ok(());
+        }
+
+        match name {
+            "language" => {
+                self.transcription.language = parse_language(value)?;
+            },
+            "language_candidates" => {
+                self.transcription.language_candidates = language_detect::parse_candidates(value)?;
+            },
+            "quality" => {
+                self.transcription.quality = TranscriptionQuality::from_name(value).ok_or_else(|| {
+                    format!(
+                        "不支援的品質等級: {} (可用: turbo, balanced, medium, high_accuracy, premium)",
+                        value
+                    )
+                })?;
+            },
+            "initial_prompt" => {
+                if value.contains('\0') {
+                    return Err("initial_prompt 不可包含空字元".to_string());
+                }
+                if value.chars().count() > vocabulary::MAX_PROMPT_CHARS {
+                    return Err(format!("initial_prompt 過長 (最多 {} 字)", vocabulary::MAX_PROMPT_CHARS));
+                }
+                self.transcription.initial_prompt = Some(value.to_string());
+            },
+            "vocabulary" => {
+                self.transcription.vocabulary = vocabulary::parse_terms(value)?;
+            },
+            "organization" => {
+                self.organization = Some(value.to_string());
+            },
+            "translate" => {
+                self.transcription.translate = parse_bool(value)
+                    .ok_or_else(|| format!("translate 必須為 true 或 false: {}", value))?;
+            },
+            "temperature" => {
+                let temperature: f32 = value
+                    .parse()
+                    .map_err(|_| format!("temperature 必須為數字: {}", value))?;
+                if !(0.0..=1.0).contains(&temperature) {
+                    return Err(format!("temperature 必須介於 0.0 與 1.0 之間: {}", value));
+                }
+                self.transcription.temperature = Some(temperature);
+            },
+            "response_format" | "format" => {
+                self.response_format = Some(TranscriptFormat::from_name(value).ok_or_else(|| {
+                    format!("不支援的回應格式: {} (可用: {})", value, TranscriptFormat::NAMES)
+                })?);
+            },
+            "channel_mode" => {
+                self.channel_mode = Some(parse_channel_mode(value)?);
+            },
+            "dual_output" => {
+                self.dual_output = parse_bool(value)
+                    .ok_or_else(|| format!("dual_output 必須為 true 或 false: {}", value))?;
+            },
+            _ => return Err(format!("未知的參數: {}", name)),
+        }
+
+        Ok(())
+    }
+}
+
+/// 檢查回應格式能否承載雙語輸出：翻譯只包含在 JSON 回應中，其他格式會遺失翻譯
+pub fn check_dual_output(dual_output: bool, format: TranscriptFormat) -> Result<(), String> {
+    if dual_output && format != TranscriptFormat::Json {
+        return Err(format!("dual_output 僅支援 JSON 回應，無法以 {} 格式輸出翻譯", format.name()));
+    }
+    Ok(())
+}
+
+/// 解析語言代碼，"auto" 表示自動偵測
+fn parse_language(value: &str) -> Result<Option<String>, String> {
+    let language = value.to_ascii_lowercase();
+    if language == "auto" {
+        return Ok(None);
+    }
+
+    // Whisper 語言代碼為 2-3 個英文字母 (例如 zh, en, yue)
+    let well_formed = (2..=3).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_alphabetic());
+    if !well_formed || whisper_rs::get_lang_id(&language).is_none() {
+        return Err(format!("不支援的語言代碼: {}", value));
+    }
+
+    Ok(Some(language))
+}
+
+/// 解析聲道模式：downmix / separate / left / right / 聲道編號
+fn parse_channel_mode(value: &str) -> Result<ChannelMode, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "downmix" | "mono" => Ok(ChannelMode::Downmix),
+        "separate" | "split" => Ok(ChannelMode::Separate),
+        "left" => Ok(ChannelMode::Select(0)),
+        "right" => Ok(ChannelMode::Select(1)),
+        other => match other.parse::<usize>() {
+            Ok(index) if index < 8 => Ok(ChannelMode::Select(index)),
+            _ => Err(format!(
+                "不支援的聲道模式: {} (可用: downmix, separate, left, right, 0-7)",
+                value
+            )),
+        },
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" => Some(true),
+        "false" | "0" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_match_upload_behaviour() {
+        let options = UploadOptions::default();
+        assert_eq!(options.transcription.quality, TranscriptionQuality::Medium);
+        assert_eq!(options.transcription.language.as_deref(), Some("zh"));
+        assert_eq!(options.response_format, None);
+    }
+
+    #[test]
+    fn test_apply_valid_fields() {
+        let mut options = UploadOptions::default();
+        options.apply_field("quality", "Premium").unwrap();
+        options.apply_field("language", "auto").unwrap();
+        options.apply_field("translate", "true").unwrap();
+        options.apply_field("temperature", "0.2").unwrap();
+        options.apply_field("initial_prompt", "長照, 護理師").unwrap();
+        options.apply_field("format", "srt").unwrap();
+        options.apply_field("channel_mode", "separate").unwrap();
+        options.apply_field("language_candidates", "zh, EN,ja").unwrap();
+        options.apply_field("dual_output", "yes").unwrap();
+        options.apply_field("vocabulary", "居服員、失智").unwrap();
+        options.apply_field("organization", "sunrise").unwrap();
+
+        assert_eq!(options.transcription.quality, TranscriptionQuality::Premium);
+        assert_eq!(options.transcription.language, None);
+        assert!(options.transcription.translate);
+        assert_eq!(options.transcription.temperature, Some(0.2));
+        assert_eq!(options.transcription.initial_prompt.as_deref(), Some("長照, 護理師"));
+        assert_eq!(options.response_format, Some(TranscriptFormat::Srt));
+        assert_eq!(options.channel_mode, Some(ChannelMode::Separate));
+        assert_eq!(options.transcription.language_candidates, vec!["zh", "en", "ja"]);
+        assert!(options.dual_output);
+        assert_eq!(options.transcription.vocabulary, vec!["居服員", "失智"]);
+        assert_eq!(options.organization.as_deref(), Some("sunrise"));
+
+        options.apply_field("channel_mode", "right").unwrap();
+        assert_eq!(options.channel_mode, Some(ChannelMode::Select(1)));
+    }
+
+    #[test]
+    fn test_reject_invalid_fields() {
+        let mut options = UploadOptions::default();
+        assert!(options.apply_field("quality", "ultra").is_err());
+        assert!(options.apply_field("language", "chinese").is_err());
+        assert!(options.apply_field("translate", "maybe").is_err());
+        assert!(options.apply_field("temperature", "1.5").is_err());
+        assert!(options.apply_field("temperature", "NaN").is_err());
+        assert!(options.apply_field("response_format", "xml").is_err());
+        assert!(options.apply_field("channel_mode", "surround").is_err());
+        assert!(options.apply_field("channel_mode", "8").is_err());
+        assert!(options.apply_field("language_candidates", "zh,xx").is_err());
+        assert!(options.apply_field("initial_prompt", &"長".repeat(vocabulary::MAX_PROMPT_CHARS + 1)).is_err());
+    }
+
+    #[test]
+    fn test_dual_output_requires_json() {
+        assert!(check_dual_output(true, TranscriptFormat::Json).is_ok());
+        assert!(check_dual_output(false, TranscriptFormat::Srt).is_ok());
+        assert!(check_dual_output(true, TranscriptFormat::Srt).unwrap_err().contains("srt"));
+    }
+
+    #[test]
+    fn test_empty_field_keeps_default() {
+        let mut options = UploadOptions::default();
+        options.apply_field("language", "  ").unwrap();
+        assert_eq!(options.transcription.language.as_deref(), Some("zh"));
+    }
+}
@@ -0,0 +1,305 @@
+// ===================================
+// 語音活動偵測 (VAD)
+// 以短時能量 + 過零率判斷語音幀並合併為語音區段，
+// 只將語音區段串接送入 Whisper，再把轉錄時間戳映射回原始時間軸
+// ===================================
+
+use metrics::{counter, histogram};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+/// 能量高出門檻此值 (dB) 的幀不論過零率皆視為語音
+const LOUD_FRAME_MARGIN_DB: f32 = 10.0;
+/// 估計背景噪音時使用的能量百分位
+const NOISE_FLOOR_PERCENTILE: f32 = 0.1;
+/// 串接語音區段時插入的靜音長度 (毫秒)，讓 Whisper 在區段間自然斷句
+const REGION_SEPARATOR_MS: u32 = 300;
+
+/// VAD 配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VadConfig {
+    /// 是否在轉錄前過濾靜音與背景噪音
+    pub enabled: bool,
+    /// 分析幀長度 (毫秒)
+    pub frame_ms: u32,
+    /// 語音幀的最低能量 (dBFS)
+    pub energy_threshold_db: f32,
+    /// 語音幀需高出估計背景噪音的能量 (dB)
+    pub noise_margin_db: f32,
+    /// 語音幀的過零率上限 (每樣本)；能量不突出且過零率過高的幀視為嘶聲/風聲
+    pub max_zero_crossing_rate: f32,
+    /// 短於此長度的語音區段視為雜音捨棄 (毫秒)
+    pub min_speech_ms: u32,
+    /// 語音區段間短於此長度的停頓會被合併 (毫秒)
+    pub min_silence_ms: u32,
+    /// 每個語音區段前後保留的邊界 (毫秒)
+    pub padding_ms: u32,
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            frame_ms: 30,
+            energy_threshold_db: -45.0,
+            noise_margin_db: 10.0,
+            max_zero_crossing_rate: 0.35,
+            min_speech_ms: 250,
+            min_silence_ms: 500,
+            padding_ms: 200,
+        }
+    }
+}
+
+/// 原始音頻中的語音區段 (樣本索引，左閉右開)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpeechRegion {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl SpeechRegion {
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+}
+
+/// 單幀特徵
+struct FrameFeatures {
+    energy_db: f32,
+    zero_crossing_rate: f32,
+}
+
+fn frame_features(frame: &[f32]) -> FrameFeatures {
+    let mean_square = frame.iter().map(|s| s * s).sum::<f32>() / frame.len().max(1) as f32;
+    let crossings = frame
+        .windows(2)
+        .filter(|pair| (pair[0] >= 0.0) != (pair[1] >= 0.0))
+        .count();
+    FrameFeatures {
+        energy_db: 10.0 * mean_square.max(1e-12).log10(),
+        zero_crossing_rate: crossings as f32 / frame.len().saturating_sub(1).max(1) as f32,
+    }
+}
+
+/// 各幀能量的最大值 (dBFS)，音頻短於一幀時返回 None
+pub fn peak_energy_db(samples: &[f32], sample_rate: u32, frame_ms: u32) -> Option<f32> {
+    let frame = ms_to_samples(frame_ms, sample_rate).max(1);
+    if samples.len() < frame {
+        return None;
+    }
+    samples
+        .chunks(frame)
+        .map(|chunk| frame_features(chunk).energy_db)
+        .max_by(f32::total_cmp)
+}
+
+fn ms_to_samples(ms: u32, sample_rate: u32) -> usize {
+    (ms as u64 * sample_rate as u64 / 1000) as usize
+}
+
+/// 偵測語音區段 (已合併短停頓、捨棄過短區段並加上前後邊界)
+pub fn detect_speech(samples: &[f32], sample_rate: u32, config: &VadConfig) -> Vec<SpeechRegion> {
+    let frame_len = ms_to_samples(config.frame_ms, sample_rate).max(1);
+    let features: Vec<FrameFeatures> = samples.chunks(frame_len).map(frame_features).collect();
+    if features.is_empty() {
+        return Vec::new();
+    }
+
+    // 依背景噪音自適應提高門檻 (錄音環境噪音較大時)
+    let mut energies: Vec<f32> = features.iter().map(|f| f.energy_db).collect();
+    energies.sort_by(f32::total_cmp);
+    let noise_floor = energies[((energies.len() - 1) as f32 * NOISE_FLOOR_PERCENTILE) as usize];
+    let threshold = config.energy_threshold_db.max(noise_floor + config.noise_margin_db);
+    debug!("VAD 背景噪音 {:.1} dB, 語音門檻 {:.1} dB", noise_floor, threshold);
+
+    let is_speech = |f: &FrameFeatures| {
+        f.energy_db >= threshold
+            && (f.zero_crossing_rate <= config.max_zero_crossing_rate
+                || f.energy_db >= threshold + LOUD_FRAME_MARGIN_DB)
+    };
+
+    // 連續語音幀 → 區段，短停頓合併
+    let min_silence = ms_to_samples(config.min_silence_ms, sample_rate);
+    let mut regions: Vec<SpeechRegion> = Vec::new();
+    for (index, frame) in features.iter().enumerate() {
+        if !is_speech(frame) {
+            continue;
+        }
+        let start = index * frame_len;
+        let end = (start + frame_len).min(samples.len());
+        match regions.last_mut() {
+            Some(last) if start - last.end < min_silence => last.end = end,
+            _ => regions.push(SpeechRegion { start, end }),
+        }
+    }
+
+    // 捨棄過短區段後加上邊界，邊界重疊的區段再次合併
+    let min_speech = ms_to_samples(config.min_speech_ms, sample_rate);
+    let padding = ms_to_samples(config.padding_ms, sample_rate);
+    let mut padded: Vec<SpeechRegion> = Vec::with_capacity(regions.len());
+    for region in regions.into_iter().filter(|region| region.len() >= min_speech) {
+        let start = region.start.saturating_sub(padding);
+        let end = (region.end + padding).min(samples.len());
+        match padded.last_mut() {
+            Some(last) if start <= last.end => last.end = end,
+            _ => padded.push(SpeechRegion { start, end }),
+        }
+    }
+    padded
+}
+
+/// 串接後的語音音頻與原始時間軸的對照
+#[derive(Debug, Clone)]
+pub struct SpeechTimeline {
+    pub regions: Vec<SpeechRegion>,
+    /// 串接後送入 Whisper 的樣本 (區段間插入短靜音)
+    pub audio: Vec<f32>,
+    /// 每個區段在串接音頻中的起點
+    offsets: Vec<usize>,
+    sample_rate: u32,
+    original_len: usize,
+}
+
+impl SpeechTimeline {
+    /// 偵測語音並串接語音區段
+    pub fn detect(samples: &[f32], sample_rate: u32, config: &VadConfig) -> Self {
+        let vad_start = std::time::Instant::now();
+        let regions = detect_speech(samples, sample_rate, config);
+        let timeline = Self::from_regions(samples, sample_rate, regions);
+
+        histogram!("vad_time_ms").record(vad_start.elapsed().as_millis() as f64);
+        histogram!("vad_speech_ratio").record(timeline.speech_ratio());
+        counter!("vad_runs_total").increment(1);
+        if timeline.regions.is_empty() {
+            counter!("vad_no_speech_total").increment(1);
+        }
+
+        info!(
+            "🗣️ VAD: {} 個語音區段, 語音 {:.1} / {:.1} 秒 ({:.0}%)",
+            timeline.regions.len(),
+            timeline.speech_samples() as f64 / sample_rate as f64,
+            samples.len() as f64 / sample_rate as f64,
+            timeline.speech_ratio() * 100.0
+        );
+        timeline
+    }
+
+    pub fn from_regions(samples: &[f32], sample_rate: u32, regions: Vec<SpeechRegion>) -> Self {
+        let separator = ms_to_samples(REGION_SEPARATOR_MS, sample_rate);
+        let mut audio = Vec::with_capacity(regions.iter().map(|r| r.len() + separator).sum());
+        let mut offsets = Vec::with_capacity(regions.len());
+        for (index, region) in regions.iter().enumerate() {
+            if index > 0 {
+                audio.resize(audio.len() + separator, 0.0);
+            }
+            offsets.push(audio.len());
+            audio.extend_from_slice(&samples[region.start..region.end]);
+        }
+        Self {
+            regions,
+            audio,
+            offsets,
+            sample_rate,
+            original_len: samples.len(),
+        }
+    }
+
+    /// 語音區段的總樣本數
+    pub fn speech_samples(&self) -> usize {
+        self.regions.iter().map(SpeechRegion::len).sum()
+    }
+
+    /// 語音佔原始音頻的比例
+    pub fn speech_ratio(&self) -> f64 {
+        self.speech_samples() as f64 / self.original_len.max(1) as f64
+    }
+
+    /// 將串接音頻中的時間 (秒) 映射回原始時間軸；落在區段間靜音的時間歸到前一區段結尾
+    pub fn to_original(&self, seconds: f32) -> f32 {
+        let position = (seconds.max(0.0) as f64 * self.sample_rate as f64).round() as usize;
+        let index = self.offsets.partition_point(|&offset| offset <= position).saturating_sub(1);
+        let Some(region) = self.regions.get(index) else {
+            return seconds;
+        };
+        let within = (position - self.offsets[index].min(position)).min(region.len());
+        (region.start + within) as f32 / self.sample_rate as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+
+    fn tone(seconds: f32, amplitude: f32) -> Vec<f32> {
+        (0..(RATE as f32 * seconds) as usize)
+            .map(|i| (2.0 * std::f32::consts::PI * 220.0 * i as f32 / RATE as f32).sin() * amplitude)
+            .collect()
+    }
+
+    fn silence(seconds: f32) -> Vec<f32> {
+        vec![0.0; (RATE as f32 * seconds) as usize]
+    }
+
+    #[test]
+    fn test_silence_has_no_speech() {
+        let timeline = SpeechTimeline::detect(&silence(3.0), RATE, &VadConfig::default());
+        assert!(timeline.regions.is_empty());
+        assert!(timeline.audio.is_empty());
+    }
+
+    #[test]
+    fn test_speech_bursts_are_detected_with_padding() {
+        // 1s 靜音, 1s 語音, 2s 靜音, 0.6s 語音, 1s 靜音, 0.1s 點擊聲
+        let samples = [silence(1.0), tone(1.0, 0.3), silence(2.0), tone(0.6, 0.3), silence(1.0), tone(0.1, 0.3)].concat();
+        let regions = detect_speech(&samples, RATE, &VadConfig::default());
+
+        assert_eq!(regions.len(), 2, "{:?}", regions);
+        // 語音起點 1.0s 減去 0.2s 邊界 (以 30ms 幀對齊)
+        assert!((regions[0].start as f32 / RATE as f32 - 0.8).abs() < 0.04);
+        assert!((regions[0].end as f32 / RATE as f32 - 2.2).abs() < 0.04);
+        assert!((regions[1].start as f32 / RATE as f32 - 3.8).abs() < 0.04);
+    }
+
+    #[test]
+    fn test_hiss_below_loud_margin_is_rejected() {
+        // 能量與語音相近但過零率極高 (每個樣本正負交替) 的噪音不視為語音
+        let hiss: Vec<f32> = (0..RATE as usize).map(|i| if i % 2 == 0 { 0.01 } else { -0.01 }).collect();
+        let samples = [silence(1.0), hiss, silence(1.0), tone(1.0, 0.02), silence(1.0)].concat();
+        let regions = detect_speech(&samples, RATE, &VadConfig::default());
+
+        assert_eq!(regions.len(), 1, "{:?}", regions);
+        assert!(regions[0].start as f32 / RATE as f32 > 2.5);
+    }
+
+    #[test]
+    fn test_peak_energy_finds_loudest_frame() {
+        let mut samples = silence(1.0);
+        samples.extend(tone(0.1, 0.5));
+        let peak = peak_energy_db(&samples, RATE, 30).unwrap();
+        assert!(peak > -10.0, "{}", peak);
+        assert!(peak_energy_db(&silence(1.0), RATE, 30).unwrap() < -90.0);
+        assert_eq!(peak_energy_db(&samples[..100], RATE, 30), None);
+    }
+
+    #[test]
+    fn test_timestamps_map_back_to_original_timeline() {
+        let samples = silence(10.0);
+        let regions = vec![
+            SpeechRegion { start: RATE as usize, end: 3 * RATE as usize },
+            SpeechRegion { start: 7 * RATE as usize, end: 8 * RATE as usize },
+        ];
+        let timeline = SpeechTimeline::from_regions(&samples, RATE, regions);
+
+        // 2s + 0.3s 分隔 + 1s
+        assert_eq!(timeline.audio.len(), (3.3 * RATE as f32) as usize);
+        assert!((timeline.to_original(0.5) - 1.5).abs() < 1e-3);
+        // 分隔靜音內的時間歸到第一區段結尾
+        assert!((timeline.to_original(2.1) - 3.0).abs() < 1e-3);
+        assert!((timeline.to_original(2.8) - 7.5).abs() < 1e-3);
+        assert!((timeline.speech_ratio() - 0.3).abs() < 1e-6);
+    }
+}
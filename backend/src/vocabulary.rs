@@ -0,0 +1,222 @@
+// ===================================
+// 專有詞彙與初始提示詞
+// 請求與機構的詞彙表 (長照、居服員、藥名、個案姓名等) 組合成 Whisper 初始提示詞，
+// 並依模型的提示詞 token 預算裁切，避免 whisper.cpp 自行截掉開頭的詞彙
+// ===================================
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// 單一詞彙的最大字元數
+pub const MAX_TERM_CHARS: usize = 32;
+/// 單一請求可帶入的詞彙數
+pub const MAX_REQUEST_TERMS: usize = 50;
+/// 配置中每個詞彙表的詞彙數上限
+pub const MAX_CONFIG_TERMS: usize = 200;
+/// 請求 initial_prompt 的最大字元數 (實際送入模型的長度另受 token 預算限制)
+pub const MAX_PROMPT_CHARS: usize = 1000;
+
+/// 詞彙之間的分隔
+const TERM_SEPARATOR: &str = ", ";
+
+/// 詞彙表配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VocabularyConfig {
+    /// 所有請求共用的詞彙
+    pub terms: Vec<String>,
+    /// 各機構的詞彙，請求以 organization 欄位選用
+    pub organizations: HashMap<String, Vec<String>>,
+}
+
+impl VocabularyConfig {
+    /// 合併請求、機構與共用詞彙 (依此優先順序，重複者只保留一次)
+    ///
+    /// 指定的機構不存在時返回錯誤。
+    pub fn resolve(&self, organization: Option<&str>, request_terms: &[String]) -> Result<Vec<String>, String> {
+        let organization_terms = match organization {
+            Some(name) => self
+                .organizations
+                .get(name)
+                .ok_or_else(|| format!("未知的機構: {}", name))?
+                .as_slice(),
+            None => &[],
+        };
+
+        let mut terms: Vec<String> = Vec::new();
+        for term in request_terms.iter().chain(organization_terms).chain(&self.terms) {
+            if !terms.contains(term) {
+                terms.push(term.clone());
+            }
+        }
+        Ok(terms)
+    }
+
+    /// 驗證詞彙表內容
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = term_problems("vocabulary.terms", &self.terms);
+        for (name, terms) in &self.organizations {
+            if name.trim().is_empty() {
+                problems.push("vocabulary.organizations 不可包含空白的機構名稱".to_string());
+            }
+            problems.extend(term_problems(&format!("vocabulary.organizations.{}", name), terms));
+        }
+        problems
+    }
+}
+
+fn term_problems(name: &str, terms: &[String]) -> Vec<String> {
+    let mut problems = Vec::new();
+    if terms.len() > MAX_CONFIG_TERMS {
+        problems.push(format!("{} 最多 {} 個詞彙: {}", name, MAX_CONFIG_TERMS, terms.len()));
+    }
+    problems.extend(terms.iter().filter_map(|term| check_term(term).err()).map(|e| format!("{}: {}", name, e)));
+    problems
+}
+
+fn check_term(term: &str) -> Result<(), String> {
+    if term.trim().is_empty() {
+        return Err("詞彙不可為空".to_string());
+    }
+    if term.contains('\0') {
+        return Err("詞彙不可包含空字元".to_string());
+    }
+    if term.chars().count() > MAX_TERM_CHARS {
+        return Err(format!("詞彙過長 (最多 {} 字): {}", MAX_TERM_CHARS, term));
+    }
+    Ok(())
+}
+
+/// 解析請求的詞彙清單 (以逗號、頓號或換行分隔)
+pub fn parse_terms(value: &str) -> Result<Vec<String>, String> {
+    let mut terms: Vec<String> = Vec::new();
+    for term in value.split([',', '，', '、', '\n']).map(str::trim).filter(|term| !term.is_empty()) {
+        check_term(term)?;
+        if !terms.iter().any(|existing| existing == term) {
+            terms.push(term.to_string());
+        }
+    }
+    if terms.len() > MAX_REQUEST_TERMS {
+        return Err(format!("vocabulary 最多 {} 個詞彙: {}", MAX_REQUEST_TERMS, terms.len()));
+    }
+    Ok(terms)
+}
+
+/// 組合後的提示詞
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuiltPrompt {
+    pub text: String,
+    /// 因超出預算而略過的詞彙數
+    pub dropped_terms: usize,
+    /// 初始提示詞開頭是否被裁切
+    pub truncated_prompt: bool,
+}
+
+/// 在 token 預算內組合提示詞：詞彙表在前，初始提示詞在後
+///
+/// 詞彙優先保留，超出預算時依優先順序從最後的詞彙開始略過；
+/// 初始提示詞只使用剩餘預算，並保留結尾 (分段轉錄時結尾為前一區塊的文字)。
+/// 兩者皆為空時返回 None。
+pub fn build_prompt(
+    terms: &[String],
+    initial_prompt: Option<&str>,
+    budget: usize,
+    count_tokens: impl Fn(&str) -> usize,
+) -> Option<BuiltPrompt> {
+    let glossary = |count: usize| terms[..count].join(TERM_SEPARATOR);
+    let combine = |glossary: &str, prompt: &str| match (glossary.is_empty(), prompt.is_empty()) {
+        (false, false) => format!("{}{}{}", glossary, TERM_SEPARATOR, prompt),
+        (false, true) => glossary.to_string(),
+        _ => prompt.to_string(),
+    };
+
+    // token 數隨詞彙數單調遞增，二分搜尋可容納的最多詞彙數
+    let (mut low, mut high) = (0, terms.len());
+    while low < high {
+        let mid = (low + high).div_ceil(2);
+        if count_tokens(&glossary(mid)) <= budget {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    let kept_terms = low;
+    let glossary = glossary(kept_terms);
+
+    // 二分搜尋初始提示詞需略過的最少開頭字元數
+    let prompt = initial_prompt.map(str::trim).unwrap_or_default();
+    let chars: Vec<(usize, char)> = prompt.char_indices().collect();
+    let tail = |skip: usize| chars.get(skip).map_or("", |&(index, _)| prompt[index..].trim_start());
+    let (mut low, mut high) = (0, chars.len());
+    while low < high {
+        let mid = (low + high) / 2;
+        if count_tokens(&combine(&glossary, tail(mid))) <= budget {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    let prompt_tail = tail(low);
+
+    let text = combine(&glossary, prompt_tail);
+    (!text.is_empty()).then(|| BuiltPrompt {
+        text,
+        dropped_terms: terms.len() - kept_terms,
+        truncated_prompt: prompt_tail.len() < prompt.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 測試用 token 計數：每個字元一個 token
+    fn chars(text: &str) -> usize {
+        text.chars().count()
+    }
+
+    fn terms(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_terms_and_resolve_priority() {
+        let request = parse_terms("失智，居服員\n長照, 失智").unwrap();
+        assert_eq!(request, terms(&["失智", "居服員", "長照"]));
+        assert!(parse_terms(&"長".repeat(MAX_TERM_CHARS + 1)).is_err());
+
+        let config = VocabularyConfig {
+            terms: terms(&["長照", "Donepezil"]),
+            organizations: HashMap::from([("sunrise".to_string(), terms(&["王小明", "居服員"]))]),
+        };
+        assert_eq!(
+            config.resolve(Some("sunrise"), &request).unwrap(),
+            terms(&["失智", "居服員", "長照", "王小明", "Donepezil"])
+        );
+        assert_eq!(config.resolve(None, &[]).unwrap(), terms(&["長照", "Donepezil"]));
+        assert!(config.resolve(Some("unknown"), &[]).is_err());
+    }
+
+    #[test]
+    fn test_build_prompt_within_budget() {
+        let built = build_prompt(&terms(&["長照", "失智"]), Some("個案訪視紀錄"), 100, chars).unwrap();
+        assert_eq!(built.text, "長照, 失智, 個案訪視紀錄");
+        assert_eq!((built.dropped_terms, built.truncated_prompt), (0, false));
+        assert_eq!(build_prompt(&[], Some("  "), 100, chars), None);
+    }
+
+    #[test]
+    fn test_build_prompt_drops_terms_then_trims_prompt_start() {
+        // 詞彙表超出預算：略過最後的詞彙，初始提示詞沒有剩餘空間
+        let built = build_prompt(&terms(&["長照", "失智", "居服員"]), Some("上一段"), 6, chars).unwrap();
+        assert_eq!(built.text, "長照, 失智");
+        assert_eq!((built.dropped_terms, built.truncated_prompt), (1, true));
+
+        // 初始提示詞保留結尾
+        let built = build_prompt(&terms(&["長照"]), Some("前一區塊的 最後幾句話"), 10, chars).unwrap();
+        assert_eq!(built.text, "長照, 最後幾句話");
+        assert!(built.truncated_prompt);
+        assert!(chars(&built.text) <= 10);
+    }
+}
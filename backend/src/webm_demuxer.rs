@@ -0,0 +1,509 @@
+// ===================================
+// WebM/Matroska 解封裝器 - Opus 音軌
+// 解析 EBML 結構，從 Segment/Cluster/SimpleBlock/BlockGroup 取出完整 Opus 數據包
+// 支援 MediaRecorder 產生的未知長度 Segment/Cluster 以及 Xiph/EBML/固定長度 lacing
+// ===================================
+
+use anyhow::{anyhow, bail, Result};
+use metrics::{counter, histogram};
+use tracing::{debug, info, warn};
+
+// EBML / Matroska 元素 ID (保留長度標記位)
+const ID_EBML: u32 = 0x1A45_DFA3;
+const ID_SEGMENT: u32 = 0x1853_8067;
+const ID_TRACKS: u32 = 0x1654_AE6B;
+const ID_TRACK_ENTRY: u32 = 0xAE;
+const ID_TRACK_NUMBER: u32 = 0xD7;
+const ID_TRACK_TYPE: u32 = 0x83;
+const ID_CODEC_ID: u32 = 0x86;
+const ID_CODEC_PRIVATE: u32 = 0x63A2;
+const ID_AUDIO: u32 = 0xE1;
+const ID_CHANNELS: u32 = 0x9F;
+const ID_CLUSTER: u32 = 0x1F43_B675;
+const ID_SIMPLE_BLOCK: u32 = 0xA3;
+const ID_BLOCK_GROUP: u32 = 0xA0;
+const ID_BLOCK: u32 = 0xA1;
+
+/// Matroska 音頻軌道類型
+const TRACK_TYPE_AUDIO: u64 = 2;
+/// Opus 的 Matroska CodecID
+const CODEC_OPUS: &str = "A_OPUS";
+
+/// OpusHead 標頭資訊 (RFC 7845 §5.1)
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpusHead {
+    pub channels: u8,
+    /// 解碼後需丟棄的 48kHz 樣本數
+    pub pre_skip: u16,
+    /// 原始錄音採樣率 (僅供參考，Opus 一律以 48kHz 解碼)
+    pub input_sample_rate: u32,
+    /// 輸出增益 (Q7.8 dB)
+    pub output_gain: i16,
+    pub mapping_family: u8,
+}
+
+impl OpusHead {
+    /// 解析 OpusHead 結構 (WebM CodecPrivate 與 OGG 第一個數據包共用此格式)
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < 19 || &data[..8] != b"OpusHead" {
+            bail!("無效的 OpusHead ({} bytes)", data.len());
+        }
+        let channels = data[9];
+        if channels == 0 {
+            bail!("OpusHead 聲道數為 0");
+        }
+        Ok(Self {
+            channels,
+            pre_skip: u16::from_le_bytes([data[10], data[11]]),
+            input_sample_rate: u32::from_le_bytes([data[12], data[13], data[14], data[15]]),
+            output_gain: i16::from_le_bytes([data[16], data[17]]),
+            mapping_family: data[18],
+        })
+    }
+}
+
+/// 解封裝結果
+#[derive(Debug, Clone)]
+pub struct WebmOpusStream {
+    pub track_number: u64,
+    /// CodecPrivate 中的 OpusHead；缺少時為 None
+    pub head: Option<OpusHead>,
+    /// Matroska Audio 元素宣告的聲道數
+    pub channels: u8,
+    pub packets: Vec<Vec<u8>>,
+}
+
+impl WebmOpusStream {
+    /// 需丟棄的 48kHz 樣本數
+    pub fn pre_skip(&self) -> usize {
+        self.head.as_ref().map(|h| h.pre_skip as usize).unwrap_or(0)
+    }
+}
+
+/// 音軌描述 (解析 Tracks 時暫存)
+#[derive(Debug, Default)]
+struct TrackInfo {
+    number: u64,
+    track_type: u64,
+    codec_id: String,
+    codec_private: Vec<u8>,
+    channels: u64,
+}
+
+/// 元素標頭
+struct ElementHeader {
+    id: u32,
+    /// None 表示未知長度 (所有數值位為 1)
+    size: Option<u64>,
+    header_len: usize,
+}
+
+/// 讀取 EBML 變長整數，返回 (值, 長度)；keep_marker 為 true 時保留長度標記位 (用於元素 ID)
+fn read_vint(data: &[u8], keep_marker: bool) -> Option<(u64, usize)> {
+    let first = *data.first()?;
+    if first == 0 {
+        return None;
+    }
+    let len = first.leading_zeros() as usize + 1;
+    if data.len() < len {
+        return None;
+    }
+    let mut value = if keep_marker {
+        first as u64
+    } else {
+        first as u64 & ((1u64 << (8 - len)) - 1)
+    };
+    for &byte in &data[1..len] {
+        value = (value << 8) | byte as u64;
+    }
+    Some((value, len))
+}
+
+/// 讀取元素 ID 與長度
+fn read_element_header(data: &[u8]) -> Option<ElementHeader> {
+    let (id, id_len) = read_vint(data, true)?;
+    if id_len > 4 {
+        return None;
+    }
+    let (size, size_len) = read_vint(&data[id_len..], false)?;
+    let unknown = size == (1u64 << (7 * size_len)) - 1;
+    Some(ElementHeader {
+        id: id as u32,
+        size: if unknown { None } else { Some(size) },
+        header_len: id_len + size_len,
+    })
+}
+
+fn read_uint(data: &[u8]) -> u64 {
+    data.iter().take(8).fold(0u64, |acc, &b| (acc << 8) | b as u64)
+}
+
+/// 逐一走訪已知長度的子元素
+fn for_each_child<'a>(data: &'a [u8], mut visit: impl FnMut(u32, &'a [u8]) -> Result<()>) -> Result<()> {
+    let mut pos = 0;
+    while pos < data.len() {
+        let header = read_element_header(&data[pos..])
+            .ok_or_else(|| anyhow!("無效的 EBML 元素標頭 (偏移 {})", pos))?;
+        let start = pos + header.header_len;
+        let size = header
+            .size
+            .ok_or_else(|| anyhow!("元素 0x{:X} 不允許未知長度", header.id))? as usize;
+        let end = start
+            .checked_add(size)
+            .filter(|&end| end <= data.len())
+            .ok_or_else(|| anyhow!("元素 0x{:X} 超出父元素範圍", header.id))?;
+        visit(header.id, &data[start..end])?;
+        pos = end;
+    }
+    Ok(())
+}
+
+fn parse_tracks(data: &[u8], tracks: &mut Vec<TrackInfo>) -> Result<()> {
+    for_each_child(data, |id, body| {
+        if id != ID_TRACK_ENTRY {
+            return Ok(());
+        }
+        let mut track = TrackInfo::default();
+        for_each_child(body, |id, value| {
+            match id {
+                ID_TRACK_NUMBER => track.number = read_uint(value),
+                ID_TRACK_TYPE => track.track_type = read_uint(value),
+                ID_CODEC_ID => {
+                    track.codec_id = String::from_utf8_lossy(value).trim_end_matches('\0').to_string()
+                }
+                ID_CODEC_PRIVATE => track.codec_private = value.to_vec(),
+                ID_AUDIO => for_each_child(value, |id, value| {
+                    if id == ID_CHANNELS {
+                        track.channels = read_uint(value);
+                    }
+                    Ok(())
+                })?,
+                _ => {}
+            }
+            Ok(())
+        })?;
+        tracks.push(track);
+        Ok(())
+    })
+}
+
+/// 解析 Block / SimpleBlock 負載，返回 (軌道號, 幀列表)
+fn parse_block(data: &[u8]) -> Result<(u64, Vec<&[u8]>)> {
+    let (track, track_len) = read_vint(data, false).ok_or_else(|| anyhow!("無效的 Block 軌道號"))?;
+    // 軌道號後為 2 bytes 相對時間碼與 1 byte 旗標
+    let mut pos = track_len + 3;
+    if data.len() < pos {
+        bail!("Block 標頭被截斷");
+    }
+    let flags = data[pos - 1];
+    let lacing = (flags >> 1) & 0x03;
+    if lacing == 0 {
+        return Ok((track, vec![&data[pos..]]));
+    }
+
+    let frame_count = *data.get(pos).ok_or_else(|| anyhow!("Block lacing 標頭被截斷"))? as usize + 1;
+    pos += 1;
+
+    let mut sizes = Vec::with_capacity(frame_count);
+    match lacing {
+        // Xiph lacing: 每幀長度以 255 累加
+        1 => {
+            for _ in 0..frame_count - 1 {
+                let mut size = 0usize;
+                loop {
+                    let byte = *data.get(pos).ok_or_else(|| anyhow!("Xiph lacing 被截斷"))?;
+                    pos += 1;
+                    size += byte as usize;
+                    if byte != 255 {
+                        break;
+                    }
+                }
+                sizes.push(size);
+            }
+        }
+        // 固定長度 lacing
+        2 => {
+            let remaining = data.len() - pos;
+            if remaining % frame_count != 0 {
+                bail!("固定長度 lacing 無法整除: {} bytes / {} 幀", remaining, frame_count);
+            }
+            sizes.resize(frame_count - 1, remaining / frame_count);
+        }
+        // EBML lacing: 第一幀為無號 vint，其後為與前一幀的有號差值
+        _ if frame_count > 1 => {
+            let (first, len) = read_vint(&data[pos..], false).ok_or_else(|| anyhow!("EBML lacing 被截斷"))?;
+            pos += len;
+            let mut size = first as i64;
+            sizes.push(first as usize);
+            for _ in 1..frame_count - 1 {
+                let (raw, len) = read_vint(&data[pos..], false).ok_or_else(|| anyhow!("EBML lacing 被截斷"))?;
+                pos += len;
+                let bias = (1i64 << (7 * len - 1)) - 1;
+                size += raw as i64 - bias;
+                if size < 0 {
+                    bail!("EBML lacing 長度為負");
+                }
+                sizes.push(size as usize);
+            }
+        }
+        _ => {}
+    }
+
+    let mut frames = Vec::with_capacity(frame_count);
+    for size in sizes {
+        let end = pos + size;
+        if end > data.len() {
+            bail!("lacing 幀長度超出 Block 範圍");
+        }
+        frames.push(&data[pos..end]);
+        pos = end;
+    }
+    frames.push(&data[pos..]);
+    Ok((track, frames))
+}
+
+/// 從 WebM 數據中解出第一條 Opus 音軌的所有數據包
+pub fn demux_opus(data: &[u8]) -> Result<WebmOpusStream> {
+    let demux_start = std::time::Instant::now();
+
+    let mut tracks: Vec<TrackInfo> = Vec::new();
+    let mut opus_track: Option<u64> = None;
+    let mut packets: Vec<Vec<u8>> = Vec::new();
+    let mut laced_blocks = 0usize;
+
+    let mut pos = 0;
+    while pos < data.len() {
+        let Some(header) = read_element_header(&data[pos..]) else {
+            warn!("⚠️ WebM 元素標頭無效或被截斷 (偏移 {})，停止解析", pos);
+            break;
+        };
+        let body_start = pos + header.header_len;
+
+        // Segment 與 Cluster 直接進入其內容：MediaRecorder 串流輸出時兩者皆為未知長度，
+        // 未知長度的 Cluster 會在下一個 Cluster 或其他頂層元素出現時自然結束
+        if header.id == ID_SEGMENT || header.id == ID_CLUSTER {
+            pos = body_start;
+            continue;
+        }
+
+        let Some(size) = header.size else {
+            bail!("元素 0x{:X} 不允許未知長度", header.id);
+        };
+        let body_end = match body_start.checked_add(size as usize) {
+            Some(end) if end <= data.len() => end,
+            _ => {
+                // 錄音被中途截斷時最後一個 Block 可能不完整
+                warn!("⚠️ WebM 元素 0x{:X} 被截斷 (偏移 {})，忽略剩餘數據", header.id, pos);
+                counter!("webm_demux_truncated_total").increment(1);
+                break;
+            }
+        };
+        let body = &data[body_start..body_end];
+
+        match header.id {
+            ID_EBML => {}
+            ID_TRACKS => {
+                parse_tracks(body, &mut tracks)?;
+                opus_track = tracks
+                    .iter()
+                    .find(|t| t.track_type == TRACK_TYPE_AUDIO && t.codec_id == CODEC_OPUS)
+                    .map(|t| t.number);
+            }
+            ID_SIMPLE_BLOCK | ID_BLOCK_GROUP => {
+                let block = if header.id == ID_SIMPLE_BLOCK {
+                    Some(body)
+                } else {
+                    let mut block = None;
+                    for_each_child(body, |id, value| {
+                        if id == ID_BLOCK {
+                            block = Some(value);
+                        }
+                        Ok(())
+                    })?;
+                    block
+                };
+
+                if let (Some(block), Some(opus_track)) = (block, opus_track) {
+                    let (track, frames) = parse_block(block)?;
+                    if track == opus_track {
+                        if frames.len() > 1 {
+                            laced_blocks += 1;
+                        }
+                        packets.extend(frames.into_iter().filter(|f| !f.is_empty()).map(<[u8]>::to_vec));
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        pos = body_end;
+    }
+
+    let track = match opus_track.and_then(|number| tracks.iter().find(|t| t.number == number)) {
+        Some(track) => track,
+        None => {
+            let codecs: Vec<&str> = tracks.iter().map(|t| t.codec_id.as_str()).collect();
+            bail!("WebM 中沒有 Opus 音軌 (找到: {:?})", codecs);
+        }
+    };
+
+    let head = if track.codec_private.is_empty() {
+        None
+    } else {
+        Some(OpusHead::parse(&track.codec_private)?)
+    };
+    let channels = head
+        .as_ref()
+        .map(|h| h.channels)
+        .unwrap_or_else(|| track.channels.clamp(1, 2) as u8);
+
+    histogram!("webm_demux_time_ms").record(demux_start.elapsed().as_millis() as f64);
+    histogram!("webm_demux_packets").record(packets.len() as f64);
+    counter!("webm_demux_total").increment(1);
+
+    debug!("WebM 使用 lacing 的 Block 數: {}", laced_blocks);
+    info!(
+        "📦 WebM 解封裝完成: 軌道 {}, {} 聲道, pre-skip {}, {} 個 Opus 包",
+        track.number,
+        channels,
+        head.as_ref().map(|h| h.pre_skip).unwrap_or(0),
+        packets.len()
+    );
+
+    Ok(WebmOpusStream {
+        track_number: track.number,
+        head,
+        channels,
+        packets,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 編碼元素長度 (8 bytes vint)
+    fn element(id: u32, body: &[u8]) -> Vec<u8> {
+        let mut out = id.to_be_bytes().iter().copied().skip_while(|&b| b == 0).collect::<Vec<_>>();
+        out.push(0x01);
+        out.extend_from_slice(&(body.len() as u64).to_be_bytes()[1..]);
+        out.extend_from_slice(body);
+        out
+    }
+
+    /// 未知長度的 master 元素標頭
+    fn unknown_size(id: u32) -> Vec<u8> {
+        let mut out = id.to_be_bytes().to_vec();
+        out.extend_from_slice(&[0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+        out
+    }
+
+    fn opus_head(channels: u8, pre_skip: u16) -> Vec<u8> {
+        let mut head = b"OpusHead".to_vec();
+        head.push(1);
+        head.push(channels);
+        head.extend_from_slice(&pre_skip.to_le_bytes());
+        head.extend_from_slice(&48000u32.to_le_bytes());
+        head.extend_from_slice(&0i16.to_le_bytes());
+        head.push(0);
+        head
+    }
+
+    fn tracks(codec: &str, channels: u8, pre_skip: u16) -> Vec<u8> {
+        let entry = [
+            element(ID_TRACK_NUMBER, &[1]),
+            element(ID_TRACK_TYPE, &[2]),
+            element(ID_CODEC_ID, codec.as_bytes()),
+            element(ID_CODEC_PRIVATE, &opus_head(channels, pre_skip)),
+            element(ID_AUDIO, &element(ID_CHANNELS, &[channels])),
+        ]
+        .concat();
+        element(ID_TRACKS, &element(ID_TRACK_ENTRY, &entry))
+    }
+
+    fn block(flags: u8, payload: &[u8]) -> Vec<u8> {
+        let mut out = vec![0x81, 0x00, 0x00, flags];
+        out.extend_from_slice(payload);
+        out
+    }
+
+    /// MediaRecorder 風格：未知長度 Segment 與 Cluster
+    fn media_recorder_file(clusters: &[Vec<u8>]) -> Vec<u8> {
+        let mut file = element(ID_EBML, &element(0x4282, b"webm"));
+        file.extend(unknown_size(ID_SEGMENT));
+        file.extend(tracks(CODEC_OPUS, 2, 312));
+        for cluster in clusters {
+            file.extend(unknown_size(ID_CLUSTER));
+            file.extend(element(0xE7, &[0]));
+            file.extend(cluster);
+        }
+        file
+    }
+
+    #[test]
+    fn test_opus_head_and_simple_blocks_in_unknown_size_clusters() {
+        let file = media_recorder_file(&[
+            [
+                element(ID_SIMPLE_BLOCK, &block(0x80, &[0xFC, 1, 2])),
+                element(ID_SIMPLE_BLOCK, &block(0x80, &[0xFC, 3])),
+            ]
+            .concat(),
+            element(ID_BLOCK_GROUP, &element(ID_BLOCK, &block(0x00, &[0xFC, 4, 5, 6]))),
+        ]);
+
+        let stream = demux_opus(&file).unwrap();
+        assert_eq!(stream.channels, 2);
+        assert_eq!(stream.pre_skip(), 312);
+        assert_eq!(stream.packets, vec![vec![0xFC, 1, 2], vec![0xFC, 3], vec![0xFC, 4, 5, 6]]);
+    }
+
+    #[test]
+    fn test_xiph_ebml_and_fixed_lacing() {
+        // Xiph: 3 幀，長度 2, 1, 餘下
+        let xiph = block(0x82, &[2, 2, 1, 0xA0, 0xA1, 0xB0, 0xC0, 0xC1, 0xC2]);
+        // EBML: 3 幀，長度 3, 3-1=2, 餘下；差值 -1 以 1 byte 編碼為 0x80 | (63 - 1)
+        let ebml = block(0x86, &[2, 0x83, 0x80 | 62, 1, 2, 3, 4, 5, 6, 7]);
+        // 固定長度: 2 幀各 2 bytes
+        let fixed = block(0x84, &[1, 9, 9, 8, 8]);
+        let file = media_recorder_file(&[[
+            element(ID_SIMPLE_BLOCK, &xiph),
+            element(ID_SIMPLE_BLOCK, &ebml),
+            element(ID_SIMPLE_BLOCK, &fixed),
+        ]
+        .concat()]);
+
+        let stream = demux_opus(&file).unwrap();
+        assert_eq!(
+            stream.packets,
+            vec![
+                vec![0xA0, 0xA1],
+                vec![0xB0],
+                vec![0xC0, 0xC1, 0xC2],
+                vec![1, 2, 3],
+                vec![4, 5],
+                vec![6, 7],
+                vec![9, 9],
+                vec![8, 8],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_truncated_tail_keeps_complete_packets() {
+        let mut file = media_recorder_file(&[element(ID_SIMPLE_BLOCK, &block(0x80, &[0xFC, 1]))]);
+        let partial = element(ID_SIMPLE_BLOCK, &block(0x80, &[0xFC, 2, 3, 4]));
+        file.extend_from_slice(&partial[..partial.len() - 2]);
+
+        let stream = demux_opus(&file).unwrap();
+        assert_eq!(stream.packets, vec![vec![0xFC, 1]]);
+    }
+
+    #[test]
+    fn test_non_opus_track_is_rejected() {
+        let mut file = element(ID_EBML, &[]);
+        file.extend(unknown_size(ID_SEGMENT));
+        file.extend(tracks("A_VORBIS", 1, 0));
+        let err = demux_opus(&file).unwrap_err().to_string();
+        assert!(err.contains("A_VORBIS"));
+    }
+}
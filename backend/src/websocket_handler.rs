@@ -0,0 +1,579 @@
+// ===================================
+// WebSocket 即時轉錄
+// 客戶端持續送出 WebCodecs OPUS 包，伺服器回傳部分與最終段落
+// ===================================
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tracing::{info, error, warn, debug};
+use uuid::Uuid;
+
+use metrics::{counter, gauge};
+
+use crate::error::PipelineError;
+use crate::opus_decoder::{CareVoiceOpusDecoder, OpusDecoderConfig};
+use crate::resampler;
+use crate::upload_options::UploadOptions;
+use crate::whisper_model_pool::{TranscriptionOptions, WhisperModelPool};
+use crate::WhisperService;
+
+/// WebCodecs OPUS 固定採樣率
+const STREAM_SAMPLE_RATE: usize = 48000;
+/// 每累積多少個包解碼一次 (20ms 幀 × 10 = 200ms)
+const DECODE_BATCH_PACKETS: usize = 10;
+/// 新增多少秒音頻後產生一次部分結果
+const PARTIAL_INTERVAL_SECONDS: f64 = 1.0;
+/// 滾動窗口超過此長度時提交已穩定的段落
+const COMMIT_WINDOW_SECONDS: f64 = 20.0;
+/// 滾動緩衝區上限 (Whisper 單次最多處理 30 秒)
+const MAX_BUFFER_SECONDS: f64 = 30.0;
+
+/// 伺服器推送的訊息
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage {
+    /// 連線就緒
+    Ready { session_id: Uuid, sample_rate: usize },
+    /// 尚未穩定的部分結果，後續可能被修正
+    Partial { text: String, segments: Vec<StreamSegment> },
+    /// 已確定的段落，不會再變動
+    Final { segments: Vec<StreamSegment> },
+    /// 處理錯誤 (連線保持)
+    Error { message: String, code: &'static str, retryable: bool },
+    /// 串流結束
+    Done { duration_seconds: f64 },
+}
+
+impl ServerMessage {
+    fn error(e: &PipelineError) -> Self {
+        Self::Error {
+            message: e.to_string(),
+            code: e.code(),
+            retryable: e.is_retryable(),
+        }
+    }
+}
+
+/// 帶絕對時間戳的段落 (相對於串流開始)
+#[derive(Debug, Clone, Serialize)]
+struct StreamSegment {
+    start_time: f64,
+    end_time: f64,
+    text: String,
+}
+
+/// 客戶端控制訊息
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    /// 結束說話，輸出剩餘段落
+    Stop,
+}
+
+/// 送往 Whisper 的音頻窗口 (48kHz)
+struct AudioWindow {
+    samples_48k: Vec<f32>,
+    /// 窗口起點與終點在串流中的時間
+    start_seconds: f64,
+    end_seconds: f64,
+    /// 緩衝區溢出時切出的窗口：音頻已離開滾動緩衝區，段落全部直接提交
+    overflow: bool,
+    /// 切出窗口時的溢出次數，用於辨識已過時的部分結果
+    generation: u64,
+}
+
+impl AudioWindow {
+    fn new(samples_48k: Vec<f32>, start_seconds: f64, overflow: bool, generation: u64) -> Self {
+        let end_seconds = start_seconds + samples_48k.len() as f64 / STREAM_SAMPLE_RATE as f64;
+        Self { samples_48k, start_seconds, end_seconds, overflow, generation }
+    }
+}
+
+/// 滾動音頻緩衝區與段落提交邏輯
+#[derive(Default)]
+struct RollingBuffer {
+    /// 滾動音頻緩衝區 (48kHz)
+    samples_48k: Vec<f32>,
+    /// 緩衝區起點在串流中的時間
+    start_seconds: f64,
+    /// 上次部分結果之後新增的樣本數
+    samples_since_partial: usize,
+    /// 超過上限而切出、等待轉錄的窗口
+    overflow_windows: VecDeque<AudioWindow>,
+    /// 溢出次數
+    generation: u64,
+}
+
+impl RollingBuffer {
+    fn buffer_seconds(&self) -> f64 {
+        self.samples_48k.len() as f64 / STREAM_SAMPLE_RATE as f64
+    }
+
+    fn stream_seconds(&self) -> f64 {
+        self.start_seconds + self.buffer_seconds()
+    }
+
+    /// 移除緩衝區開頭的音頻
+    fn drop_front(&mut self, seconds: f64) {
+        let samples = ((seconds * STREAM_SAMPLE_RATE as f64) as usize).min(self.samples_48k.len());
+        self.samples_48k.drain(..samples);
+        self.start_seconds += samples as f64 / STREAM_SAMPLE_RATE as f64;
+    }
+
+    /// 加入新解碼的音頻；超過上限時切出最舊的完整窗口排入轉錄，不丟棄音頻
+    fn push(&mut self, samples: &[f32]) -> bool {
+        self.samples_since_partial += samples.len();
+        self.samples_48k.extend_from_slice(samples);
+
+        let max_samples = (MAX_BUFFER_SECONDS * STREAM_SAMPLE_RATE as f64) as usize;
+        let mut overflowed = false;
+        while self.samples_48k.len() > max_samples {
+            let rest = self.samples_48k.split_off(max_samples);
+            let window = AudioWindow::new(
+                std::mem::replace(&mut self.samples_48k, rest),
+                self.start_seconds,
+                true,
+                self.generation,
+            );
+            self.start_seconds = window.end_seconds;
+            self.samples_since_partial = self.samples_48k.len();
+            self.generation += 1;
+            self.overflow_windows.push_back(window);
+            overflowed = true;
+        }
+        overflowed
+    }
+
+    /// 下一個要轉錄的窗口：優先處理溢出窗口，其次在新增音頻足夠時轉錄滾動窗口
+    fn next_window(&mut self) -> Option<AudioWindow> {
+        if let Some(window) = self.overflow_windows.pop_front() {
+            return Some(window);
+        }
+        let new_seconds = self.samples_since_partial as f64 / STREAM_SAMPLE_RATE as f64;
+        if new_seconds < PARTIAL_INTERVAL_SECONDS {
+            return None;
+        }
+        Some(self.take_window())
+    }
+
+    /// 以目前整個滾動緩衝區建立窗口
+    fn take_window(&mut self) -> AudioWindow {
+        self.samples_since_partial = 0;
+        AudioWindow::new(self.samples_48k.clone(), self.start_seconds, false, self.generation)
+    }
+
+    /// 處理滾動窗口的轉錄結果，返回 (已提交段落, 部分結果)
+    ///
+    /// 窗口超過 COMMIT_WINDOW_SECONDS 時提交穩定段落 (最後一段可能仍在說話中，保留到下一輪)，flush 時提交全部；
+    /// 轉錄期間緩衝區已溢出時結果已過時，返回 None。
+    fn accept(
+        &mut self,
+        window: &AudioWindow,
+        mut segments: Vec<StreamSegment>,
+        flush: bool,
+    ) -> Option<(Vec<StreamSegment>, Vec<StreamSegment>)> {
+        if window.overflow {
+            return Some((segments, Vec::new()));
+        }
+        if window.generation != self.generation {
+            return None;
+        }
+
+        if !flush && window.end_seconds - window.start_seconds < COMMIT_WINDOW_SECONDS {
+            return Some((Vec::new(), segments));
+        }
+
+        let split = if flush || segments.len() < 2 { segments.len() } else { segments.len() - 1 };
+        let remaining = segments.split_off(split);
+        let committed = std::mem::replace(&mut segments, remaining);
+
+        // 只移除已提交的音頻；轉錄期間新增的音頻保留在緩衝區
+        let cut_seconds = match segments.first() {
+            Some(next) => next.start_time,
+            None => window.end_seconds,
+        };
+        self.drop_front((cut_seconds - self.start_seconds).max(0.0));
+
+        Some((committed, segments))
+    }
+}
+
+/// 單一連線的串流狀態
+struct StreamSession {
+    id: Uuid,
+    decoder: CareVoiceOpusDecoder,
+    options: TranscriptionOptions,
+    pending_packets: Vec<Vec<u8>>,
+    buffer: RollingBuffer,
+}
+
+impl StreamSession {
+    /// 解碼累積的 OPUS 包並加入滾動緩衝區
+    fn decode_pending(&mut self) -> Result<(), PipelineError> {
+        if self.pending_packets.is_empty() {
+            return Ok(());
+        }
+
+        let packets = std::mem::take(&mut self.pending_packets);
+        let samples = self.decoder.decode_webcodecs_packets(&packets)
+            .map_err(|e| PipelineError::CorruptContainer(format!("OPUS 包解碼失敗: {}", e)))?;
+        if self.buffer.push(&samples) {
+            warn!("⚠️ 串流 {} 緩衝區超過 {} 秒，強制轉錄並提交最舊的窗口", self.id, MAX_BUFFER_SECONDS);
+            counter!("websocket_buffer_overflow_total").increment(1);
+        }
+        Ok(())
+    }
+}
+
+/// 串流解碼器配置
+///
+/// 每批只有約 200ms，逐批峰值正規化會把靜音與背景噪音放大到滿幅，
+/// 且增益每批跳動，破壞 VAD 噪音底估計與幻覺過濾的靜音判斷。
+fn stream_decoder_config() -> OpusDecoderConfig {
+    OpusDecoderConfig {
+        sample_rate: STREAM_SAMPLE_RATE as u32,
+        channels: 1,
+        enable_normalization: false,
+        ..Default::default()
+    }
+}
+
+/// 轉錄窗口，段落時間戳轉為相對於串流開始
+async fn transcribe_window(
+    pool: Arc<WhisperModelPool>,
+    options: TranscriptionOptions,
+    samples_48k: Vec<f32>,
+    offset: f64,
+) -> Result<Vec<StreamSegment>, PipelineError> {
+    let samples_16k = resampler::resample_to_16k(&samples_48k, STREAM_SAMPLE_RATE as u32)
+        .map_err(|e| PipelineError::CorruptContainer(format!("串流音頻重採樣失敗: {}", e)))?;
+    let result = pool.transcribe_blocking(samples_16k, options).await?;
+
+    Ok(result.segments.into_iter()
+        .filter(|segment| !segment.text.trim().is_empty())
+        .map(|segment| StreamSegment {
+            start_time: offset + segment.start_time as f64,
+            end_time: offset + segment.end_time as f64,
+            text: segment.text.trim().to_string(),
+        })
+        .collect())
+}
+
+/// 在背景轉錄的窗口，接收迴圈可同時繼續接收音頻
+struct WindowTask {
+    window: AudioWindow,
+    handle: JoinHandle<Result<Vec<StreamSegment>, PipelineError>>,
+}
+
+impl WindowTask {
+    /// 樣本移交給背景任務，保留窗口的時間範圍供處理結果
+    fn spawn(pool: &Arc<WhisperModelPool>, options: &TranscriptionOptions, mut window: AudioWindow) -> Self {
+        let samples = std::mem::take(&mut window.samples_48k);
+        let handle = tokio::spawn(transcribe_window(pool.clone(), options.clone(), samples, window.start_seconds));
+        Self { window, handle }
+    }
+}
+
+/// GET /ws/transcribe - 升級為 WebSocket 即時轉錄
+///
+/// 查詢參數與 /upload 的選項欄位相同 (language, quality, initial_prompt, vocabulary, organization, translate, temperature)。
+pub async fn websocket_handler(
+    ws: WebSocketUpgrade,
+    State(whisper_service): State<Arc<WhisperService>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Response, PipelineError> {
+    let mut options = UploadOptions::default();
+    for (name, value) in &params {
+        if UploadOptions::is_option_field(name) {
+            options.apply_field(name, value).map_err(PipelineError::InvalidRequest)?;
+        }
+    }
+    whisper_service.apply_vocabulary(&mut options)?;
+
+    let decoder = CareVoiceOpusDecoder::new(stream_decoder_config()).map_err(|e| {
+        error!("串流 OPUS 解碼器初始化失敗: {}", e);
+        PipelineError::DecoderUnavailable(format!("OPUS 解碼器初始化失敗: {}", e))
+    })?;
+
+    let session = StreamSession {
+        id: Uuid::new_v4(),
+        decoder,
+        options: options.transcription,
+        pending_packets: Vec::new(),
+        buffer: RollingBuffer::default(),
+    };
+
+    Ok(ws.on_upgrade(move |socket| handle_socket(socket, whisper_service, session)))
+}
+
+async fn send_message(socket: &mut WebSocket, message: &ServerMessage) -> bool {
+    match serde_json::to_string(message) {
+        Ok(json) => socket.send(Message::Text(json)).await.is_ok(),
+        Err(e) => {
+            error!("WebSocket 訊息序列化失敗: {}", e);
+            false
+        }
+    }
+}
+
+/// 等待背景轉錄完成；沒有進行中的窗口時永不完成，select! 只處理 WebSocket 訊息
+async fn wait_window(in_flight: &mut Option<WindowTask>) -> Result<Vec<StreamSegment>, PipelineError> {
+    match in_flight {
+        Some(task) => (&mut task.handle).await
+            .unwrap_or_else(|e| Err(PipelineError::TranscriptionFailed(format!("串流轉錄任務中止: {}", e)))),
+        None => std::future::pending().await,
+    }
+}
+
+async fn handle_socket(mut socket: WebSocket, whisper_service: Arc<WhisperService>, mut session: StreamSession) {
+    info!("🔌 WebSocket 即時轉錄連線建立: {}", session.id);
+    counter!("websocket_sessions_total").increment(1);
+    gauge!("websocket_sessions_active").increment(1.0);
+
+    let pool = whisper_service.model_pool.clone();
+    let mut in_flight: Option<WindowTask> = None;
+    let mut stream_open = send_message(&mut socket, &ServerMessage::Ready {
+        session_id: session.id,
+        sample_rate: STREAM_SAMPLE_RATE,
+    }).await;
+
+    while stream_open {
+        // 同一時間只轉錄一個窗口，結果依序推送
+        if in_flight.is_none() {
+            in_flight = session.buffer.next_window()
+                .map(|window| WindowTask::spawn(&pool, &session.options, window));
+        }
+
+        tokio::select! {
+            outcome = wait_window(&mut in_flight) => {
+                if let Some(task) = in_flight.take() {
+                    stream_open = send_window_result(&mut socket, &mut session, &task.window, outcome, false).await;
+                }
+            },
+            message = socket.recv() => {
+                let Some(Ok(message)) = message else {
+                    break;
+                };
+
+                match message {
+                    Message::Binary(packet) => {
+                        session.pending_packets.push(packet);
+                        if session.pending_packets.len() < DECODE_BATCH_PACKETS {
+                            continue;
+                        }
+
+                        if let Err(e) = session.decode_pending() {
+                            warn!("⚠️ 串流 {} OPUS 解碼失敗: {}", session.id, e);
+                            stream_open = send_message(&mut socket, &ServerMessage::error(&e)).await;
+                        }
+                    },
+                    Message::Text(text) => match serde_json::from_str::<ClientMessage>(&text) {
+                        Ok(ClientMessage::Stop) => {
+                            finish_stream(&mut socket, &pool, &mut session, in_flight.take()).await;
+                            break;
+                        },
+                        Err(e) => {
+                            let error = PipelineError::InvalidRequest(format!("無效的控制訊息: {}", e));
+                            stream_open = send_message(&mut socket, &ServerMessage::error(&error)).await;
+                        },
+                    },
+                    Message::Close(_) => break,
+                    // Ping/Pong 由 axum 自動處理
+                    _ => {},
+                }
+            },
+        }
+    }
+
+    // 連線中斷時不再等待背景轉錄
+    if let Some(task) = in_flight {
+        task.handle.abort();
+    }
+    gauge!("websocket_sessions_active").decrement(1.0);
+    info!("🔌 WebSocket 即時轉錄連線結束: {} ({:.1} 秒音頻)", session.id, session.buffer.stream_seconds());
+}
+
+/// 推送窗口轉錄結果：先推送已提交段落，再推送部分結果
+async fn send_window_result(
+    socket: &mut WebSocket,
+    session: &mut StreamSession,
+    window: &AudioWindow,
+    outcome: Result<Vec<StreamSegment>, PipelineError>,
+    flush: bool,
+) -> bool {
+    let segments = match outcome {
+        Ok(segments) => segments,
+        Err(e) => {
+            warn!("⚠️ 串流 {} 轉錄失敗: {}", session.id, e);
+            return send_message(socket, &ServerMessage::error(&e)).await;
+        }
+    };
+
+    let Some((committed, partial)) = session.buffer.accept(window, segments, flush) else {
+        debug!("串流 {} 的部分結果已因緩衝區溢出過時，略過", session.id);
+        return true;
+    };
+
+    if !committed.is_empty() {
+        debug!("📌 串流 {} 提交 {} 段", session.id, committed.len());
+        counter!("websocket_final_segments_total").increment(committed.len() as u64);
+        if !send_message(socket, &ServerMessage::Final { segments: committed }).await {
+            return false;
+        }
+    }
+
+    if window.overflow || flush {
+        return true;
+    }
+    counter!("websocket_partials_total").increment(1);
+    let text = partial.iter().map(|segment| segment.text.as_str()).collect::<Vec<_>>().join("");
+    send_message(socket, &ServerMessage::Partial { text, segments: partial }).await
+}
+
+/// 結束串流：依序轉錄溢出窗口與剩餘音頻並提交全部段落
+async fn finish_stream(
+    socket: &mut WebSocket,
+    pool: &Arc<WhisperModelPool>,
+    session: &mut StreamSession,
+    mut in_flight: Option<WindowTask>,
+) {
+    if let Err(e) = session.decode_pending() {
+        warn!("⚠️ 串流 {} OPUS 解碼失敗: {}", session.id, e);
+    }
+
+    // 進行中的溢出窗口需要等待；部分結果會被最終轉錄取代
+    match in_flight.as_ref().map(|task| task.window.overflow) {
+        Some(true) => {
+            let outcome = wait_window(&mut in_flight).await;
+            if let Some(task) = in_flight.take() {
+                if !send_window_result(socket, session, &task.window, outcome, true).await {
+                    return;
+                }
+            }
+        },
+        Some(false) => {
+            if let Some(task) = in_flight.take() {
+                task.handle.abort();
+            }
+        },
+        None => {},
+    }
+
+    let mut windows: Vec<AudioWindow> = session.buffer.overflow_windows.drain(..).collect();
+    if !session.buffer.samples_48k.is_empty() {
+        windows.push(session.buffer.take_window());
+    }
+    for mut window in windows {
+        let samples = std::mem::take(&mut window.samples_48k);
+        let outcome = transcribe_window(pool.clone(), session.options.clone(), samples, window.start_seconds).await;
+        if !send_window_result(socket, session, &window, outcome, true).await {
+            return;
+        }
+    }
+
+    send_message(socket, &ServerMessage::Done { duration_seconds: session.buffer.stream_seconds() }).await;
+    let _ = socket.send(Message::Close(None)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seconds(seconds: f64) -> Vec<f32> {
+        vec![0.0; (seconds * STREAM_SAMPLE_RATE as f64) as usize]
+    }
+
+    fn segment(start_time: f64, end_time: f64, text: &str) -> StreamSegment {
+        StreamSegment { start_time, end_time, text: text.to_string() }
+    }
+
+    fn texts(segments: &[StreamSegment]) -> Vec<&str> {
+        segments.iter().map(|segment| segment.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_quiet_batch_is_not_amplified() {
+        let decoder = CareVoiceOpusDecoder::new(stream_decoder_config()).unwrap();
+        let quiet: Vec<f32> = (0..9600).map(|i| if i % 2 == 0 { 0.01 } else { -0.01 }).collect();
+        assert_eq!(decoder.post_process_audio(quiet.clone()).unwrap(), quiet);
+    }
+
+    #[test]
+    fn test_short_window_is_partial_only() {
+        let mut buffer = RollingBuffer::default();
+        buffer.push(&seconds(5.0));
+        let window = buffer.next_window().unwrap();
+        assert!(buffer.next_window().is_none());
+
+        let (committed, partial) = buffer.accept(&window, vec![segment(0.0, 4.0, "你好")], false).unwrap();
+        assert!(committed.is_empty());
+        assert_eq!(texts(&partial), ["你好"]);
+        assert_eq!(buffer.buffer_seconds(), 5.0);
+    }
+
+    #[test]
+    fn test_long_window_commits_all_but_last_segment() {
+        let mut buffer = RollingBuffer::default();
+        buffer.push(&seconds(21.0));
+        let window = buffer.next_window().unwrap();
+        // 轉錄期間繼續收到的音頻
+        buffer.push(&seconds(1.0));
+
+        let segments = vec![segment(0.0, 8.0, "一"), segment(8.0, 15.0, "二"), segment(15.5, 21.0, "三")];
+        let (committed, partial) = buffer.accept(&window, segments, false).unwrap();
+        assert_eq!(texts(&committed), ["一", "二"]);
+        assert_eq!(texts(&partial), ["三"]);
+        // 緩衝區從仍在說話的段落開始，保留轉錄期間新增的音頻
+        assert_eq!(buffer.start_seconds, 15.5);
+        assert!((buffer.stream_seconds() - 22.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_flush_commits_everything_up_to_window_end() {
+        let mut buffer = RollingBuffer::default();
+        buffer.push(&seconds(3.0));
+        let window = buffer.take_window();
+        buffer.push(&seconds(0.5));
+
+        let segments = vec![segment(0.0, 1.0, "一"), segment(1.0, 2.5, "二")];
+        let (committed, partial) = buffer.accept(&window, segments, true).unwrap();
+        assert_eq!(texts(&committed), ["一", "二"]);
+        assert!(partial.is_empty());
+        assert_eq!(buffer.start_seconds, 3.0);
+        assert_eq!(buffer.buffer_seconds(), 0.5);
+    }
+
+    #[test]
+    fn test_overflow_queues_oldest_window_instead_of_dropping_audio() {
+        let mut buffer = RollingBuffer::default();
+        assert!(!buffer.push(&seconds(MAX_BUFFER_SECONDS)));
+        let stale = buffer.take_window();
+        assert!(buffer.push(&seconds(1.0)));
+
+        // 溢出窗口優先轉錄，涵蓋完整的 30 秒
+        let window = buffer.next_window().unwrap();
+        assert!(window.overflow);
+        assert_eq!((window.start_seconds, window.end_seconds), (0.0, MAX_BUFFER_SECONDS));
+        assert_eq!(buffer.start_seconds, MAX_BUFFER_SECONDS);
+        assert_eq!(buffer.buffer_seconds(), 1.0);
+
+        // 溢出窗口的段落全部提交，不移除新的緩衝區
+        let segments = vec![segment(0.0, 20.0, "一"), segment(20.0, 30.0, "二")];
+        let (committed, partial) = buffer.accept(&window, segments, false).unwrap();
+        assert_eq!(texts(&committed), ["一", "二"]);
+        assert!(partial.is_empty());
+        assert_eq!(buffer.buffer_seconds(), 1.0);
+
+        // 溢出前切出的滾動窗口結果已過時
+        assert!(buffer.accept(&stale, vec![segment(0.0, 30.0, "舊")], false).is_none());
+    }
+}
@@ -3,25 +3,38 @@
 // 業界領先的智能模型選擇與 GPU 資源最佳化
 // ===================================
 
-use whisper_rs::{WhisperContext, WhisperContextParameters, FullParams, SamplingStrategy};
+use whisper_rs::{WhisperContext, WhisperContextParameters, WhisperState, FullParams};
 use std::sync::Arc;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use tracing::{info, error, warn, debug, span, Level};
 use anyhow::{Result, Context as AnyhowContext};
-use std::collections::HashMap;
-use std::time::Instant;
-use crossbeam::channel::{self, Receiver, Sender};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use crossbeam::channel::{self, Receiver, Sender, TrySendError};
 use uuid::Uuid;
 use std::sync::atomic::AtomicU64;
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
 
 // 效能監控
 use metrics::{counter, histogram, gauge};
 
-// CPU 資訊
-use num_cpus;
+use crate::app_config::WhisperConfig;
+use crate::chunker::{self, AudioChunk, ChunkPlanner};
+use crate::confidence::{self, TokenConfidence, TokenPiece};
+use crate::decoding::{DecodingConfig, TemperatureTrace};
+use crate::error::PipelineError;
+use crate::hallucination::{FilteredSegment, HallucinationConfig, HallucinationFilter, HallucinationReason};
+use crate::language_detect::LanguageDetection;
+use crate::model_registry::ModelRegistryConfig;
+use crate::resampler::WHISPER_SAMPLE_RATE;
+use crate::vad::{self, SpeechTimeline, VadConfig};
+use crate::vocabulary;
+use crate::word_timing::{self, WordTiming};
 
 /// 轉錄品質等級
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum TranscriptionQuality {
     /// 超快速處理 (0.05x 實時) - 適用於即時應用
     Turbo,
@@ -36,16 +49,6 @@ pub enum TranscriptionQuality {
 }
 
 impl TranscriptionQuality {
-    pub fn model_name(&self) -> &'static str {
-        match self {
-            Self::Turbo => "ggml-tiny.bin",
-            Self::Balanced => "ggml-base.bin", 
-            Self::Medium => "ggml-medium.bin",
-            Self::HighAccuracy => "ggml-large-v2.bin",
-            Self::Premium => "ggml-large-v3.bin",
-        }
-    }
-
     pub fn target_latency_ms(&self) -> u64 {
         match self {
             Self::Turbo => 50,
@@ -65,6 +68,62 @@ impl TranscriptionQuality {
     pub fn is_taiwanese_capable(&self) -> bool {
         matches!(self, Self::Premium)
     }
+
+    /// 指標標籤與請求參數使用的名稱
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Turbo => "turbo",
+            Self::Balanced => "balanced",
+            Self::Medium => "medium",
+            Self::HighAccuracy => "high_accuracy",
+            Self::Premium => "premium",
+        }
+    }
+
+    /// 從請求參數名稱解析品質等級
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "turbo" => Some(Self::Turbo),
+            "balanced" => Some(Self::Balanced),
+            "medium" => Some(Self::Medium),
+            "high_accuracy" | "high-accuracy" => Some(Self::HighAccuracy),
+            "premium" => Some(Self::Premium),
+            _ => None,
+        }
+    }
+}
+
+/// 單次轉錄的請求選項
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranscriptionOptions {
+    pub quality: TranscriptionQuality,
+    /// 語言代碼，None 表示由模型自動偵測
+    pub language: Option<String>,
+    /// 自動偵測時限定的候選語言，空表示不限
+    pub language_candidates: Vec<String>,
+    /// 初始提示詞 (上下文)
+    pub initial_prompt: Option<String>,
+    /// 專有詞彙 (已合併機構與共用詞彙，依優先順序排列)
+    pub vocabulary: Vec<String>,
+    /// 翻譯為英文
+    pub translate: bool,
+    /// 解碼溫度，None 表示使用品質等級預設值
+    pub temperature: Option<f32>,
+}
+
+impl Default for TranscriptionOptions {
+    /// 預設：中文優化模型 + 中文語言
+    fn default() -> Self {
+        Self {
+            quality: TranscriptionQuality::Medium,
+            language: Some("zh".to_string()),
+            language_candidates: Vec::new(),
+            initial_prompt: None,
+            vocabulary: Vec::new(),
+            translate: false,
+            temperature: None,
+        }
+    }
 }
 
 /// 轉錄任務
@@ -72,28 +131,203 @@ impl TranscriptionQuality {
 pub struct TranscriptionTask {
     pub id: Uuid,
     pub audio_samples: Vec<f32>,
-    pub quality: TranscriptionQuality,
-    pub language: Option<String>,
+    pub options: TranscriptionOptions,
     pub timestamp: Instant,
+    /// 完成通道：工作線程透過此通道回傳結果或錯誤
+    completion: oneshot::Sender<Result<TranscriptionResult, PipelineError>>,
+}
+
+/// 任務執行狀態 (僅追蹤尚未結束的任務)
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskStatus {
+    /// 已排入佇列，等待工作線程
+    Queued,
+    /// 工作線程處理中
+    Running,
+    /// 已取消，工作線程會丟棄此任務
+    Cancelled,
+}
+
+/// 已提交任務的控制代碼，用於等待轉錄結果
+#[derive(Debug)]
+pub struct TranscriptionHandle {
+    pub task_id: Uuid,
+    receiver: oneshot::Receiver<Result<TranscriptionResult, PipelineError>>,
+}
+
+impl TranscriptionHandle {
+    /// 等待工作線程回傳結果；任務被取消或工作線程中止時返回 Cancelled
+    pub async fn wait(self) -> Result<TranscriptionResult, PipelineError> {
+        match self.receiver.await {
+            Ok(outcome) => outcome,
+            Err(_) => {
+                debug!("轉錄任務 {} 已取消或工作線程已中止", self.task_id);
+                Err(PipelineError::Cancelled)
+            }
+        }
+    }
 }
 
 /// 轉錄結果
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TranscriptionResult {
     pub task_id: Uuid,
     pub transcript: String,
     pub confidence: Option<f32>,
     pub processing_time_ms: u64,
     pub model_used: String,
+    /// 轉錄使用的語言 (指定或自動偵測)
+    pub language: Option<String>,
+    /// 自動偵測語言時的機率分佈
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detected_language: Option<LanguageDetection>,
     pub segments: Vec<TranscriptSegment>,
+    /// 幻覺過濾移除的段落
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub filtered_segments: Vec<FilteredSegment>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TranscriptSegment {
     pub start_time: f32,
     pub end_time: f32,
     pub text: String,
+    /// 段落信心分數：文字 token 機率的幾何平均
     pub confidence: Option<f32>,
+    /// 文字 token 的平均對數機率
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_logprob: Option<f32>,
+    /// 產生此段的解碼溫度 (含溫度回退)
+    pub temperature: f32,
+    /// 逐 token 信心，供標示低信心的字詞
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tokens: Vec<TokenConfidence>,
+    /// 逐詞時間戳 (CJK 文字為逐字)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<WordTiming>,
+    /// 標記模式下疑似幻覺的原因
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hallucination: Option<HallucinationReason>,
+    /// 逐聲道轉錄時的來源聲道 (從 0 開始)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel: Option<usize>,
+    /// 逐聲道轉錄時該聲道的說話者標籤
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>,
+}
+
+#[cfg(test)]
+impl TranscriptSegment {
+    /// 測試用段落：只有時間與文字，其餘欄位為空
+    pub(crate) fn fixture(start_time: f32, end_time: f32, text: &str) -> Self {
+        Self {
+            start_time,
+            end_time,
+            text: text.to_string(),
+            confidence: None,
+            avg_logprob: None,
+            temperature: 0.0,
+            tokens: Vec::new(),
+            words: Vec::new(),
+            hallucination: None,
+            channel: None,
+            speaker: None,
+        }
+    }
+}
+
+#[cfg(test)]
+impl TranscriptionResult {
+    /// 測試用轉錄結果：完整文字與信心分數由段落計算
+    pub(crate) fn fixture(segments: Vec<TranscriptSegment>) -> Self {
+        Self {
+            task_id: Uuid::new_v4(),
+            transcript: segments.iter().map(|segment| segment.text.trim()).collect::<Vec<_>>().join(" "),
+            confidence: confidence::overall_confidence(&segments),
+            processing_time_ms: 0,
+            model_used: "ggml-base.bin".to_string(),
+            language: Some("zh".to_string()),
+            detected_language: None,
+            segments,
+            filtered_segments: Vec::new(),
+        }
+    }
+}
+
+/// 一次解碼收集的段落
+#[derive(Debug, Default)]
+struct DecodedSegments {
+    segments: Vec<TranscriptSegment>,
+    /// 各段在 Whisper 輸入音頻中的最大幀能量，供幻覺過濾比對文字與音量
+    peak_energies: Vec<Option<f32>>,
+}
+
+/// 合併多個部分結果 (區塊或聲道) 的語言，偵測結果依權重加權
+pub fn combined_language(parts: &[(&TranscriptionResult, f32)]) -> (Option<String>, Option<LanguageDetection>) {
+    let detections: Vec<(LanguageDetection, f32)> = parts
+        .iter()
+        .filter_map(|(result, weight)| Some((result.detected_language.clone()?, *weight)))
+        .collect();
+    let detected = LanguageDetection::combine(&detections);
+    let language = detected
+        .as_ref()
+        .map(|detection| detection.language.clone())
+        .or_else(|| parts.iter().find_map(|(result, _)| result.language.clone()));
+    (language, detected)
+}
+
+/// 合併逐聲道轉錄結果為單一時間軸
+///
+/// 每段標記來源聲道與說話者，依開始時間排序 (同時開始時聲道小者在前)；
+/// 完整文字以「說話者: 內容」逐段換行，信心分數由合併後的段落重新計算。
+pub fn merge_channel_results(mut results: Vec<(usize, String, TranscriptionResult)>) -> TranscriptionResult {
+    results.sort_by_key(|(channel, _, _)| *channel);
+
+    let processing_time_ms = results.iter().map(|(_, _, result)| result.processing_time_ms).max().unwrap_or(0);
+    let model_used = results.first().map(|(_, _, result)| result.model_used.clone()).unwrap_or_default();
+    // 各聲道的語言偵測依該聲道的語音長度加權
+    let (language, detected_language) = combined_language(&results
+        .iter()
+        .map(|(_, _, result)| (result, result.segments.iter().map(|s| s.end_time - s.start_time).sum::<f32>()))
+        .collect::<Vec<_>>());
+
+    let mut filtered_segments: Vec<FilteredSegment> = results
+        .iter_mut()
+        .flat_map(|(_, _, result)| std::mem::take(&mut result.filtered_segments))
+        .collect();
+    filtered_segments.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+
+    let mut segments: Vec<TranscriptSegment> = results
+        .into_iter()
+        .flat_map(|(channel, speaker, result)| {
+            result.segments.into_iter().map(move |segment| TranscriptSegment {
+                channel: Some(channel),
+                speaker: Some(speaker.clone()),
+                ..segment
+            })
+        })
+        .collect();
+    // 穩定排序保留同一時間點的聲道順序
+    segments.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+
+    let transcript = segments
+        .iter()
+        .filter(|segment| !segment.text.trim().is_empty())
+        .map(|segment| format!("{}: {}", segment.speaker.as_deref().unwrap_or_default(), segment.text.trim()))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    TranscriptionResult {
+        task_id: Uuid::new_v4(),
+        transcript,
+        confidence: confidence::overall_confidence(&segments),
+        processing_time_ms,
+        model_used,
+        language,
+        detected_language,
+        segments,
+        filtered_segments,
+    }
 }
 
 /// Whisper 模型實例
@@ -101,23 +335,28 @@ struct WhisperModel {
     context: WhisperContext,
     quality: TranscriptionQuality,
     model_path: String,
+    /// 配置的模型檔案名稱 (回報於 model_used)
+    model_file: String,
     creation_time: Instant,
     total_processed: AtomicU64,
     total_processing_time: AtomicU64,
 }
 
 impl WhisperModel {
-    fn new(model_path: String, quality: TranscriptionQuality) -> Result<Self> {
+    fn new(model_path: String, model_file: String, quality: TranscriptionQuality, use_gpu: bool) -> Result<Self> {
         let span = span!(Level::INFO, "whisper_model_creation", quality = ?quality);
         let _enter = span.enter();
 
-        info!("正在初始化 {} 模型: {}", quality.model_name(), model_path);
+        info!("正在初始化 {} 模型: {}", quality.label(), model_path);
         
         let start_time = Instant::now();
         
         // 🚀 業界領先 CUDA 兼容性檢測
-        let params = WhisperContextParameters::default();
-        if let Ok(_) = std::env::var("CUDA_VISIBLE_DEVICES") {
+        let mut params = WhisperContextParameters::default();
+        if !use_gpu {
+            info!("🔧 whisper.use_gpu=false，強制使用 CPU 模式");
+            params.use_gpu(false);
+        } else if let Ok(_) = std::env::var("CUDA_VISIBLE_DEVICES") {
             // GPU 可見時進行架構兼容性檢測
             if !WhisperModelPool::check_cuda_compatibility() {
                 warn!("🚨 CUDA 架構不兼容，但遵循 GPU 為生原則，繼續嘗試 GPU 模式");
@@ -131,23 +370,30 @@ impl WhisperModel {
         ).with_context(|| format!("無法載入 Whisper 模型: {}", model_path))?;
         
         let creation_time = start_time.elapsed();
-        info!("✅ {} 模型初始化完成，耗時: {:?}", quality.model_name(), creation_time);
+        info!("✅ {} 模型初始化完成，耗時: {:?}", model_file, creation_time);
         
         // 記錄模型載入指標
         histogram!("whisper_model_load_time_ms").record(creation_time.as_millis() as f64);
-        counter!("whisper_model_loaded_total", "quality" => quality.model_name()).increment(1);
+        counter!("whisper_model_loaded_total", "quality" => quality.label()).increment(1);
 
         Ok(Self {
             context,
             quality,
             model_path,
+            model_file,
             creation_time: Instant::now(),
             total_processed: AtomicU64::new(0),
             total_processing_time: AtomicU64::new(0),
         })
     }
 
-    async fn transcribe(&self, task: &TranscriptionTask) -> Result<TranscriptionResult> {
+    async fn transcribe(
+        &self,
+        task: &TranscriptionTask,
+        vad: &VadConfig,
+        decoding: &DecodingConfig,
+        hallucination: &HallucinationFilter,
+    ) -> Result<TranscriptionResult> {
         let span = span!(Level::DEBUG, "whisper_transcribe", 
             task_id = %task.id,
             quality = ?self.quality,
@@ -156,11 +402,47 @@ impl WhisperModel {
         let _enter = span.enter();
 
         let start_time = Instant::now();
-        
-        // 配置轉錄參數
-        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
-        
-        // 根據品質等級調整參數
+
+        // 語音活動偵測：只轉錄語音區段，時間戳稍後映射回原始時間軸
+        let timeline = vad.enabled
+            .then(|| SpeechTimeline::detect(&task.audio_samples, WHISPER_SAMPLE_RATE, vad));
+        let audio = timeline.as_ref()
+            .map_or(task.audio_samples.as_slice(), |timeline| timeline.audio.as_slice());
+        if audio.is_empty() {
+            debug!("🔇 任務 {} 沒有偵測到語音，跳過 Whisper", task.id);
+            return Ok(TranscriptionResult {
+                task_id: task.id,
+                transcript: String::new(),
+                confidence: None,
+                processing_time_ms: start_time.elapsed().as_millis() as u64,
+                model_used: self.model_file.clone(),
+                language: task.options.language.clone(),
+                detected_language: None,
+                segments: Vec::new(),
+                filtered_segments: Vec::new(),
+            });
+        }
+
+        let mut state = self.context.create_state()
+            .with_context(|| "無法創建 Whisper 狀態")?;
+
+        // 未指定語言時先做語言識別，以便回報機率並限定候選語言
+        let options = &task.options;
+        let detected_language = match options.language {
+            Some(_) => None,
+            None => self.detect_language(&mut state, audio, &options.language_candidates)?,
+        };
+        let language = options.language.clone()
+            .or_else(|| detected_language.as_ref().map(|detection| detection.language.clone()));
+
+        // 配置轉錄參數：取樣方式與溫度回退依請求的品質等級
+        let profile = decoding.profile(options.quality);
+        debug!("解碼設定 ({}): {:?}", options.quality.label(), profile);
+        let mut params = FullParams::new(profile.sampling_strategy());
+        profile.apply(&mut params);
+        hallucination.apply_params(&mut params);
+
+        // 根據模型調整執行緒與輸出
         match self.quality {
             TranscriptionQuality::Turbo => {
                 params.set_n_threads(4);
@@ -174,65 +456,51 @@ impl WhisperModel {
             },
             TranscriptionQuality::Medium => {
                 params.set_n_threads(8);
-                params.set_temperature(0.1);  // 中文優化：適度降低溫度
                 params.set_print_special(false);
                 params.set_print_progress(false);
             },
             TranscriptionQuality::HighAccuracy => {
                 params.set_n_threads(8);
-                params.set_temperature(0.0);
-                // params.set_best_of(3); // whisper-rs API 已變更
             },
             TranscriptionQuality::Premium => {
                 params.set_n_threads(8);
-                params.set_temperature(0.0);  // 中文最佳準確度設定
                 params.set_print_special(false);
                 params.set_print_progress(false);
-                // 中文優化：強制設定語言以提升準確度
-                params.set_language(Some("zh"));
             },
         }
 
-        // 設定語言
-        if let Some(ref language) = task.language {
-            params.set_language(Some(language));
+        // 套用請求選項
+        params.set_language(Some(language.as_deref().unwrap_or("auto")));
+        if let Some(prompt) = self.build_prompt(options) {
+            params.set_initial_prompt(&prompt);
+        }
+        params.set_translate(options.translate);
+        if let Some(temperature) = options.temperature {
+            params.set_temperature(temperature);
         }
 
         params.set_print_timestamps(true);
-        
-        // 執行轉錄
-        let mut state = self.context.create_state()
-            .with_context(|| "無法創建 Whisper 狀態")?;
-            
-        state.full(params, &task.audio_samples)
-            .with_context(|| "Whisper 轉錄失敗")?;
-
-        // 收集轉錄結果
-        let num_segments = state.full_n_segments()
-            .with_context(|| "無法獲取轉錄段數")?;
-
-        let mut segments = Vec::new();
-        let mut full_transcript = String::new();
+        // token 層級時間戳，用於逐詞對齊
+        params.set_token_timestamps(true);
 
-        for i in 0..num_segments {
-            let segment_text = state.full_get_segment_text(i)
-                .with_context(|| format!("無法獲取第 {} 段文字", i))?;
-            
-            let start_time = state.full_get_segment_t0(i)
-                .with_context(|| format!("無法獲取第 {} 段開始時間", i))? as f32 / 100.0;
-                
-            let end_time = state.full_get_segment_t1(i)
-                .with_context(|| format!("無法獲取第 {} 段結束時間", i))? as f32 / 100.0;
-
-            segments.push(TranscriptSegment {
-                start_time,
-                end_time,
-                text: segment_text.clone(),
-                confidence: None, // Whisper-rs 目前不提供信心分數
-            });
+        // 執行轉錄：whisper.cpp 逐 30 秒視窗做溫度回退，回呼記錄各視窗的解碼次數
+        let mut trace = TemperatureTrace::default();
+        // SAFETY: trace 在 state.full 返回前不會移動或釋放
+        unsafe { trace.install(&mut params) };
+        state.full(params, audio)
+            .with_context(|| "Whisper 轉錄失敗")?;
 
-            full_transcript.push_str(&segment_text);
+        let fallbacks = trace.fallback_count();
+        if fallbacks > 0 {
+            debug!("🌡️ 任務 {} 有 {} 次視窗解碼未達門檻，提高溫度重試", task.id, fallbacks);
+            counter!("whisper_temperature_fallback_total", "quality" => self.quality.label()).increment(fallbacks as u64);
         }
+        let schedule = profile.temperatures(options.temperature);
+        let decoded = self.collect_segments(&state, audio, timeline.as_ref(), |segment| trace.temperature(segment, &schedule))?;
+        let num_segments = decoded.segments.len();
+
+        let (segments, filtered_segments) = hallucination.apply(decoded.segments, &decoded.peak_energies);
+        let full_transcript: String = segments.iter().map(|segment| segment.text.as_str()).collect();
 
         let processing_time = start_time.elapsed();
         
@@ -244,29 +512,165 @@ impl WhisperModel {
         );
 
         // 記錄效能指標
-        histogram!("whisper_transcription_time_ms").record(processing_time.as_millis() as f64);
+        histogram!("whisper_transcription_time_ms", "quality" => self.quality.label())
+            .record(processing_time.as_millis() as f64);
         counter!("whisper_transcriptions_completed_total", 
-            "quality" => self.quality.model_name()).increment(1);
-        gauge!("whisper_audio_duration_seconds").set(task.audio_samples.len() as f64 / 16000.0);
+            "quality" => self.quality.label()).increment(1);
+        histogram!("whisper_audio_duration_seconds", "quality" => self.quality.label())
+            .record(task.audio_samples.len() as f64 / 16000.0);
 
         debug!("✅ 轉錄完成: {} 段, 耗時: {:?}", num_segments, processing_time);
 
         Ok(TranscriptionResult {
             task_id: task.id,
             transcript: full_transcript.trim().to_string(),
-            confidence: None,
+            confidence: confidence::overall_confidence(&segments),
             processing_time_ms: processing_time.as_millis() as u64,
-            model_used: self.quality.model_name().to_string(),
+            model_used: self.model_file.clone(),
+            language,
+            detected_language,
             segments,
+            filtered_segments,
         })
     }
 
+    /// 收集解碼的段落，時間戳映射回原始時間軸並標記各段的解碼溫度
+    fn collect_segments(
+        &self,
+        state: &WhisperState,
+        audio: &[f32],
+        timeline: Option<&SpeechTimeline>,
+        temperature: impl Fn(usize) -> f32,
+    ) -> Result<DecodedSegments> {
+        let num_segments = state.full_n_segments()
+            .with_context(|| "無法獲取轉錄段數")?;
+
+        let mut decoded = DecodedSegments::default();
+        let token_eot = self.context.token_eot();
+
+        for i in 0..num_segments {
+            let segment_text = state.full_get_segment_text(i)
+                .with_context(|| format!("無法獲取第 {} 段文字", i))?;
+            
+            let start_time = state.full_get_segment_t0(i)
+                .with_context(|| format!("無法獲取第 {} 段開始時間", i))? as f32 / 100.0;
+                
+            let end_time = state.full_get_segment_t1(i)
+                .with_context(|| format!("無法獲取第 {} 段結束時間", i))? as f32 / 100.0;
+
+            let to_sample = |seconds: f32| ((seconds.max(0.0) * WHISPER_SAMPLE_RATE as f32) as usize).min(audio.len());
+            let segment_audio = &audio[to_sample(start_time).min(to_sample(end_time))..to_sample(end_time)];
+            decoded.peak_energies.push(vad::peak_energy_db(segment_audio, WHISPER_SAMPLE_RATE, SEGMENT_ENERGY_FRAME_MS));
+
+            let to_original = |seconds: f32| match timeline {
+                Some(timeline) => timeline.to_original(seconds),
+                None => seconds,
+            };
+            let (start_time, end_time) = (to_original(start_time), to_original(end_time));
+            let end_time = end_time.max(start_time);
+
+            // 逐 token 機率；時間戳與控制 token (id >= EOT) 不計入信心
+            // whisper-rs 0.14 未提供 WhisperState 的 no-speech 機率，純靜音由 VAD 與幻覺過濾處理
+            let num_tokens = state.full_n_tokens(i)
+                .with_context(|| format!("無法獲取第 {} 段 token 數", i))?;
+            let mut pieces = Vec::with_capacity(num_tokens.max(0) as usize);
+            for j in 0..num_tokens {
+                let data = state.full_get_token_data(i, j)
+                    .with_context(|| format!("無法獲取第 {} 段第 {} 個 token", i, j))?;
+                if data.id >= token_eot {
+                    continue;
+                }
+                pieces.push(TokenPiece {
+                    bytes: state.full_get_token_bytes(i, j)
+                        .with_context(|| format!("無法獲取第 {} 段第 {} 個 token 文字", i, j))?,
+                    probability: data.p,
+                    logprob: data.plog,
+                    // token 時間以 10ms 為單位，限制在段落範圍內
+                    start: to_original(data.t0 as f32 / 100.0).clamp(start_time, end_time),
+                    end: to_original(data.t1 as f32 / 100.0).clamp(start_time, end_time),
+                });
+            }
+            let (confidence, avg_logprob, tokens) = match confidence::segment_confidence(&pieces) {
+                Some(scored) => {
+                    histogram!("whisper_segment_confidence", "quality" => self.quality.label())
+                        .record(scored.confidence as f64);
+                    (Some(scored.confidence), Some(scored.avg_logprob), scored.tokens)
+                },
+                None => (None, None, Vec::new()),
+            };
+
+            decoded.segments.push(TranscriptSegment {
+                start_time,
+                end_time,
+                text: segment_text,
+                confidence,
+                avg_logprob,
+                temperature: temperature(i as usize),
+                words: word_timing::group_words(&tokens),
+                tokens,
+                hallucination: None,
+                channel: None,
+                speaker: None,
+            });
+        }
+
+        Ok(decoded)
+    }
+
+    /// Whisper 語言識別 (分析音頻開頭 30 秒)
+    fn detect_language(
+        &self,
+        state: &mut WhisperState,
+        audio: &[f32],
+        candidates: &[String],
+    ) -> Result<Option<LanguageDetection>> {
+        let detect_start = Instant::now();
+        state.pcm_to_mel(audio, LANGUAGE_DETECT_THREADS)
+            .with_context(|| "無法計算語言識別所需的 mel 頻譜")?;
+        let (_, probabilities) = state.lang_detect(0, LANGUAGE_DETECT_THREADS)
+            .with_context(|| "Whisper 語言識別失敗")?;
+        let detection = LanguageDetection::from_probabilities(&probabilities, candidates);
+
+        histogram!("whisper_language_detect_time_ms").record(detect_start.elapsed().as_millis() as f64);
+        if let Some(ref detection) = detection {
+            counter!("whisper_language_detected_total", "language" => detection.language.clone()).increment(1);
+            info!("🌐 偵測語言: {} ({:.0}%)", detection.language, detection.probability * 100.0);
+        }
+        Ok(detection)
+    }
+
+    /// 組合詞彙與初始提示詞，限制在模型的提示詞 token 預算內
+    ///
+    /// whisper.cpp 只保留提示詞最後 n_text_ctx / 2 個 token，超出時會截掉開頭的詞彙表，
+    /// 因此在此先依優先順序裁切。
+    fn build_prompt(&self, options: &TranscriptionOptions) -> Option<String> {
+        let budget = (self.context.n_text_ctx() / 2).max(0) as usize;
+        // 每個 token 至少一個位元組，以位元組數作為上限確保緩衝區足夠
+        let count_tokens = |text: &str| {
+            self.context.tokenize(text, text.len() + 1).map_or(usize::MAX, |tokens| tokens.len())
+        };
+        let built = vocabulary::build_prompt(&options.vocabulary, options.initial_prompt.as_deref(), budget, count_tokens)?;
+
+        if built.dropped_terms > 0 || built.truncated_prompt {
+            counter!("whisper_prompt_truncated_total").increment(1);
+            warn!(
+                "⚠️ 提示詞超出 {} token 預算：略過 {} 個詞彙{}",
+                budget,
+                built.dropped_terms,
+                if built.truncated_prompt { "，初始提示詞已裁切開頭" } else { "" }
+            );
+        }
+        histogram!("whisper_prompt_tokens").record(count_tokens(&built.text) as f64);
+        Some(built.text)
+    }
+
     fn get_stats(&self) -> ModelStats {
         let total_processed = self.total_processed.load(std::sync::atomic::Ordering::Relaxed);
         let total_time = self.total_processing_time.load(std::sync::atomic::Ordering::Relaxed);
         
         ModelStats {
             quality: self.quality,
+            model_file: self.model_file.clone(),
             total_processed,
             total_processing_time_ms: total_time,
             average_processing_time_ms: if total_processed > 0 { 
@@ -283,29 +687,74 @@ impl WhisperModel {
 #[derive(Debug, Clone)]
 pub struct ModelStats {
     pub quality: TranscriptionQuality,
+    pub model_file: String,
     pub total_processed: u64,
     pub total_processing_time_ms: u64,
     pub average_processing_time_ms: u64,
     pub uptime: std::time::Duration,
 }
 
+/// 工作線程共用的轉錄設定
+#[derive(Debug, Clone)]
+struct WorkerSettings {
+    vad: VadConfig,
+    decoding: DecodingConfig,
+    hallucination: HallucinationFilter,
+    /// 請求的品質等級未載入時的回退順序
+    registry: ModelRegistryConfig,
+}
+
+/// 關閉排空時檢查工作線程的間隔
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// 期限到達並取消佇列任務後，等待閒置工作線程退出的時間
+const SHUTDOWN_JOIN_GRACE: Duration = Duration::from_secs(1);
+/// 語言識別使用的執行緒數
+const LANGUAGE_DETECT_THREADS: usize = 4;
+/// 計算段落最大幀能量的幀長度 (毫秒)
+const SEGMENT_ENERGY_FRAME_MS: u32 = 30;
+
+/// 模型池關閉結果
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownReport {
+    /// 開始關閉時尚未完成的任務數
+    pub pending_at_start: usize,
+    /// 期限內完成的任務數
+    pub drained: usize,
+    /// 超過期限而放棄的任務數 (佇列中被取消 + 仍在處理中)
+    pub abandoned: usize,
+    /// 已結束並回收的工作線程數
+    pub workers_joined: usize,
+    /// 仍在處理而未回收的工作線程數
+    pub workers_detached: usize,
+}
+
+/// 分段轉錄的區塊組 (對外以單一任務 ID 查詢與取消)
+#[derive(Debug)]
+struct ChunkGroup {
+    status: TaskStatus,
+    /// 已提交的區塊任務 ID
+    chunk_ids: Vec<Uuid>,
+}
+
 /// Whisper 模型池 - 業界領先的並行處理架構
 pub struct WhisperModelPool {
     models: RwLock<HashMap<TranscriptionQuality, Arc<WhisperModel>>>,
-    task_sender: Sender<TranscriptionTask>,
-    result_receiver: Arc<RwLock<HashMap<Uuid, TranscriptionResult>>>,
-    worker_handles: Vec<std::thread::JoinHandle<()>>,
+    /// 關閉後為 None，通道關閉使工作線程在排空佇列後退出
+    task_sender: Mutex<Option<Sender<TranscriptionTask>>>,
+    task_status: Arc<RwLock<HashMap<Uuid, TaskStatus>>>,
+    worker_handles: Mutex<Vec<std::thread::JoinHandle<()>>>,
+    /// 阻塞式轉錄超時
+    timeout: Duration,
+    /// 長音頻分段規劃
+    chunk_planner: ChunkPlanner,
+    /// 區塊以前一區塊文字作為提示詞 (依序轉錄)
+    chunk_carry_prompt: bool,
+    chunk_groups: RwLock<HashMap<Uuid, ChunkGroup>>,
 }
 
 impl WhisperModelPool {
     /// 🚀 業界領先 CUDA 架構兼容性檢測
     fn check_cuda_compatibility() -> bool {
-        // 檢查環境變數是否強制 CPU 模式
-        if std::env::var("WHISPER_USE_GPU").map(|v| v == "false").unwrap_or(false) {
-            info!("🔧 WHISPER_USE_GPU=false，強制使用 CPU 模式");
-            return false;
-        }
-        
         // 嘗試檢測 GPU compute capability
         if let Ok(output) = std::process::Command::new("nvidia-smi")
             .arg("--query-gpu=compute_cap")
@@ -343,30 +792,34 @@ impl WhisperModelPool {
     }
 
     /// 創建新的模型池
-    pub fn new(model_base_path: &str) -> Result<Self> {
+    pub fn new(
+        config: &WhisperConfig,
+        vad: &VadConfig,
+        decoding: &DecodingConfig,
+        hallucination: &HallucinationConfig,
+    ) -> Result<Self> {
         info!("🚀 正在初始化 Whisper 模型池...");
         
         let mut models = HashMap::new();
         
-        // 只載入最佳中文模型 (large-v3)
-        for quality in [
-            TranscriptionQuality::Premium,
-        ] {
-            let model_path = format!("{}/{}", model_base_path, quality.model_name());
-            
+        // 載入配置的品質等級 (預設只載入最佳中文模型 large-v3)
+        for &quality in &config.models.load {
+            let model_file = config.models.model_file(quality).to_string();
+            let model_path = config.models.model_path(&config.model_path, quality);
+
             // 檢查模型檔案是否存在
-            if !std::path::Path::new(&model_path).exists() {
-                warn!("⚠️  模型檔案不存在，跳過: {}", model_path);
+            if !model_path.exists() {
+                warn!("⚠️  {} 模型檔案不存在，跳過: {}", quality.label(), model_path.display());
                 continue;
             }
-            
-            match WhisperModel::new(model_path, quality) {
+
+            match WhisperModel::new(model_path.display().to_string(), model_file.clone(), quality, config.use_gpu) {
                 Ok(model) => {
                     models.insert(quality, Arc::new(model));
-                    info!("✅ {} 模型載入成功", quality.model_name());
+                    info!("✅ {} 模型載入成功: {}", quality.label(), model_file);
                 },
                 Err(e) => {
-                    error!("❌ {} 模型載入失敗: {}", quality.model_name(), e);
+                    error!("❌ {} 模型載入失敗 ({}): {}", quality.label(), model_file, e);
                 }
             }
         }
@@ -376,25 +829,36 @@ impl WhisperModelPool {
         }
 
         // 創建任務通道
-        let (task_sender, task_receiver) = channel::bounded(1000);
-        let result_storage = Arc::new(RwLock::new(HashMap::new()));
+        let (task_sender, task_receiver) = channel::bounded(config.queue_capacity);
+        let task_status = Arc::new(RwLock::new(HashMap::new()));
         
         // 啟動工作線程
         let worker_handles = Self::start_workers(
             Arc::new(RwLock::new(models.clone())),
             task_receiver,
-            result_storage.clone(),
+            task_status.clone(),
+            config.worker_count(),
+            WorkerSettings {
+                vad: vad.clone(),
+                decoding: decoding.clone(),
+                hallucination: HallucinationFilter::new(hallucination),
+                registry: config.models.clone(),
+            },
         );
 
         info!("✅ Whisper 模型池初始化完成，載入 {} 個模型", models.len());
         counter!("whisper_model_pool_initialized_total").increment(1);
-        gauge!("whisper_models_loaded_count").set(models.len() as f64);
+        gauge!("whisper_models_loaded").set(models.len() as f64);
 
         Ok(Self {
             models: RwLock::new(models),
-            task_sender,
-            result_receiver: result_storage,
-            worker_handles,
+            task_sender: Mutex::new(Some(task_sender)),
+            task_status,
+            worker_handles: Mutex::new(worker_handles),
+            timeout: config.timeout(),
+            chunk_planner: ChunkPlanner::new(config.chunk_secs, config.chunk_overlap_secs, WHISPER_SAMPLE_RATE),
+            chunk_carry_prompt: config.chunk_carry_prompt,
+            chunk_groups: RwLock::new(HashMap::new()),
         })
     }
 
@@ -402,16 +866,18 @@ impl WhisperModelPool {
     fn start_workers(
         models: Arc<RwLock<HashMap<TranscriptionQuality, Arc<WhisperModel>>>>,
         task_receiver: Receiver<TranscriptionTask>,
-        result_storage: Arc<RwLock<HashMap<Uuid, TranscriptionResult>>>,
+        task_status: Arc<RwLock<HashMap<Uuid, TaskStatus>>>,
+        num_workers: usize,
+        settings: WorkerSettings,
     ) -> Vec<std::thread::JoinHandle<()>> {
-        let num_workers = num_cpus::get().min(8);
-        info!("啟動 {} 個 Whisper 工作線程", num_workers);
+        info!("啟動 {} 個 Whisper 工作線程 (VAD: {})", num_workers, if settings.vad.enabled { "啟用" } else { "停用" });
 
         (0..num_workers)
             .map(|worker_id| {
                 let models = models.clone();
                 let task_receiver = task_receiver.clone();
-                let result_storage = result_storage.clone();
+                let task_status = task_status.clone();
+                let WorkerSettings { vad, decoding, hallucination, registry } = settings.clone();
 
                 std::thread::spawn(move || {
                     let rt = tokio::runtime::Runtime::new()
@@ -424,40 +890,65 @@ impl WhisperModelPool {
                         );
                         let _enter = span.enter();
 
+                        // 跳過已取消的任務，否則標記為處理中
+                        {
+                            let mut status = task_status.write();
+                            if status.get(&task.id) == Some(&TaskStatus::Cancelled) {
+                                status.remove(&task.id);
+                                debug!("⏭️  任務 {} 已取消，跳過", task.id);
+                                counter!("whisper_tasks_cancelled_total").increment(1);
+                                continue;
+                            }
+                            status.insert(task.id, TaskStatus::Running);
+                        }
+
                         // 選擇合適的模型
                         let model = {
                             let models_guard = models.read();
-                            if let Some(model) = models_guard.get(&task.quality) {
-                                model.clone()
-                            } else {
-                                // 智能回退：優先選擇中文優化模型
-                                if let Some(model) = models_guard.get(&TranscriptionQuality::Medium) {
-                                    warn!("所請求的品質 {:?} 不可用，回退到 Medium (中文優化)", task.quality);
-                                    model.clone()
-                                } else if let Some(model) = models_guard.get(&TranscriptionQuality::Balanced) {
-                                    warn!("所請求的品質 {:?} 不可用，回退到 Balanced", task.quality);
-                                    model.clone()
-                                } else if let Some((_, model)) = models_guard.iter().next() {
-                                    warn!("推薦模型不可用，使用第一個可用模型");
-                                    model.clone()
-                                } else {
-                                    error!("沒有可用的模型");
+                            let requested = task.options.quality;
+                            // 請求的等級未載入時依配置的回退順序選擇
+                            match registry.resolve(requested, |quality| models_guard.contains_key(&quality)) {
+                                Some(quality) => {
+                                    if quality != requested {
+                                        warn!("所請求的品質 {} 不可用，回退到 {}", requested.label(), quality.label());
+                                        counter!("whisper_model_fallback_total",
+                                            "requested" => requested.label(), "used" => quality.label()).increment(1);
+                                    }
+                                    models_guard[&quality].clone()
+                                },
+                                None => {
+                                    error!("沒有可用的模型 (請求: {})", requested.label());
+                                    task_status.write().remove(&task.id);
+                                    let _ = task.completion.send(Err(PipelineError::ModelUnavailable));
                                     continue;
-                                }
+                                },
                             }
                         };
 
                         // 執行轉錄
-                        match rt.block_on(model.transcribe(&task)) {
-                            Ok(result) => {
-                                debug!("✅ 任務 {} 完成", task.id);
-                                result_storage.write().insert(task.id, result);
-                            },
+                        let outcome = rt.block_on(model.transcribe(&task, &vad, &decoding, &hallucination))
+                            .map_err(|e| PipelineError::TranscriptionFailed(format!("{:#}", e)));
+
+                        // 處理期間被取消的任務直接丟棄結果
+                        if task_status.write().remove(&task.id) == Some(TaskStatus::Cancelled) {
+                            debug!("🗑️  任務 {} 已在處理期間取消，丟棄結果", task.id);
+                            counter!("whisper_tasks_cancelled_total").increment(1);
+                            continue;
+                        }
+
+                        match &outcome {
+                            Ok(_) => debug!("✅ 任務 {} 完成", task.id),
                             Err(e) => {
                                 error!("❌ 任務 {} 失敗: {}", task.id, e);
-                                counter!("whisper_transcription_errors_total").increment(1);
+                                counter!("whisper_transcription_errors_total",
+                                    "quality" => model.quality.label()).increment(1);
                             }
                         }
+
+                        // 呼叫端已放棄等待時，結果隨通道一併釋放
+                        if task.completion.send(outcome).is_err() {
+                            debug!("任務 {} 的呼叫端已不再等待，丟棄結果", task.id);
+                        }
                     }
 
                     info!("工作線程 {} 退出", worker_id);
@@ -470,57 +961,324 @@ impl WhisperModelPool {
     pub async fn transcribe_async(
         &self,
         audio_samples: Vec<f32>,
-        quality: TranscriptionQuality,
-        language: Option<String>,
-    ) -> Result<Uuid> {
-        let task_id = Uuid::new_v4();
+        options: TranscriptionOptions,
+    ) -> Result<TranscriptionHandle, PipelineError> {
+        self.submit(Uuid::new_v4(), audio_samples, options)
+    }
+
+    /// 以指定 ID 將任務送入佇列
+    fn submit(
+        &self,
+        task_id: Uuid,
+        audio_samples: Vec<f32>,
+        options: TranscriptionOptions,
+    ) -> Result<TranscriptionHandle, PipelineError> {
+        let Some(task_sender) = self.task_sender.lock().clone() else {
+            return Err(PipelineError::ShuttingDown);
+        };
+
+        let quality = options.quality;
+        let (completion, receiver) = oneshot::channel();
         let task = TranscriptionTask {
             id: task_id,
             audio_samples,
-            quality,
-            language,
+            options,
             timestamp: Instant::now(),
+            completion,
         };
 
-        self.task_sender.send(task)
-            .with_context(|| "任務佇列已滿，無法提交新任務")?;
+        self.task_status.write().insert(task_id, TaskStatus::Queued);
+
+        if let Err(e) = task_sender.try_send(task) {
+            self.task_status.write().remove(&task_id);
+            return Err(match e {
+                TrySendError::Full(_) => {
+                    warn!("⚠️ 轉錄佇列已滿，拒絕任務 {}", task_id);
+                    counter!("whisper_tasks_rejected_total", "reason" => "queue_full").increment(1);
+                    PipelineError::QueueFull
+                },
+                TrySendError::Disconnected(_) => PipelineError::ShuttingDown,
+            });
+        }
 
         counter!("whisper_tasks_submitted_total", 
-            "quality" => quality.model_name()).increment(1);
+            "quality" => quality.label()).increment(1);
 
         debug!("📝 任務 {} 已提交 (品質: {:?})", task_id, quality);
-        Ok(task_id)
+        Ok(TranscriptionHandle { task_id, receiver })
     }
 
-    /// 獲取轉錄結果
-    pub fn get_result(&self, task_id: Uuid) -> Option<TranscriptionResult> {
-        self.result_receiver.write().remove(&task_id)
+    /// 是否仍接受新任務 (關閉排空期間為 false)
+    pub fn is_accepting(&self) -> bool {
+        self.task_sender.lock().is_some()
     }
 
-    /// 阻塞式轉錄 (向後相容)
+    /// 檢查目前能否接受新任務 (關閉中或佇列已滿時返回對應錯誤)
+    pub fn ensure_capacity(&self) -> Result<(), PipelineError> {
+        match self.task_sender.lock().as_ref() {
+            None => Err(PipelineError::ShuttingDown),
+            Some(sender) if sender.is_full() => {
+                counter!("whisper_tasks_rejected_total", "reason" => "queue_full").increment(1);
+                Err(PipelineError::QueueFull)
+            },
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// 優雅關閉：停止接受新任務，在期限內處理完佇列，回收工作線程
+    ///
+    /// 期限到達時取消仍在佇列中的任務；處理中的任務無法中斷，其工作線程不再等待。
+    pub async fn shutdown(&self, deadline: Duration) -> ShutdownReport {
+        let shutdown_start = Instant::now();
+        self.task_sender.lock().take();
+
+        let pending_at_start = self.task_status.read()
+            .values()
+            .filter(|status| **status != TaskStatus::Cancelled)
+            .count();
+        info!("🛑 Whisper 模型池停止接受新任務，排空 {} 個任務 (期限 {:?})", pending_at_start, deadline);
+
+        let handles = std::mem::take(&mut *self.worker_handles.lock());
+        let all_finished = |handles: &[std::thread::JoinHandle<()>]| handles.iter().all(|handle| handle.is_finished());
+
+        while !all_finished(&handles) && shutdown_start.elapsed() < deadline {
+            tokio::time::sleep(SHUTDOWN_POLL_INTERVAL).await;
+        }
+
+        // 期限到達：取消佇列中的任務，呼叫端會收到通道關閉錯誤
+        let abandoned = {
+            let mut status = self.task_status.write();
+            let mut abandoned = 0;
+            for task_status in status.values_mut() {
+                match task_status {
+                    TaskStatus::Queued => {
+                        *task_status = TaskStatus::Cancelled;
+                        abandoned += 1;
+                    },
+                    TaskStatus::Running => abandoned += 1,
+                    TaskStatus::Cancelled => {},
+                }
+            }
+            abandoned
+        };
+
+        if abandoned > 0 {
+            let grace_start = Instant::now();
+            while !all_finished(&handles) && grace_start.elapsed() < SHUTDOWN_JOIN_GRACE {
+                tokio::time::sleep(SHUTDOWN_POLL_INTERVAL).await;
+            }
+        }
+
+        let mut report = ShutdownReport {
+            pending_at_start,
+            drained: pending_at_start.saturating_sub(abandoned),
+            abandoned,
+            ..Default::default()
+        };
+        for handle in handles {
+            if handle.is_finished() {
+                if handle.join().is_err() {
+                    warn!("⚠️ 工作線程異常結束");
+                }
+                report.workers_joined += 1;
+            } else {
+                // 處理中的工作線程隨行程結束
+                report.workers_detached += 1;
+            }
+        }
+
+        counter!("whisper_tasks_abandoned_total").increment(abandoned as u64);
+        histogram!("whisper_shutdown_drain_time_ms").record(shutdown_start.elapsed().as_millis() as f64);
+        report
+    }
+
+    /// 查詢任務狀態 (已結束的任務返回 None)；分段轉錄以整組狀態回報
+    pub fn get_task_status(&self, task_id: Uuid) -> Option<TaskStatus> {
+        if let Some(group) = self.chunk_groups.read().get(&task_id) {
+            return Some(group.status.clone());
+        }
+        self.task_status.read().get(&task_id).cloned()
+    }
+
+    /// 取消佇列中或處理中的任務，返回取消前的狀態；分段轉錄會一併取消所有區塊
+    pub fn cancel_task(&self, task_id: Uuid) -> Option<TaskStatus> {
+        let group = self.chunk_groups.write().get_mut(&task_id).map(|group| {
+            (std::mem::replace(&mut group.status, TaskStatus::Cancelled), group.chunk_ids.clone())
+        });
+        if let Some((previous, chunk_ids)) = group {
+            for chunk_id in &chunk_ids {
+                self.cancel_task(*chunk_id);
+            }
+            debug!("🛑 分段轉錄 {} 已取消 ({} 個區塊)", task_id, chunk_ids.len());
+            return Some(previous);
+        }
+
+        let mut status = self.task_status.write();
+        let previous = status.get(&task_id).cloned()?;
+
+        // 工作線程會清理狀態並關閉完成通道
+        if previous != TaskStatus::Cancelled {
+            status.insert(task_id, TaskStatus::Cancelled);
+            debug!("🛑 任務 {} 已取消 (原狀態: {:?})", task_id, previous);
+        }
+
+        Some(previous)
+    }
+
+    /// 阻塞式轉錄的區塊等待上限
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// 阻塞式轉錄 (向後相容)；長音頻自動分段處理
     pub async fn transcribe_blocking(
         &self,
         audio_samples: Vec<f32>,
-        quality: TranscriptionQuality,
-        language: Option<String>,
-    ) -> Result<TranscriptionResult> {
-        let task_id = self.transcribe_async(audio_samples, quality, language).await?;
-        
-        // 輪詢結果
-        let start_time = Instant::now();
-        let timeout = std::time::Duration::from_secs(90); // 增加到90秒以處理OPUS解碼修復
-        
-        loop {
-            if let Some(result) = self.get_result(task_id) {
-                return Ok(result);
+        options: TranscriptionOptions,
+    ) -> Result<TranscriptionResult, PipelineError> {
+        self.transcribe_chunked(Uuid::new_v4(), audio_samples, options, Some(self.timeout)).await
+    }
+
+    /// 轉錄並等待結果：超過區塊長度的音頻在靜音處分段，逐一 (或停用提示詞延續時並行) 轉錄後拼接
+    ///
+    /// `task_id` 可用於 get_task_status / cancel_task (分段時代表整組區塊)；
+    /// `timeout` 套用於每個區塊的等待時間，None 表示不限時。
+    pub async fn transcribe_chunked(
+        &self,
+        task_id: Uuid,
+        audio_samples: Vec<f32>,
+        options: TranscriptionOptions,
+        timeout: Option<Duration>,
+    ) -> Result<TranscriptionResult, PipelineError> {
+        let chunks = self.chunk_planner.plan(&audio_samples);
+        if chunks.len() <= 1 {
+            let handle = self.submit(task_id, audio_samples, options)?;
+            return self.wait_with_timeout(handle, timeout).await;
+        }
+
+        self.chunk_groups.write().insert(task_id, ChunkGroup {
+            status: TaskStatus::Queued,
+            chunk_ids: Vec::with_capacity(chunks.len()),
+        });
+        let outcome = self.run_chunks(task_id, &audio_samples, &chunks, options, timeout).await;
+        self.chunk_groups.write().remove(&task_id);
+        outcome
+    }
+
+    /// 等待完成通道，工作線程失敗時立即返回錯誤；超時則取消任務
+    async fn wait_with_timeout(
+        &self,
+        handle: TranscriptionHandle,
+        timeout: Option<Duration>,
+    ) -> Result<TranscriptionResult, PipelineError> {
+        let task_id = handle.task_id;
+        let Some(timeout) = timeout else {
+            return handle.wait().await;
+        };
+
+        match tokio::time::timeout(timeout, handle.wait()).await {
+            Ok(outcome) => outcome,
+            Err(_) => {
+                self.cancel_task(task_id);
+                Err(PipelineError::Timeout { seconds: timeout.as_secs() })
             }
-            
-            if start_time.elapsed() > timeout {
-                return Err(anyhow::anyhow!("轉錄超時"));
+        }
+    }
+
+    /// 提交並等待所有區塊
+    ///
+    /// 啟用提示詞延續時，區塊 i 在區塊 i-1 完成後才提交並以其文字作為提示詞；
+    /// 停用時不帶前文，同時最多提交與工作線程數相同的區塊。
+    async fn run_chunks(
+        &self,
+        group_id: Uuid,
+        samples: &[f32],
+        chunks: &[AudioChunk],
+        options: TranscriptionOptions,
+        timeout: Option<Duration>,
+    ) -> Result<TranscriptionResult, PipelineError> {
+        let chunk_start = Instant::now();
+        let max_in_flight = if self.chunk_carry_prompt {
+            1
+        } else {
+            self.worker_handles.lock().len().max(1)
+        };
+        info!(
+            "✂️ 長音頻分段轉錄: {:.1} 秒 → {} 個區塊 (同時 {} 個)",
+            samples.len() as f64 / WHISPER_SAMPLE_RATE as f64,
+            chunks.len(),
+            max_in_flight
+        );
+        counter!("whisper_chunked_transcriptions_total").increment(1);
+        histogram!("whisper_chunks_per_transcription").record(chunks.len() as f64);
+
+        let set_group_status = |status: TaskStatus| {
+            if let Some(group) = self.chunk_groups.write().get_mut(&group_id) {
+                if group.status != TaskStatus::Cancelled {
+                    group.status = status;
+                }
             }
-            
-            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        };
+
+        let mut results: Vec<Option<TranscriptionResult>> = vec![None; chunks.len()];
+        let mut in_flight: VecDeque<(usize, TranscriptionHandle)> = VecDeque::new();
+        let mut next = 0;
+
+        let outcome = 'chunks: loop {
+            if self.get_task_status(group_id) == Some(TaskStatus::Cancelled) {
+                break Err(PipelineError::Cancelled);
+            }
+
+            while in_flight.len() < max_in_flight && next < chunks.len() {
+                // 依序轉錄時前一區塊必定已完成
+                let previous = match next.checked_sub(1) {
+                    Some(index) if self.chunk_carry_prompt => results[index].as_ref().map(|result| result.transcript.as_str()),
+                    _ => None,
+                };
+                let chunk_options = TranscriptionOptions {
+                    initial_prompt: chunker::carry_prompt(options.initial_prompt.as_deref(), previous),
+                    ..options.clone()
+                };
+                let chunk = chunks[next];
+                let chunk_id = Uuid::new_v4();
+                match self.submit(chunk_id, samples[chunk.start..chunk.end].to_vec(), chunk_options) {
+                    Ok(handle) => in_flight.push_back((next, handle)),
+                    Err(e) => break 'chunks Err(e),
+                }
+                if let Some(group) = self.chunk_groups.write().get_mut(&group_id) {
+                    group.chunk_ids.push(chunk_id);
+                }
+                next += 1;
+            }
+
+            let Some((index, handle)) = in_flight.pop_front() else {
+                break Ok(());
+            };
+            if self.get_task_status(handle.task_id) != Some(TaskStatus::Queued) {
+                set_group_status(TaskStatus::Running);
+            }
+            match self.wait_with_timeout(handle, timeout).await {
+                Ok(result) => {
+                    debug!("區塊 {}/{} 完成: {} 段", index + 1, chunks.len(), result.segments.len());
+                    set_group_status(TaskStatus::Running);
+                    results[index] = Some(result);
+                },
+                Err(e) => break Err(e),
+            }
+        };
+
+        if let Err(e) = outcome {
+            for (_, handle) in &in_flight {
+                self.cancel_task(handle.task_id);
+            }
+            counter!("whisper_chunked_transcription_errors_total", "code" => e.code()).increment(1);
+            return Err(e);
         }
+
+        let results: Vec<TranscriptionResult> = results.into_iter().flatten().collect();
+        let processing_time = chunk_start.elapsed();
+        histogram!("whisper_chunked_transcription_time_ms").record(processing_time.as_millis() as f64);
+        Ok(chunker::stitch(chunks, results, WHISPER_SAMPLE_RATE, group_id, processing_time.as_millis() as u64))
     }
 
     /// 自適應品質轉錄
@@ -549,7 +1307,12 @@ impl WhisperModelPool {
         };
 
         info!("🎯 自適應品質選擇: {:?} (音頻: {}ms)", quality, audio_duration_ms);
-        self.transcribe_blocking(audio_samples, quality, None).await
+        let options = TranscriptionOptions {
+            quality,
+            language: None,
+            ..Default::default()
+        };
+        Ok(self.transcribe_blocking(audio_samples, options).await?)
     }
 
     /// 中文優化轉錄 - 針對正體中文和台語
@@ -579,7 +1342,12 @@ impl WhisperModelPool {
         });
 
         info!("🀄 中文優化轉錄: {:?}, 台語: {}, 語言: {}", quality, is_taiwanese, language);
-        self.transcribe_blocking(audio_samples, quality, Some(language)).await
+        let options = TranscriptionOptions {
+            quality,
+            language: Some(language),
+            ..Default::default()
+        };
+        Ok(self.transcribe_blocking(audio_samples, options).await?)
     }
 
     /// 獲取模型池統計資料
@@ -599,6 +1367,156 @@ impl WhisperModelPool {
 impl Drop for WhisperModelPool {
     fn drop(&mut self) {
         info!("正在關閉 Whisper 模型池...");
-        // 工作線程會在通道關閉時自動退出
+        // 關閉任務通道，工作線程處理完剩餘任務後自動退出
+        self.task_sender.get_mut().take();
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handle_with_sender() -> (TranscriptionHandle, oneshot::Sender<Result<TranscriptionResult, PipelineError>>) {
+        let (sender, receiver) = oneshot::channel();
+        (TranscriptionHandle { task_id: Uuid::new_v4(), receiver }, sender)
+    }
+
+    fn pool_without_models(workers: usize) -> WhisperModelPool {
+        let (task_sender, task_receiver) = channel::bounded(16);
+        let task_status = Arc::new(RwLock::new(HashMap::new()));
+        let worker_handles = WhisperModelPool::start_workers(
+            Arc::new(RwLock::new(HashMap::new())),
+            task_receiver,
+            task_status.clone(),
+            workers,
+            WorkerSettings {
+                vad: VadConfig::default(),
+                decoding: DecodingConfig::default(),
+                hallucination: HallucinationFilter::new(&HallucinationConfig::default()),
+                registry: ModelRegistryConfig::default(),
+            },
+        );
+
+        WhisperModelPool {
+            models: RwLock::new(HashMap::new()),
+            task_sender: Mutex::new(Some(task_sender)),
+            task_status,
+            worker_handles: Mutex::new(worker_handles),
+            timeout: Duration::from_secs(5),
+            chunk_planner: ChunkPlanner::new(30, 2, WHISPER_SAMPLE_RATE),
+            chunk_carry_prompt: true,
+            chunk_groups: RwLock::new(HashMap::new()),
+        }
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_chunk_prompt_comes_from_previous_chunk() {
+        let (task_sender, task_receiver) = channel::bounded::<TranscriptionTask>(16);
+        let mut pool = pool_without_models(0);
+        *pool.task_sender.get_mut() = Some(task_sender);
+        // 多個工作線程時仍須依序提交才能延續提示詞
+        *pool.worker_handles.get_mut() = (0..4).map(|_| std::thread::spawn(|| ())).collect();
+
+        // 模擬工作線程：記錄收到的提示詞，依收到順序回傳「第 N 段」
+        let prompts = Arc::new(Mutex::new(Vec::new()));
+        let worker = std::thread::spawn({
+            let prompts = prompts.clone();
+            move || {
+                for (index, task) in task_receiver.iter().enumerate() {
+                    prompts.lock().push(task.options.initial_prompt.clone());
+                    let result = TranscriptionResult {
+                        transcript: format!("第{}段", index),
+                        ..TranscriptionResult::fixture(Vec::new())
+                    };
+                    let _ = task.completion.send(Ok(result));
+                }
+            }
+        });
+
+        let samples = vec![0.1; 100 * WHISPER_SAMPLE_RATE as usize];
+        let options = TranscriptionOptions { initial_prompt: Some("長照".to_string()), ..Default::default() };
+        pool.transcribe_chunked(Uuid::new_v4(), samples, options, None).await.unwrap();
+        pool.task_sender.lock().take();
+        worker.join().unwrap();
+
+        let prompts = prompts.lock();
+        assert!(prompts.len() > 2);
+        assert_eq!(prompts[0].as_deref(), Some("長照"));
+        for (index, prompt) in prompts.iter().enumerate().skip(1) {
+            assert_eq!(prompt.clone(), chunker::carry_prompt(Some("長照"), Some(&format!("第{}段", index - 1))));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_drains_queue_and_rejects_new_tasks() {
+        let pool = pool_without_models(2);
+        let handle = pool.transcribe_async(vec![0.0; 16000], TranscriptionOptions::default()).await.unwrap();
+
+        let report = pool.shutdown(Duration::from_secs(5)).await;
+        assert!(!pool.is_accepting());
+        assert_eq!(report.abandoned, 0);
+        assert_eq!(report.workers_joined, 2);
+        assert_eq!(report.workers_detached, 0);
+
+        // 已提交的任務仍由工作線程處理 (無模型時回報錯誤而非被丟棄)
+        assert!(matches!(handle.wait().await, Err(PipelineError::ModelUnavailable)));
+        assert!(matches!(
+            pool.transcribe_async(vec![0.0; 16000], TranscriptionOptions::default()).await,
+            Err(PipelineError::ShuttingDown)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_chunked_transcription_propagates_chunk_error() {
+        let pool = pool_without_models(2);
+        let group_id = Uuid::new_v4();
+        let samples = vec![0.1; 100 * WHISPER_SAMPLE_RATE as usize];
+
+        let outcome = pool.transcribe_chunked(group_id, samples, TranscriptionOptions::default(), None).await;
+        assert!(matches!(outcome, Err(PipelineError::ModelUnavailable)));
+        // 整組結束後不再追蹤
+        assert_eq!(pool.get_task_status(group_id), None);
+        assert!(pool.chunk_groups.read().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_handle_receives_worker_error() {
+        let (handle, sender) = handle_with_sender();
+        sender.send(Err(PipelineError::TranscriptionFailed("Whisper 轉錄失敗".to_string()))).unwrap();
+
+        let error = handle.wait().await.unwrap_err();
+        assert_eq!(error.code(), "transcription_failed");
+        assert!(error.to_string().contains("Whisper 轉錄失敗"));
+    }
+
+    fn channel_result(segments: &[(f32, f32, &str)], confidence: Option<f32>) -> TranscriptionResult {
+        TranscriptionResult::fixture(segments.iter().map(|&(start_time, end_time, text)| TranscriptSegment {
+            confidence,
+            ..TranscriptSegment::fixture(start_time, end_time, text)
+        }).collect())
+    }
+
+
+    #[test]
+    fn test_merge_channel_results_interleaves_speakers() {
+        let merged = merge_channel_results(vec![
+            (1, "client".to_string(), channel_result(&[(1.0, 2.0, " 我頭有點暈"), (4.0, 5.0, " 好")], Some(0.6))),
+            (0, "caregiver".to_string(), channel_result(&[(0.0, 1.0, " 您好"), (2.5, 3.5, " 先坐下休息")], Some(0.8))),
+        ]);
+
+        let order: Vec<(Option<usize>, &str)> = merged.segments.iter()
+            .map(|segment| (segment.channel, segment.text.trim()))
+            .collect();
+        assert_eq!(order, vec![(Some(0), "您好"), (Some(1), "我頭有點暈"), (Some(0), "先坐下休息"), (Some(1), "好")]);
+        assert_eq!(merged.segments[1].speaker.as_deref(), Some("client"));
+        assert_eq!(merged.transcript, "caregiver: 您好\nclient: 我頭有點暈\ncaregiver: 先坐下休息\nclient: 好");
+        assert!((merged.confidence.unwrap() - 0.7).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_handle_errors_when_task_dropped() {
+        let (handle, sender) = handle_with_sender();
+        drop(sender);
+
+        assert!(matches!(handle.wait().await, Err(PipelineError::Cancelled)));
+    }
+}
@@ -0,0 +1,184 @@
+// ===================================
+// 逐字時間戳
+// 將帶時間的 token 組合為詞：拉丁文字依空白斷詞，
+// 中日韓文字每個字元各為一詞 (時間依字數平均分配)，標點附在前一詞
+// ===================================
+
+use serde::Serialize;
+
+use crate::confidence::TokenConfidence;
+
+/// 單詞 (或 CJK 單字) 的時間與信心
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WordTiming {
+    pub word: String,
+    /// 開始時間 (秒)
+    pub start: f32,
+    /// 結束時間 (秒)
+    pub end: f32,
+    /// 組成此詞的 token 中最低的機率
+    pub probability: f32,
+}
+
+/// 中日韓文字 (漢字、假名、諺文)
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'   // 平假名、片假名
+        | '\u{3400}'..='\u{4DBF}' // CJK 擴充 A
+        | '\u{4E00}'..='\u{9FFF}' // CJK 統一漢字
+        | '\u{AC00}'..='\u{D7AF}' // 諺文音節
+        | '\u{F900}'..='\u{FAFF}' // CJK 相容漢字
+        | '\u{20000}'..='\u{2FA1F}' // CJK 擴充 B 以後
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UnitKind {
+    Cjk,
+    Text,
+    Punctuation,
+}
+
+/// token 內切出的最小單位
+struct Unit {
+    text: String,
+    kind: UnitKind,
+    space_before: bool,
+    start: f32,
+    end: f32,
+    probability: f32,
+}
+
+/// 將 token 切成 CJK 單字與連續的非 CJK 片段，時間依字元數比例分配
+fn split_token(token: &TokenConfidence, units: &mut Vec<Unit>) {
+    let space_before = token.text.starts_with(char::is_whitespace);
+    let text = token.text.trim();
+    let total_chars = text.chars().count().max(1) as f32;
+    let duration = (token.end - token.start).max(0.0);
+
+    let mut runs: Vec<(String, bool)> = Vec::new();
+    for c in text.chars() {
+        match runs.last_mut() {
+            Some((run, false)) if !is_cjk(c) => run.push(c),
+            _ => runs.push((c.to_string(), is_cjk(c))),
+        }
+    }
+
+    let mut consumed = 0usize;
+    for (index, (run, cjk)) in runs.into_iter().enumerate() {
+        let chars = run.chars().count();
+        let kind = if cjk {
+            UnitKind::Cjk
+        } else if run.chars().any(char::is_alphanumeric) {
+            UnitKind::Text
+        } else {
+            UnitKind::Punctuation
+        };
+        units.push(Unit {
+            kind,
+            space_before: index == 0 && space_before,
+            start: token.start + duration * consumed as f32 / total_chars,
+            end: token.start + duration * (consumed + chars) as f32 / total_chars,
+            probability: token.probability,
+            text: run,
+        });
+        consumed += chars;
+    }
+}
+
+/// 將段落的 token 組合為詞
+pub fn group_words(tokens: &[TokenConfidence]) -> Vec<WordTiming> {
+    let mut units = Vec::new();
+    for token in tokens {
+        split_token(token, &mut units);
+    }
+
+    let mut words: Vec<WordTiming> = Vec::new();
+    let mut last_kind = None;
+    // 段首的標點 (例如開引號) 併入第一個詞
+    let mut leading_punctuation = String::new();
+    for unit in units {
+        let continues_word = match (unit.kind, last_kind) {
+            // 標點附在前一詞，不延長其時間
+            (UnitKind::Punctuation, Some(_)) => {
+                if let Some(word) = words.last_mut() {
+                    word.word.push_str(&unit.text);
+                }
+                continue;
+            },
+            (UnitKind::Punctuation, None) => {
+                leading_punctuation.push_str(&unit.text);
+                continue;
+            },
+            (UnitKind::Text, Some(UnitKind::Text)) => !unit.space_before,
+            _ => false,
+        };
+        last_kind = Some(unit.kind);
+
+        match words.last_mut() {
+            Some(word) if continues_word => {
+                word.word.push_str(&unit.text);
+                word.end = unit.end.max(word.start);
+                word.probability = word.probability.min(unit.probability);
+            },
+            _ => words.push(WordTiming {
+                word: std::mem::take(&mut leading_punctuation) + &unit.text,
+                start: unit.start,
+                end: unit.end,
+                probability: unit.probability,
+            }),
+        }
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(text: &str, start: f32, end: f32, probability: f32) -> TokenConfidence {
+        TokenConfidence { text: text.to_string(), probability, start, end }
+    }
+
+    fn texts(words: &[WordTiming]) -> Vec<&str> {
+        words.iter().map(|word| word.word.as_str()).collect()
+    }
+
+    #[test]
+    fn test_latin_subwords_join_until_whitespace() {
+        let words = group_words(&[
+            token(" Take", 0.0, 0.3, 0.9),
+            token(" the", 0.3, 0.4, 0.95),
+            token(" medic", 0.4, 0.7, 0.8),
+            token("ation", 0.7, 1.0, 0.6),
+            token(".", 1.0, 1.1, 0.99),
+        ]);
+
+        assert_eq!(texts(&words), vec!["Take", "the", "medication."]);
+        assert_eq!((words[2].start, words[2].end), (0.4, 1.0));
+        assert!((words[2].probability - 0.6).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_cjk_characters_are_separate_words() {
+        let words = group_words(&[
+            token(" 記得", 1.0, 1.4, 0.9),
+            token("吃藥", 1.4, 1.8, 0.5),
+            token("，", 1.8, 1.9, 0.99),
+            token("OK", 1.9, 2.2, 0.7),
+        ]);
+
+        assert_eq!(texts(&words), vec!["記", "得", "吃", "藥，", "OK"]);
+        assert!((words[1].start - 1.2).abs() < 1e-6);
+        assert!((words[3].end - 1.8).abs() < 1e-6);
+        assert!((words[3].probability - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_leading_punctuation_and_whitespace_tokens() {
+        let words = group_words(&[token("「", 0.0, 0.1, 0.9), token(" ", 0.1, 0.2, 0.9), token("好", 0.2, 0.4, 0.8)]);
+        assert_eq!(texts(&words), vec!["「好"]);
+        assert_eq!(words[0].start, 0.2);
+        assert!(group_words(&[]).is_empty());
+    }
+}